@@ -25,6 +25,12 @@ impl Audio {
     pub fn stop(&self, id: SoundID) {
         self.sender.send(AudioMessage::Stop(id)).unwrap()
     }
+    pub fn pause_all(&self) {
+        self.sender.send(AudioMessage::PauseAll).unwrap()
+    }
+    pub fn resume_all(&self) {
+        self.sender.send(AudioMessage::ResumeAll).unwrap()
+    }
     pub fn exit_hook(&self) {
         self.sender
             .send(AudioMessage::Exit)
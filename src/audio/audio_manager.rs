@@ -49,6 +49,8 @@ pub struct AudioManager {
     /// End Of Work channel. A thread can signal that it ended playback
     /// and should be cleaned up from the AudioManager sound hashmap.
     eow: (Sender<SoundID>, Receiver<SoundID>),
+    /// Whether the game is paused. Sounds started meanwhile wait for it to resume.
+    paused: bool,
 }
 
 impl AudioManager {
@@ -59,6 +61,7 @@ impl AudioManager {
             receiver,
             active_sounds: HashMap::new(),
             eow,
+            paused: false,
         };
         manager.listen();
     }
@@ -69,6 +72,7 @@ impl AudioManager {
         sound: Sound,
         eow_sender: Sender<SoundID>,
         repeat: bool,
+        paused: bool,
     ) {
         info!("Spawning audio thread with id: {id}");
         let (_stream, stream_handle) = OutputStream::try_default().unwrap();
@@ -78,7 +82,11 @@ impl AudioManager {
         } else {
             sink.append(sound.source);
         }
-        sink.play();
+        if paused {
+            sink.pause();
+        } else {
+            sink.play();
+        }
         loop {
             let message = match receiver.try_recv() {
                 Ok(m) => m,
@@ -101,6 +109,8 @@ impl AudioManager {
                 InternalMessage::Stop => {
                     sink.stop();
                 }
+                InternalMessage::Pause => sink.pause(),
+                InternalMessage::Resume => sink.play(),
                 InternalMessage::Exit => {
                     info!("Killing player thread!");
                     break;
@@ -147,11 +157,12 @@ impl AudioManager {
                 let (sender, receiver) = mpsc::channel::<InternalMessage>();
                 // Sender that enables the thread to signal that its work has finished and it should get cleaned up
                 let eow_sender = self.eow.0.clone();
+                let paused = self.paused;
                 // Save the sound info to a hashmap, allowing later communication
                 self.active_sounds.insert(id, sender);
                 rayon::spawn(move || {
                     let sound = Sound::new(path);
-                    AudioManager::player_thread(id, receiver, sound, eow_sender, repeat)
+                    AudioManager::player_thread(id, receiver, sound, eow_sender, repeat, paused)
                 });
                 false
             }
@@ -164,6 +175,24 @@ impl AudioManager {
                 info!("Stopping ID {id}");
                 false
             }
+            AudioMessage::PauseAll | AudioMessage::ResumeAll => {
+                let pause = matches!(msg, AudioMessage::PauseAll);
+                if pause == self.paused {
+                    return false;
+                }
+                info!("{} all sounds", if pause { "Pausing" } else { "Resuming" });
+                self.paused = pause;
+                self.active_sounds.values().for_each(|s| {
+                    let message = if pause {
+                        InternalMessage::Pause
+                    } else {
+                        InternalMessage::Resume
+                    };
+                    // The sound might have just finished and not been cleaned up yet
+                    let _ = s.send(message);
+                });
+                false
+            }
             AudioMessage::Exit => {
                 info!("Starting audio cleanup");
                 self.active_sounds.values().for_each(|s| {
@@ -3,10 +3,16 @@ use super::sound::SoundID;
 pub enum AudioMessage {
     Play(SoundID, &'static str, bool),
     Stop(SoundID),
+    /// Hold every sound where it is, including the ones started later
+    PauseAll,
+    /// Continue every held sound
+    ResumeAll,
     Exit,
 }
 
 pub enum InternalMessage {
     Stop,
+    Pause,
+    Resume,
     Exit,
 }
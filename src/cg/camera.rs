@@ -1,4 +1,5 @@
 use crate::game::flight::steerable::Steerable;
+use crate::game::rebase::Rebase;
 use crate::gen_ref_getters;
 use crate::SCR_HEIGHT;
 use crate::SCR_WIDTH;
@@ -7,16 +8,13 @@ use cgmath::perspective;
 use cgmath::prelude::*;
 use cgmath::vec3;
 use cgmath::Deg;
-use cgmath::Matrix3;
-use cgmath::Point2;
 use cgmath::Quaternion;
-use num::ToPrimitive;
 
 type Point3 = cgmath::Point3<f32>;
 type Vector3 = cgmath::Vector3<f32>;
 type Matrix4 = cgmath::Matrix4<f32>;
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(PartialEq, Clone, Copy, Debug)]
 pub enum Movement {
     PitchUp,
     PitchDown,
@@ -34,7 +32,6 @@ pub enum ControlSurfaces {
     Roll = 2,
 }
 
-const SENSITIVTY: f32 = 0.1;
 const ZOOM: f32 = 45.0;
 
 #[derive(Debug, Clone)]
@@ -44,7 +41,6 @@ pub struct Camera {
     pub up: Vector3,
     pub right: Vector3,
     projection_matrix: Matrix4,
-    mouse_sensitivity: f32,
     zoom: f32,
 }
 
@@ -61,7 +57,6 @@ impl Default for Camera {
                 0.1,
                 30000.0,
             ),
-            mouse_sensitivity: SENSITIVTY,
             zoom: ZOOM,
         }
     }
@@ -94,41 +89,21 @@ impl Steerable for Camera {
     }
 }
 
+impl Rebase for Camera {
+    fn rebase(&mut self, shift: Vector3) {
+        self.position += shift;
+    }
+}
+
 impl Camera {
     pub fn view_matrix(&self) -> Matrix4 {
         Matrix4::look_at(self.position, self.position + self.front, self.up)
     }
 
-    pub fn orientation_quat(&self) -> Quaternion<f32> {
-        let m = self.view_matrix().invert().expect("Invertible view matrix");
-        let rot = Matrix3::from([
-            [m.x.x, m.x.y, m.x.z],
-            [m.y.x, m.y.y, m.y.z],
-            [m.z.x, m.z.y, m.z.z],
-        ]);
-        Quaternion::from(rot)
-    }
-
-    pub fn process_mouse_movement(&mut self, mut xoffset: f32, mut yoffset: f32) {
-        xoffset *= self.mouse_sensitivity;
-        yoffset *= self.mouse_sensitivity;
-        self.yaw(-xoffset);
-        self.pitch(yoffset);
-    }
-
     pub fn process_mouse_scroll(&mut self, yoffset: f32) {
         if self.zoom >= 1.0 && self.zoom <= 45.0 {
             self.zoom -= yoffset;
         }
         self.zoom = self.zoom.clamp(1.0, 45.0);
     }
-
-    pub fn altitude(&self) -> f32 {
-        self.position().y
-    }
-
-    pub fn xz_ints(&self) -> Point2<i32> {
-        let p = self.position().map(|s| s.to_i32().unwrap());
-        (p.x, p.z).into()
-    }
 }
@@ -0,0 +1,40 @@
+use std::{mem::size_of_val, os::raw::c_void};
+
+/// Triangle indices uploaded to the GPU on their own.
+/// Lets the vertices of a model be drawn with a different set of triangles than its own.
+#[derive(Debug)]
+pub struct IndexBuffer {
+    id: u32,
+    count: i32,
+}
+
+impl IndexBuffer {
+    pub unsafe fn new(indices: &[u32]) -> Self {
+        let mut id = 0;
+        gl::GenBuffers(1, &mut id);
+        // Bound as an array buffer, so that the element array of whatever VAO is bound stays intact
+        gl::BindBuffer(gl::ARRAY_BUFFER, id);
+        let size = size_of_val(indices) as isize;
+        let data = indices.as_ptr() as *const c_void;
+        gl::BufferData(gl::ARRAY_BUFFER, size, data, gl::STATIC_DRAW);
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        IndexBuffer {
+            id,
+            count: indices.len() as i32,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn count(&self) -> i32 {
+        self.count
+    }
+}
+
+impl Drop for IndexBuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteBuffers(1, &self.id) }
+    }
+}
@@ -1,5 +1,6 @@
 pub mod camera;
 pub mod consts;
+pub mod index_buffer;
 pub mod light;
 pub mod model;
 pub mod particles;
@@ -1,5 +1,6 @@
 use super::{
     consts::{VEC_FRONT, VEC_RIGHT, VEC_UP},
+    index_buffer::IndexBuffer,
     texture::Texture,
     transformation::Transformation,
     vertex::Vertex,
@@ -7,13 +8,12 @@ use super::{
 use crate::{
     c_str,
     cg::shader::Shader,
-    game::{drawable::Drawable, flight::steerable::Steerable, terrain::Bounds},
+    game::{drawable::Drawable, flight::steerable::Steerable, rebase::Rebase},
     //offset_of,
 };
 use cgmath::{prelude::*, vec2, vec3, Deg, Quaternion};
 use gl;
 use image::{DynamicImage::*, GenericImage};
-use itertools::{Itertools, MinMaxResult};
 use log::error;
 use memoffset::offset_of;
 use std::{
@@ -22,14 +22,26 @@ use std::{
     os::raw::c_void,
     path::Path,
     ptr,
+    sync::atomic::{AtomicBool, Ordering},
 };
 use tobj;
-use vek::Vec3;
 
 type Point3 = cgmath::Point3<f32>;
 type Vector3 = cgmath::Vector3<f32>;
 type Matrix4 = cgmath::Matrix4<f32>;
 
+/// When set, models only load their geometry and never touch OpenGL.
+/// Used by the headless simulation, which runs without a GL context.
+static HEADLESS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_headless(headless: bool) {
+    HEADLESS.store(headless, Ordering::Relaxed);
+}
+
+pub fn is_headless() -> bool {
+    HEADLESS.load(Ordering::Relaxed)
+}
+
 #[derive(Clone, Debug)]
 pub struct Model {
     pub vertices: Vec<Vertex>,
@@ -84,9 +96,61 @@ impl Steerable for Model {
     }
 }
 
+impl Rebase for Model {
+    fn rebase(&mut self, shift: Vector3) {
+        self.transformation.translation += shift;
+    }
+}
+
 impl Drawable for Model {
     unsafe fn draw(&self, shader: &Shader) {
-        if self.directory.is_empty() {
+        self.bind(shader);
+        gl::DrawElements(
+            gl::TRIANGLES,
+            self.indices.len() as i32,
+            gl::UNSIGNED_INT,
+            ptr::null(),
+        );
+    }
+}
+
+impl Model {
+    /// Load a ready to draw model from an `.obj` file.
+    /// In headless mode only the geometry is loaded.
+    pub fn new(path: &str) -> Model {
+        let mut model = Model::default();
+        model.load_model(path);
+        if !is_headless() {
+            unsafe { model.setup_mesh() }
+        }
+        model
+    }
+
+    /// Build a ready to draw model out of generated geometry.
+    /// The `palette` becomes a one pixel high texture the texture coordinates pick colours from.
+    /// In headless mode only the geometry is kept.
+    pub fn from_mesh(vertices: Vec<Vertex>, indices: Vec<u32>, palette: &[[u8; 3]]) -> Model {
+        let mut model = Model {
+            vertices,
+            indices,
+            ..Model::default()
+        };
+        if !is_headless() {
+            unsafe {
+                model.texture = Texture {
+                    id: texture_from_palette(palette),
+                    type_: "texture_diffuse".into(),
+                    path: "palette".into(),
+                };
+                model.setup_mesh();
+            }
+        }
+        model
+    }
+
+    /// Set up the shader and OpenGL state to draw the model
+    unsafe fn bind(&self, shader: &Shader) {
+        if self.vertices.is_empty() {
             error!(
                 "Attempt to draw a model that was not loaded. Use the `load_model` method first."
             );
@@ -110,24 +174,30 @@ impl Drawable for Model {
         // bind the texture
         gl::BindTexture(gl::TEXTURE_2D, texture.id);
 
-        // draw mesh
         gl::BindVertexArray(self.vao);
+    }
+
+    /// Draw the model's vertices with a different set of triangles than its own
+    pub unsafe fn draw_with(&self, shader: &Shader, indices: &IndexBuffer) {
+        self.bind(shader);
+        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, indices.id());
         gl::DrawElements(
             gl::TRIANGLES,
-            self.indices.len() as i32,
+            indices.count(),
             gl::UNSIGNED_INT,
             ptr::null(),
         );
+        // The element array is part of the VAO's state, give it its own back
+        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
     }
-}
 
-impl Model {
-    /// Load a ready to draw model from an `.obj` file
-    pub fn new(path: &str) -> Model {
-        let mut model = Model::default();
-        model.load_model(path);
-        unsafe { model.setup_mesh() }
-        model
+    /// Free the model's buffers on the GPU. It can't be drawn afterwards.
+    pub unsafe fn delete_buffers(&mut self) {
+        gl::DeleteVertexArrays(1, &self.vao);
+        gl::DeleteBuffers(1, &self.vbo);
+        gl::DeleteBuffers(1, &self.ebo);
+        gl::DeleteTextures(1, &self.texture.id);
+        self.vao = u32::MAX;
     }
 
     /// Construct a model matrix based on model's orientation, scale and translation
@@ -150,12 +220,6 @@ impl Model {
         Point3::from_vec(vec3(m.w.x, m.w.y, m.w.z))
     }
 
-    /// Get the model's position in world coordinates for use with `vek` and bezier curve calculations in missile.rs
-    pub fn position_vek(&self) -> Vec3<f32> {
-        let m = self.build_model_matrix();
-        Vec3::from([m.w.x, m.w.y, m.w.z])
-    }
-
     pub fn front(&self) -> Vector3 {
         self.orientation.rotate_vector(*VEC_FRONT).normalize()
     }
@@ -193,20 +257,6 @@ impl Model {
         self.orientation = quaternion;
     }
 
-    pub fn bounds(&self) -> Bounds {
-        let positions = self.vertices.iter().map(|v| v.position);
-        let x = positions.clone().minmax_by_key(|p| p.x);
-        let z = positions.clone().minmax_by_key(|p| p.z);
-        let b = match (x, z) {
-            (MinMaxResult::MinMax(min_x, max_x), MinMaxResult::MinMax(min_z, max_z)) => (
-                min_x.x as i32..max_x.x as i32,
-                min_z.z as i32..max_z.z as i32,
-            ),
-            _ => unreachable!(),
-        };
-        Bounds { x: b.0, z: b.1 }
-    }
-
     /// Load a model from file and store the resulting meshes in the meshes vector.
     pub fn load_model(&mut self, path: &str) {
         let path = Path::new(&path);
@@ -242,12 +292,15 @@ impl Model {
             })
         }
 
-        let material_id = mesh.material_id.unwrap();
-        let material = &materials[material_id];
-        let texture = self.load_material_texture(&material.diffuse_texture, "texture_diffuse");
         self.vertices = vertices;
         self.indices = indices;
-        self.texture = texture;
+        if is_headless() {
+            return;
+        }
+
+        let material_id = mesh.material_id.unwrap();
+        let material = &materials[material_id];
+        self.texture = self.load_material_texture(&material.diffuse_texture, "texture_diffuse");
     }
 
     fn load_material_texture(&mut self, path: &str, type_name: &str) -> Texture {
@@ -378,3 +431,32 @@ unsafe fn texture_from_file(path: &str, directory: &str) -> u32 {
 
     texture_id
 }
+
+unsafe fn texture_from_palette(palette: &[[u8; 3]]) -> u32 {
+    let mut texture_id = 0;
+    gl::GenTextures(1, &mut texture_id);
+    let data = palette.concat();
+
+    gl::BindTexture(gl::TEXTURE_2D, texture_id);
+    // Rows of RGB pixels aren't aligned to 4 bytes
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+    gl::TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        gl::RGB as i32,
+        palette.len() as i32,
+        1,
+        0,
+        gl::RGB,
+        gl::UNSIGNED_BYTE,
+        &data[0] as *const u8 as *const c_void,
+    );
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+    texture_id
+}
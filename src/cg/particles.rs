@@ -1,8 +1,9 @@
 use cgmath::{EuclideanSpace, InnerSpace, Point3, Vector3, Vector4, Zero};
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
+use crate::game::drawable::Drawable;
 use crate::game::modeled::Modeled;
-use crate::{game::drawable::Drawable, DELTA_TIME};
+use crate::game::rebase::Rebase;
 
 use super::model::Model;
 
@@ -50,6 +51,12 @@ impl Modeled for ParticleGenerator {
     }
 }
 
+impl Rebase for ParticleGenerator {
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        self.particles.iter_mut().for_each(|p| p.position += shift);
+    }
+}
+
 impl ParticleGenerator {
     pub fn new(size: usize, color: Vector4<f32>, offset: f32) -> Self {
         let mut model = Model::new("resources/objects/particle/particle.obj");
@@ -87,34 +94,55 @@ impl ParticleGenerator {
         position: Point3<f32>,
         first_dead: usize,
         front: Vector3<f32>,
+        rng: &mut impl Rng,
     ) {
         let first_dead = &mut self.particles[first_dead];
-        let rand1 = thread_rng().gen_range(-0.1, 0.1);
-        let rand2 = thread_rng().gen_range(-0.1, 0.1);
-        let rand3 = thread_rng().gen_range(-0.1, 0.1);
+        let rand1 = rng.gen_range(-0.1, 0.1);
+        let rand2 = rng.gen_range(-0.1, 0.1);
+        let rand3 = rng.gen_range(-0.1, 0.1);
         let random = Vector3::new(rand1, rand2, rand3);
         let offset = front * -1. * self.offset;
         first_dead.position = position + random + offset;
         first_dead.color = self.color;
-        first_dead.life = thread_rng().gen_range(3., 5.);
+        first_dead.life = rng.gen_range(3., 5.);
         first_dead.velocity = (position.to_vec()).normalize();
     }
 
+    /// Revive `count` particles at once, flying away from `position`
+    /// in random directions at up to `speed`
+    pub fn burst(&mut self, position: Point3<f32>, count: usize, speed: f32, rng: &mut impl Rng) {
+        for _ in 0..count {
+            let idx = self.first_dead_particle();
+            let direction = Vector3::new(
+                rng.gen_range(-1., 1.),
+                rng.gen_range(-1., 1.),
+                rng.gen_range(-1., 1.),
+            );
+            let particle = &mut self.particles[idx];
+            particle.position = position;
+            particle.color = self.color;
+            particle.life = rng.gen_range(1., 3.);
+            // `update_particles` moves particles against their velocity
+            particle.velocity = direction * -speed;
+        }
+    }
+
     pub fn update_particles(
         &mut self,
         position: Point3<f32>,
         number_new_particles: usize,
         front: Vector3<f32>,
+        delta_time: f32,
+        rng: &mut impl Rng,
     ) {
         if !self.enabled {
             return;
         }
         for _ in 0..number_new_particles {
             let first_dead = self.first_dead_particle();
-            self.respawn_particle(position, first_dead, front);
+            self.respawn_particle(position, first_dead, front, rng);
         }
         self.particles.iter_mut().for_each(|p| {
-            let delta_time = unsafe { DELTA_TIME };
             p.life -= delta_time;
             if p.life > 0. {
                 p.position -= p.velocity * delta_time;
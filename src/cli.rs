@@ -0,0 +1,110 @@
+use crate::game::{
+    bindings::DEFAULT_BINDINGS_FILE, clock::GameClock, flight::aircraft::AircraftKind,
+    rng::GameRng, terrain_gen::Biome, world::DEFAULT_AIRCRAFT,
+};
+use log::warn;
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+/// Number of frames simulated by a headless run unless `--frames` says otherwise
+const DEFAULT_HEADLESS_FRAMES: u64 = 3600;
+/// Length of a single step in a headless run unless `--timestep` says otherwise
+pub const HEADLESS_TIMESTEP: f32 = 1. / 60.;
+
+/// Options passed to the game on the command line
+#[derive(Debug)]
+pub struct CliOptions {
+    /// Run the simulation without a window, OpenGL or audio
+    pub headless: bool,
+    /// Number of frames a headless run simulates
+    pub frames: u64,
+    /// Length of a fixed simulation step. Variable timestep when not given.
+    pub timestep: Option<f32>,
+    /// Speed of game time relative to real time
+    pub time_scale: f32,
+    /// Seed of the game's RNG. Random when not given.
+    pub seed: Option<u64>,
+    /// File to save a replay of the session to
+    pub record: Option<PathBuf>,
+    /// Replay file to play back instead of reading input
+    pub replay: Option<PathBuf>,
+    /// Aircraft kind flown by the player
+    pub aircraft: AircraftKind,
+    /// Biome of the terrain. Random when not given.
+    pub biome: Option<Biome>,
+    /// File the key and mouse bindings are read from
+    pub bindings: PathBuf,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        Self {
+            headless: false,
+            frames: DEFAULT_HEADLESS_FRAMES,
+            timestep: None,
+            time_scale: 1.,
+            seed: None,
+            record: None,
+            replay: None,
+            aircraft: AircraftKind::new(DEFAULT_AIRCRAFT),
+            biome: None,
+            bindings: PathBuf::from(DEFAULT_BINDINGS_FILE),
+        }
+    }
+}
+
+impl CliOptions {
+    pub fn from_args() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--frames" => options.frames = value_of(&arg, args.next()),
+                "--timestep" => options.timestep = Some(value_of(&arg, args.next())),
+                "--time-scale" => options.time_scale = value_of(&arg, args.next()),
+                "--seed" => options.seed = Some(value_of(&arg, args.next())),
+                "--record" => options.record = Some(value_of(&arg, args.next())),
+                "--replay" => options.replay = Some(value_of(&arg, args.next())),
+                "--aircraft" => {
+                    options.aircraft = AircraftKind::new(value_of::<String>(&arg, args.next()))
+                }
+                "--biome" => options.biome = Some(value_of(&arg, args.next())),
+                "--bindings" => options.bindings = value_of(&arg, args.next()),
+                _ => warn!("Ignoring unknown argument: {arg}"),
+            }
+        }
+        options
+    }
+
+    /// Build the game clock requested by the options.
+    /// Headless runs always use a fixed timestep.
+    pub fn clock(&self) -> GameClock {
+        let timestep = match (self.timestep, self.headless) {
+            (None, true) => Some(HEADLESS_TIMESTEP),
+            (timestep, _) => timestep,
+        };
+        GameClock::new(timestep, self.time_scale)
+    }
+
+    /// Build the game RNG requested by the options
+    pub fn rng(&self) -> GameRng {
+        match self.seed {
+            Some(seed) => GameRng::new(seed),
+            None => GameRng::from_entropy(),
+        }
+    }
+}
+
+fn value_of<T>(arg: &str, value: Option<String>) -> T
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .unwrap_or_else(|| panic!("Missing value for {arg}"))
+        .parse()
+        .unwrap_or_else(|e| panic!("Invalid value for {arg}: {e}"))
+}
@@ -0,0 +1,338 @@
+use super::{
+    input::Action,
+    joystick::JoystickBindings,
+    keys::{key_from_name, key_name},
+    state::{GameState, StateEvent, Transition},
+};
+use glfw::{Key, MouseButton};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// File the bindings are read from unless `--bindings` says otherwise
+pub const DEFAULT_BINDINGS_FILE: &str = "bindings.json";
+
+/// Names of the mouse buttons as written in the bindings file
+const MOUSE_BUTTONS: [(MouseButton, &str); 8] = [
+    (MouseButton::Button1, "MouseLeft"),
+    (MouseButton::Button2, "MouseRight"),
+    (MouseButton::Button3, "MouseMiddle"),
+    (MouseButton::Button4, "Mouse4"),
+    (MouseButton::Button5, "Mouse5"),
+    (MouseButton::Button6, "Mouse6"),
+    (MouseButton::Button7, "Mouse7"),
+    (MouseButton::Button8, "Mouse8"),
+];
+
+/// A key or a mouse button an action can be bound to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Button {
+    Key(Key),
+    Mouse(MouseButton),
+}
+
+impl fmt::Display for Button {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Button::Key(key) => key_name(*key),
+            Button::Mouse(button) => MOUSE_BUTTONS
+                .iter()
+                .find(|(b, _)| b == button)
+                .map_or("Unknown", |(_, name)| name),
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Button {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MOUSE_BUTTONS
+            .iter()
+            .find(|(_, name)| *name == s)
+            .map(|(button, _)| Button::Mouse(*button))
+            .or_else(|| key_from_name(s).map(Button::Key))
+            .ok_or_else(|| s.to_string())
+    }
+}
+
+/// Direction the mouse can move in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseAxis {
+    X,
+    Y,
+    Scroll,
+}
+
+impl fmt::Display for MouseAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MouseAxis::X => "x",
+            MouseAxis::Y => "y",
+            MouseAxis::Scroll => "scroll",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// What a mouse axis can control
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Axis {
+    LookHorizontal,
+    LookVertical,
+    Zoom,
+}
+
+impl fmt::Display for Axis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Axis::LookHorizontal => "look_horizontal",
+            Axis::LookVertical => "look_vertical",
+            Axis::Zoom => "zoom",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Mouse axis controlling an `Axis`
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct AxisBinding {
+    pub axis: MouseAxis,
+    #[serde(default)]
+    pub inverted: bool,
+    #[serde(default = "AxisBinding::default_sensitivity")]
+    pub sensitivity: f32,
+}
+
+impl AxisBinding {
+    pub fn new(axis: MouseAxis) -> Self {
+        Self {
+            axis,
+            inverted: false,
+            sensitivity: Self::default_sensitivity(),
+        }
+    }
+
+    fn default_sensitivity() -> f32 {
+        1.
+    }
+}
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(PathBuf, io::Error),
+    Format(PathBuf, serde_json::Error),
+    UnknownButton(String),
+    /// A button bound to two different actions
+    Conflict(Button, Action, Action),
+    /// A button the game reads by itself, regardless of the bindings
+    Reserved(Button),
+    /// A mouse axis controlling two different things
+    AxisConflict(MouseAxis, Axis, Axis),
+    /// A joystick axis driving two different flight controls
+    JoystickConflict(usize, &'static str, &'static str),
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingsError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            BindingsError::Format(path, e) => write!(f, "{}: {e}", path.display()),
+            BindingsError::UnknownButton(name) => write!(f, "unknown key or button `{name}`"),
+            BindingsError::Conflict(button, a, b) => {
+                write!(f, "`{button}` is bound to both `{a}` and `{b}`")
+            }
+            BindingsError::Reserved(button) => {
+                write!(f, "`{button}` is reserved for pausing the game")
+            }
+            BindingsError::AxisConflict(mouse, a, b) => {
+                write!(f, "mouse axis `{mouse}` controls both `{a}` and `{b}`")
+            }
+            BindingsError::JoystickConflict(index, a, b) => {
+                write!(f, "joystick axis {index} drives both {a} and {b}")
+            }
+        }
+    }
+}
+
+/// On-disk representation of the bindings.
+/// Actions and axes left out keep their default bindings,
+/// a joystick section replaces the default joystick bindings as a whole.
+#[derive(Deserialize)]
+struct BindingsFile {
+    #[serde(default)]
+    buttons: BTreeMap<Action, Vec<String>>,
+    #[serde(default)]
+    axes: BTreeMap<Axis, AxisBinding>,
+    joystick: Option<JoystickBindings>,
+}
+
+/// Which keys and mouse buttons trigger which actions, which mouse axes
+/// move the view and which joystick axes fly the aircraft.
+/// A button triggers at most one action, an action can have any number of buttons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bindings {
+    buttons: BTreeMap<Button, Action>,
+    axes: BTreeMap<Axis, AxisBinding>,
+    joystick: JoystickBindings,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let buttons = [
+            (Key::W, Action::PitchDown),
+            (Key::S, Action::PitchUp),
+            (Key::A, Action::RollLeft),
+            (Key::D, Action::RollRight),
+            (Key::E, Action::YawRight),
+            (Key::Q, Action::YawLeft),
+            (Key::LeftShift, Action::ThrottleUp),
+            (Key::LeftControl, Action::ThrottleDown),
+            (Key::M, Action::FireGuns),
+            (Key::Space, Action::LaunchMissile),
+            (Key::K, Action::SwitchTarget),
+            (Key::R, Action::Respawn),
+            (Key::LeftBracket, Action::SlowDown),
+            (Key::RightBracket, Action::SpeedUp),
+            (Key::C, Action::ToggleMouseAim),
+            (Key::V, Action::CycleCamera),
+            (Key::X, Action::CycleWeapon),
+            (Key::L, Action::CycleLoadout),
+            (Key::Z, Action::ReleaseFlares),
+            (Key::B, Action::ReleaseChaff),
+        ]
+        .into_iter()
+        .map(|(key, action)| (Button::Key(key), action))
+        .chain([
+            (Button::Mouse(MouseButton::Button1), Action::FireGuns),
+            (Button::Mouse(MouseButton::Button2), Action::LaunchMissile),
+        ])
+        .collect();
+        let axes = BTreeMap::from([
+            (Axis::LookHorizontal, AxisBinding::new(MouseAxis::X)),
+            (Axis::LookVertical, AxisBinding::new(MouseAxis::Y)),
+            (Axis::Zoom, AxisBinding::new(MouseAxis::Scroll)),
+        ]);
+        Self {
+            buttons,
+            axes,
+            joystick: JoystickBindings::default(),
+        }
+    }
+}
+
+impl Bindings {
+    /// Read the bindings from a file, on top of the default ones.
+    /// Buttons listed for an action replace its default buttons,
+    /// and are taken away from any other action they trigger by default.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BindingsError> {
+        let path = path.as_ref();
+        let text =
+            fs::read_to_string(path).map_err(|e| BindingsError::Io(path.to_path_buf(), e))?;
+        let file: BindingsFile = serde_json::from_str(&text)
+            .map_err(|e| BindingsError::Format(path.to_path_buf(), e))?;
+
+        let mut bindings = Self::default();
+        bindings
+            .buttons
+            .retain(|_, action| !file.buttons.contains_key(action));
+        let defaults = bindings.buttons.clone();
+        for (action, names) in file.buttons {
+            for name in names {
+                let button: Button = name.parse().map_err(BindingsError::UnknownButton)?;
+                if defaults.contains_key(&button) {
+                    bindings.unbind(button);
+                }
+                bindings.bind(button, action)?;
+            }
+        }
+
+        bindings.axes.extend(file.axes);
+        let mut used: BTreeMap<MouseAxis, Axis> = BTreeMap::new();
+        for (axis, binding) in bindings.axes.iter() {
+            if let Some(other) = used.insert(binding.axis, *axis) {
+                return Err(BindingsError::AxisConflict(binding.axis, other, *axis));
+            }
+        }
+
+        if let Some(joystick) = file.joystick {
+            bindings.joystick = joystick;
+        }
+        let mut used: BTreeMap<usize, &'static str> = BTreeMap::new();
+        for (control, axis) in bindings.joystick.bound() {
+            if let Some(other) = used.insert(axis.index, control) {
+                return Err(BindingsError::JoystickConflict(axis.index, other, control));
+            }
+        }
+        Ok(bindings)
+    }
+
+    pub fn joystick(&self) -> &JoystickBindings {
+        &self.joystick
+    }
+
+    /// Make `button` trigger `action`. Fails if it already triggers another one.
+    pub fn bind(&mut self, button: Button, action: Action) -> Result<(), BindingsError> {
+        if let Button::Key(key) = button {
+            // Keys the flight screen handles itself would never reach the aircraft
+            if GameState::Flight.handle(StateEvent::Pressed(key)) != Transition::Ignored {
+                return Err(BindingsError::Reserved(button));
+            }
+        }
+        match self.buttons.get(&button) {
+            Some(&bound) if bound != action => Err(BindingsError::Conflict(button, bound, action)),
+            _ => {
+                self.buttons.insert(button, action);
+                Ok(())
+            }
+        }
+    }
+
+    /// Make `button` trigger nothing. Returns the action it triggered.
+    pub fn unbind(&mut self, button: Button) -> Option<Action> {
+        self.buttons.remove(&button)
+    }
+
+    /// Action triggered by `button`
+    pub fn action(&self, button: Button) -> Option<Action> {
+        self.buttons.get(&button).copied()
+    }
+
+    /// Actions triggered by the buttons `is_down` says are held, each listed once
+    pub fn held(&self, is_down: impl Fn(Button) -> bool) -> Vec<Action> {
+        self.buttons
+            .iter()
+            .filter(|(button, _)| is_down(**button))
+            .map(|(_, action)| *action)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Translate mouse movement into the look offset and zoom it's bound to.
+    /// The y axis goes from bottom to top.
+    pub fn mouse_motion(&self, x: f32, y: f32, scroll: f32) -> ((f32, f32), f32) {
+        let value = |axis: Axis| {
+            self.axes.get(&axis).map_or(0., |binding| {
+                let raw = match binding.axis {
+                    MouseAxis::X => x,
+                    MouseAxis::Y => y,
+                    MouseAxis::Scroll => scroll,
+                };
+                let sign = if binding.inverted { -1. } else { 1. };
+                raw * binding.sensitivity * sign
+            })
+        };
+        (
+            (value(Axis::LookHorizontal), value(Axis::LookVertical)),
+            value(Axis::Zoom),
+        )
+    }
+}
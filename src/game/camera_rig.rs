@@ -0,0 +1,358 @@
+use super::{flight::dynamics::Pose, missile::MissileID, rebase::Rebase, world::World};
+use crate::cg::{
+    camera::Camera,
+    consts::{VEC_FRONT, VEC_RIGHT, VEC_UP},
+};
+use cgmath::{
+    Deg, EuclideanSpace, InnerSpace, Matrix3, Point3, Quaternion, Rotation, Rotation3, Vector3,
+};
+use log::info;
+use std::fmt;
+
+/// Seconds a switch from one view to another takes
+const TRANSITION_TIME: f32 = 0.6;
+/// Position of the chase camera, behind and above the aircraft
+const CHASE_DISTANCE: f32 = 25.;
+const CHASE_HEIGHT: f32 = 6.;
+/// How far ahead of the aircraft the chase camera looks
+const CHASE_LOOK_AHEAD: f32 = 20.;
+/// Distance of the orbiting camera from the aircraft
+const ORBIT_DISTANCE: f32 = 35.;
+/// Degrees the orbiting camera moves per unit of look input
+const ORBIT_SENSITIVITY: f32 = 0.2;
+/// Highest the orbiting camera gets above or below the aircraft, in degrees.
+/// Keeps it from flipping over the aircraft's up direction.
+const MAX_ORBIT_ELEVATION: f32 = 80.;
+/// Where the orbiting camera starts: straight behind and a little above
+const ORBIT_START: (f32, f32) = (0., 15.);
+/// Position of the missile camera, behind and above the missile
+const MISSILE_CAM_DISTANCE: f32 = 8.;
+const MISSILE_CAM_HEIGHT: f32 = 1.5;
+/// Seconds the missile camera keeps watching the impact before going back to the previous view
+const IMPACT_LINGER: f32 = 2.;
+/// Position of the camera watching a crash, relative to the wreck
+const CRASH_CAMERA_DISTANCE: f32 = 60.;
+const CRASH_CAMERA_HEIGHT: f32 = 30.;
+
+/// Ways of looking at the fight
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraMode {
+    /// From the pilot's seat
+    Cockpit,
+    /// From behind the aircraft, turning with it
+    Chase,
+    /// Around the aircraft, moved with the mouse
+    Orbit,
+    /// Behind the newest missile in flight until it hits.
+    /// Chases the player's aircraft while there are no missiles.
+    Missile,
+    /// From the pilot's seat, turned toward the current target.
+    /// Looks ahead while there is none.
+    Padlock,
+}
+
+impl CameraMode {
+    pub const ALL: [CameraMode; 5] = [
+        CameraMode::Cockpit,
+        CameraMode::Chase,
+        CameraMode::Orbit,
+        CameraMode::Missile,
+        CameraMode::Padlock,
+    ];
+
+    /// Mode the camera cycles to after this one
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for CameraMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CameraMode::Cockpit => "cockpit",
+            CameraMode::Chase => "chase",
+            CameraMode::Orbit => "orbit",
+            CameraMode::Missile => "missile",
+            CameraMode::Padlock => "padlock",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Missile followed by the missile camera
+#[derive(Debug, Clone)]
+enum Watch {
+    /// Waiting for a missile to be launched
+    Searching,
+    /// Following a missile, remembering whether it was guided last time it was seen
+    Following(MissileID, bool),
+    /// The missile hit or is gone. The view holds still on the spot for the given seconds.
+    Impact(Camera, f32),
+}
+
+/// Places the camera the player looks through, depending on the chosen `CameraMode`.
+/// Purely a view of the `World`, derived from the poses of what it looks at:
+/// the aircraft is flown the same whatever the camera shows.
+pub struct CameraRig {
+    mode: CameraMode,
+    /// Mode being switched away from, and how far the switch got in range [0, 1]
+    previous: CameraMode,
+    blend: f32,
+    /// Mode to go back to once the followed missile hit
+    return_to: CameraMode,
+    /// Angles around the aircraft in orbit mode in degrees: to the right and up
+    orbit: (f32, f32),
+    watch: Watch,
+    /// Absolute position of the world's origin at the last update, so that positions
+    /// kept by the rig can follow the origin when it moves
+    origin: Point3<f64>,
+    /// Projection and zoom every view is seen with
+    lens: Camera,
+    /// What the player sees
+    view: Camera,
+}
+
+impl Rebase for CameraRig {
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        if let Watch::Impact(camera, _) = &mut self.watch {
+            camera.rebase(shift);
+        }
+        self.view.rebase(shift);
+    }
+}
+
+impl CameraRig {
+    pub fn new(world: &World) -> Self {
+        Self {
+            mode: CameraMode::Cockpit,
+            previous: CameraMode::Cockpit,
+            blend: 1.,
+            return_to: CameraMode::Cockpit,
+            orbit: ORBIT_START,
+            watch: Watch::Searching,
+            origin: world.terrain().absolute(Point3::origin()),
+            lens: Camera::default(),
+            view: seat(&Camera::default(), world.player().pose()),
+        }
+    }
+
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    pub fn view(&self) -> &Camera {
+        &self.view
+    }
+
+    /// Whether the player sits in the cockpit, so that it should be drawn around them
+    pub fn in_cockpit(&self) -> bool {
+        self.mode == CameraMode::Cockpit && self.blend >= 1.
+    }
+
+    /// Switch to the next mode
+    pub fn cycle(&mut self) {
+        self.switch(self.mode.next());
+    }
+
+    /// Start a smooth switch to `mode`
+    pub fn switch(&mut self, mode: CameraMode) {
+        info!("Camera: {mode}");
+        self.previous = self.mode;
+        self.mode = mode;
+        self.blend = 0.;
+        match mode {
+            CameraMode::Orbit => self.orbit = ORBIT_START,
+            CameraMode::Missile => {
+                self.return_to = self.previous;
+                self.watch = Watch::Searching;
+            }
+            _ => {}
+        }
+    }
+
+    /// Move the orbiting camera around the aircraft. The y axis goes from bottom to top.
+    pub fn look(&mut self, (x, y): (f32, f32)) {
+        self.orbit.0 += x * ORBIT_SENSITIVITY;
+        self.orbit.1 =
+            (self.orbit.1 + y * ORBIT_SENSITIVITY).clamp(-MAX_ORBIT_ELEVATION, MAX_ORBIT_ELEVATION);
+    }
+
+    /// Zoom every view in or out
+    pub fn zoom(&mut self, amount: f32) {
+        self.lens.process_mouse_scroll(amount);
+    }
+
+    /// Place the camera for the world's current state. `delta` are the real seconds
+    /// since the last update, transitions take the same time whatever the time scale.
+    pub fn update(&mut self, world: &World, delta: f32) {
+        let origin = world.terrain().absolute(Point3::origin());
+        if origin != self.origin {
+            let shift = self.origin - origin;
+            self.rebase(Vector3::new(shift.x as f32, shift.y as f32, shift.z as f32));
+            self.origin = origin;
+        }
+
+        if self.mode == CameraMode::Missile {
+            self.watch_missile(world, delta);
+        }
+        self.blend = (self.blend + delta / TRANSITION_TIME).min(1.);
+        let target = self.place(self.mode, world);
+        self.view = if self.blend < 1. {
+            // Smoothstep, so that the camera eases out of the old view and into the new one
+            let t = self.blend * self.blend * (3. - 2. * self.blend);
+            interpolate(&self.place(self.previous, world), &target, t)
+        } else {
+            target
+        };
+    }
+
+    /// Keep track of the missile being followed, and go back to the previous view
+    /// once it's done
+    fn watch_missile(&mut self, world: &World, delta: f32) {
+        match &mut self.watch {
+            Watch::Searching => {
+                // Only the player's own missiles are worth following
+                let missile = world
+                    .missiles()
+                    .iter()
+                    .filter(|m| m.launched_by().is_none())
+                    .max_by_key(|m| m.id());
+                if let Some(missile) = missile {
+                    self.watch = Watch::Following(missile.id(), missile.target().is_some());
+                }
+            }
+            Watch::Following(id, guided) => {
+                let missile = world.missiles().iter().find(|m| m.id() == *id);
+                match missile {
+                    Some(missile) if !*guided || missile.target().is_some() => {
+                        *guided = missile.target().is_some();
+                    }
+                    // A guided missile only stops being guided when it hits or loses its target
+                    _ => self.watch = Watch::Impact(self.view.clone(), IMPACT_LINGER),
+                }
+            }
+            Watch::Impact(_, left) => {
+                *left -= delta;
+                if *left <= 0. {
+                    self.switch(self.return_to);
+                }
+            }
+        }
+    }
+
+    /// Where the camera would be in `mode`
+    fn place(&self, mode: CameraMode, world: &World) -> Camera {
+        let pose = world.player().pose();
+        // Whatever the mode, a crash is watched from above and behind the wreck
+        if world.crashed() {
+            return crash(&self.lens, pose);
+        }
+        let player = &seat(&self.lens, pose);
+        match mode {
+            CameraMode::Cockpit => player.clone(),
+            CameraMode::Chase => chase(player),
+            CameraMode::Orbit => {
+                let (around, elevation) = self.orbit;
+                let rotation = Quaternion::from_axis_angle(player.up, Deg(-around))
+                    * Quaternion::from_axis_angle(player.right, Deg(-elevation));
+                let offset = rotation.rotate_vector(-player.front) * ORBIT_DISTANCE;
+                look_at(player, player.position + offset, player.position, player.up)
+            }
+            CameraMode::Missile => match &self.watch {
+                Watch::Following(id, _) => world
+                    .missiles()
+                    .iter()
+                    .find(|m| m.id() == *id)
+                    .map(|missile| {
+                        let front = missile.model.front();
+                        let position = missile.position() - front * MISSILE_CAM_DISTANCE
+                            + *VEC_UP * MISSILE_CAM_HEIGHT;
+                        look_at(player, position, missile.position(), *VEC_UP)
+                    })
+                    .unwrap_or_else(|| self.view.clone()),
+                Watch::Impact(camera, _) => camera.clone(),
+                Watch::Searching => chase(player),
+            },
+            CameraMode::Padlock => world
+                .targeting_data()
+                .as_ref()
+                .and_then(|data| world.enemies().get_by_id(data.target_id))
+                .map(|enemy| look_at(player, player.position, enemy.position(), player.up))
+                .unwrap_or_else(|| player.clone()),
+        }
+    }
+}
+
+/// View from the pilot's seat of an aircraft placed at `pose`
+fn seat(lens: &Camera, pose: &Pose) -> Camera {
+    let mut view = lens.clone();
+    view.position = pose.position;
+    view.front = pose.front();
+    view.up = pose.up();
+    view.right = pose.right();
+    view
+}
+
+/// View from above and behind the wreck of an aircraft which crashed at `pose`, looking down at it
+fn crash(lens: &Camera, pose: &Pose) -> Camera {
+    let front = pose.front();
+    let heading = Vector3::new(front.x, 0., front.z);
+    let heading = if heading.magnitude2() > 0. {
+        heading.normalize()
+    } else {
+        *VEC_FRONT
+    };
+    let position = pose.position - heading * CRASH_CAMERA_DISTANCE + *VEC_UP * CRASH_CAMERA_HEIGHT;
+    look_at(lens, position, pose.position, *VEC_UP)
+}
+
+/// View from behind and above the aircraft seen through `camera`
+fn chase(camera: &Camera) -> Camera {
+    let position = camera.position - camera.front * CHASE_DISTANCE + camera.up * CHASE_HEIGHT;
+    let target = camera.position + camera.front * CHASE_LOOK_AHEAD;
+    look_at(camera, position, target, camera.up)
+}
+
+/// Copy of `camera` moved to `position`, looking at `target` with its top toward `up`
+fn look_at(
+    camera: &Camera,
+    position: Point3<f32>,
+    target: Point3<f32>,
+    up: Vector3<f32>,
+) -> Camera {
+    let front = (target - position).normalize();
+    let front = if front.x.is_finite() {
+        front
+    } else {
+        camera.front
+    };
+    let right = front.cross(up);
+    // Looking straight along `up` leaves no way to tell where the top is, keep the old one
+    let right = if right.magnitude2() > f32::EPSILON {
+        right.normalize()
+    } else {
+        camera.right
+    };
+    let mut view = camera.clone();
+    view.position = position;
+    view.front = front;
+    view.right = right;
+    view.up = right.cross(front).normalize();
+    view
+}
+
+fn orientation(camera: &Camera) -> Quaternion<f32> {
+    Quaternion::from(Matrix3::from_cols(camera.right, camera.up, -camera.front))
+}
+
+/// Camera part way from `from` to `to`, `t` being in range [0, 1]
+fn interpolate(from: &Camera, to: &Camera, t: f32) -> Camera {
+    let rotation = orientation(from).slerp(orientation(to), t).normalize();
+    let mut view = to.clone();
+    view.position = from.position + (to.position - from.position) * t;
+    view.front = rotation.rotate_vector(*VEC_FRONT).normalize();
+    view.up = rotation.rotate_vector(*VEC_UP).normalize();
+    view.right = rotation.rotate_vector(*VEC_RIGHT).normalize();
+    view
+}
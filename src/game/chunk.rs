@@ -0,0 +1,108 @@
+use super::{heightfield::Heightfield, terrain_gen::TerrainGenerator};
+use crate::cg::{
+    index_buffer::IndexBuffer,
+    model::{is_headless, Model},
+    shader::Shader,
+};
+use cgmath::{vec3, InnerSpace, Point3, Vector3};
+
+/// Length of a side of a terrain chunk in meters
+pub const CHUNK_SIZE: f32 = 2000.;
+/// Number of vertices along each side of a chunk
+pub const CHUNK_RESOLUTION: usize = 65;
+
+/// Position of a chunk on the grid of chunks covering the world.
+/// Chunk `(x, z)` spans `x * CHUNK_SIZE..(x + 1) * CHUNK_SIZE` along x and the same along z.
+pub type ChunkCoord = (i32, i32);
+
+/// A square piece of the terrain with its own mesh and heightfield
+pub struct Chunk {
+    model: Model,
+    heightfield: Heightfield,
+    /// Height of the chunk's highest point above its corner
+    top: f32,
+}
+
+impl Chunk {
+    /// Generate the chunk at `coord`. `corner` is where its corner with the smallest x and z
+    /// lies in world space, at the height of the lowest possible ground.
+    pub fn generate(generator: &TerrainGenerator, coord: ChunkCoord, corner: Vector3<f32>) -> Self {
+        let cells = (CHUNK_RESOLUTION - 1) as i64;
+        let first = (coord.0 as i64 * cells, coord.1 as i64 * cells);
+        let step = CHUNK_SIZE as f64 / cells as f64;
+        let (vertices, indices) = generator.mesh(first, CHUNK_RESOLUTION, step);
+        let positions: Vec<Vector3<f32>> = vertices.iter().map(|v| v.position).collect();
+        let heightfield = Heightfield::from_mesh(&positions, &indices);
+        let top = positions
+            .iter()
+            .map(|p| p.y)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let mut model = Model::from_mesh(vertices, indices, generator.palette());
+        model.set_translation(corner);
+        Chunk {
+            model,
+            heightfield,
+            top,
+        }
+    }
+
+    /// Where the chunk's corner lies in world space
+    pub fn corner(&self) -> Vector3<f32> {
+        self.model.transformation.translation
+    }
+
+    pub fn set_corner(&mut self, corner: Vector3<f32>) {
+        self.model.set_translation(corner);
+    }
+
+    /// Height of the ground in world space at the given offset from the chunk's corner
+    pub fn height(&self, x: f32, z: f32) -> f32 {
+        self.heightfield.height(x, z) + self.corner().y
+    }
+
+    /// Unit vector perpendicular to the ground at the given offset from the chunk's corner
+    pub fn normal(&self, x: f32, z: f32) -> Vector3<f32> {
+        self.heightfield.normal(x, z)
+    }
+
+    /// Distance from `eye` to the nearest point of the box the chunk fits in
+    pub fn distance_to(&self, eye: Point3<f32>) -> f32 {
+        let corner = self.corner();
+        let outside = |v: f32, min: f32, max: f32| (min - v).max(v - max).max(0.);
+        vec3(
+            outside(eye.x, corner.x, corner.x + CHUNK_SIZE),
+            outside(eye.y, corner.y, corner.y + self.top),
+            outside(eye.z, corner.z, corner.z + CHUNK_SIZE),
+        )
+        .magnitude()
+    }
+
+    /// Draw the chunk with the given triangles of its grid
+    pub unsafe fn draw(&self, shader: &Shader, indices: &IndexBuffer) {
+        self.model.draw_with(shader, indices);
+    }
+
+    /// Offset from the chunk's corner to the given world space position
+    pub fn to_local(&self, x: f32, z: f32) -> (f32, f32) {
+        let corner = self.corner();
+        (x - corner.x, z - corner.z)
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        if !is_headless() {
+            unsafe { self.model.delete_buffers() }
+        }
+    }
+}
+
+/// Corner of the chunk at `coord` in world space, when the world's origin
+/// sits at the corner of the chunk at `origin`
+pub fn chunk_corner(coord: ChunkCoord, origin: ChunkCoord, base: f32) -> Vector3<f32> {
+    vec3(
+        (coord.0 - origin.0) as f32 * CHUNK_SIZE,
+        base,
+        (coord.1 - origin.1) as f32 * CHUNK_SIZE,
+    )
+}
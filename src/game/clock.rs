@@ -0,0 +1,130 @@
+/// Longest stretch of real time a single frame can account for.
+/// Protects the simulation from huge steps after a hitch (window dragging,
+/// breakpoints, loading) and the fixed timestep from a spiral of death.
+const MAX_FRAME_TIME: f32 = 0.25;
+
+/// Timing of a single simulation step, passed to everything that updates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTime {
+    /// Game seconds elapsed since the previous step
+    pub delta: f32,
+    /// Game seconds elapsed since the clock was started
+    pub time: f64,
+}
+
+impl FrameTime {
+    pub fn new(delta: f32, time: f64) -> Self {
+        Self { delta, time }
+    }
+}
+
+/// Converts real time into simulation steps.
+/// In variable mode every frame is a single step as long as the frame itself.
+/// In fixed mode real time is collected in an accumulator and spent in steps
+/// of constant length, which keeps the simulation deterministic.
+/// Game time can be scaled for slow-motion and fast-forward, or stopped altogether.
+#[derive(Debug, Clone)]
+pub struct GameClock {
+    time: f64,
+    scale: f32,
+    paused: bool,
+    fixed_step: Option<f32>,
+    accumulator: f64,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self::variable()
+    }
+}
+
+impl GameClock {
+    /// A clock producing one step per frame
+    pub fn variable() -> Self {
+        Self {
+            time: 0.,
+            scale: 1.,
+            paused: false,
+            fixed_step: None,
+            accumulator: 0.,
+        }
+    }
+
+    /// A clock producing steps of exactly `step` game seconds
+    pub fn fixed(step: f32) -> Self {
+        assert!(step > 0., "Fixed timestep must be positive");
+        Self {
+            fixed_step: Some(step),
+            ..Self::variable()
+        }
+    }
+
+    /// A fixed or variable clock, depending on whether `fixed_step` is given,
+    /// running at `scale` times the speed of real time
+    pub fn new(fixed_step: Option<f32>, scale: f32) -> Self {
+        let mut clock = match fixed_step {
+            Some(step) => Self::fixed(step),
+            None => Self::variable(),
+        };
+        clock.set_scale(scale);
+        clock
+    }
+
+    /// Account for `real_delta` seconds of real time having passed
+    /// and return the simulation steps that should be run this frame.
+    /// A paused clock never produces steps and doesn't accumulate time,
+    /// so resuming doesn't cause a jump.
+    pub fn tick(&mut self, real_delta: f32) -> Vec<FrameTime> {
+        if self.paused {
+            return vec![];
+        }
+        let delta = real_delta.clamp(0., MAX_FRAME_TIME) * self.scale;
+        match self.fixed_step {
+            None => {
+                self.time += delta as f64;
+                vec![FrameTime::new(delta, self.time)]
+            }
+            Some(step) => {
+                self.accumulator += delta as f64;
+                let mut steps = vec![];
+                while self.accumulator >= step as f64 {
+                    self.accumulator -= step as f64;
+                    self.time += step as f64;
+                    steps.push(FrameTime::new(step, self.time));
+                }
+                steps
+            }
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Set the speed of game time relative to real time.
+    /// Values below 1 give slow-motion, values above 1 fast-forward.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.max(0.);
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn fixed_step(&self) -> Option<f32> {
+        self.fixed_step
+    }
+
+    /// Game seconds elapsed since the clock was started
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+}
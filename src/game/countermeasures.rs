@@ -0,0 +1,272 @@
+use super::{
+    flight::dynamics::GRAVITY, missile_spec::SeekerKind, modeled::Modeled,
+    particle_generation::ParticleGeneration, rebase::Rebase,
+};
+use crate::cg::{model::Model, particles::ParticleGenerator};
+use cgmath::{Point3, Vector3, Vector4};
+use rand::Rng;
+use std::{f32::consts::PI, fmt, ops::Range};
+
+pub type DecoyID = u32;
+
+/// Chance a flare fools a heat seeker at the best aspect and timing
+const FLARE_SEDUCTION: f32 = 0.7;
+/// Chance chaff fools a radar seeker at the best aspect and timing
+const CHAFF_SEDUCTION: f32 = 0.6;
+/// Seconds before impact between which decoys work best.
+/// Released earlier, the seeker has time to tell them from the target as they fall behind.
+/// Released later, the missile is too close to be pulled off.
+const BEST_TIME_TO_GO: Range<f32> = 1.0..4.0;
+/// Seconds a dispenser needs between two decoys
+const DISPENSER_INTERVAL: f64 = 0.25;
+/// Speed in m/s at which decoys are thrown away from the aircraft
+const EJECTION_SPEED: f32 = 20.;
+const DECOY_PARTICLES: usize = 200;
+
+/// Something thrown out of an aircraft to fool the seekers of missiles chasing it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecoyKind {
+    /// Burns hotter than an engine and fools heat seekers
+    Flare,
+    /// A cloud of foil strips which reflects radar like an aircraft does
+    Chaff,
+}
+
+impl DecoyKind {
+    /// The decoy which can fool a seeker of the given kind
+    pub fn against(seeker: &SeekerKind) -> Self {
+        match seeker {
+            SeekerKind::Infrared { .. } => DecoyKind::Flare,
+            SeekerKind::Radar { .. } => DecoyKind::Chaff,
+        }
+    }
+
+    /// Chance in range [0, 1] that a seeker of the given kind takes the decoy for its target.
+    /// `aspect` is the angle in radians between the target's nose and the direction
+    /// from the target to the missile: 0 when the missile comes head on and π when it comes
+    /// from straight behind.
+    /// `time_to_go` is how many seconds the missile needs to reach the target.
+    pub fn seduction(self, seeker: &SeekerKind, aspect: f32, time_to_go: f32) -> f32 {
+        let (chance, aspect) = match (self, seeker) {
+            // A seeker staring up the exhaust sees the engine outshine the flare
+            (DecoyKind::Flare, SeekerKind::Infrared { .. }) => {
+                (FLARE_SEDUCTION, 1. - 0.6 * aspect / PI)
+            }
+            // Chaff stands still in the air. A radar tells it apart from a target
+            // flying towards or away from it, but not from one crossing its beam.
+            (DecoyKind::Chaff, SeekerKind::Radar { .. }) => {
+                (CHAFF_SEDUCTION, 0.2 + 0.8 * aspect.sin())
+            }
+            _ => return 0.,
+        };
+        let timing = if time_to_go < BEST_TIME_TO_GO.start {
+            (time_to_go / BEST_TIME_TO_GO.start).max(0.)
+        } else if time_to_go <= BEST_TIME_TO_GO.end {
+            1.
+        } else {
+            BEST_TIME_TO_GO.end / time_to_go
+        };
+        chance * aspect * timing
+    }
+
+    /// Seconds the decoy keeps burning or reflecting
+    fn lifetime(self) -> f32 {
+        match self {
+            DecoyKind::Flare => 4.,
+            DecoyKind::Chaff => 3.,
+        }
+    }
+
+    /// Fraction of the decoy's speed the air takes away every second
+    fn drag(self) -> f32 {
+        match self {
+            DecoyKind::Flare => 0.5,
+            DecoyKind::Chaff => 3.,
+        }
+    }
+
+    /// Fraction of gravity pulling the decoy down. Chaff drifts down slowly.
+    fn weight(self) -> f32 {
+        match self {
+            DecoyKind::Flare => 1.,
+            DecoyKind::Chaff => 0.05,
+        }
+    }
+
+    fn colour(self) -> Vector4<f32> {
+        match self {
+            DecoyKind::Flare => Vector4::new(1., 0.9, 0.6, 1.),
+            DecoyKind::Chaff => Vector4::new(0.7, 0.7, 0.75, 0.8),
+        }
+    }
+}
+
+impl fmt::Display for DecoyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DecoyKind::Flare => "flare",
+            DecoyKind::Chaff => "chaff",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Launcher of one kind of decoys
+#[derive(Clone, Debug)]
+pub struct Dispenser {
+    left: u32,
+    capacity: u32,
+    last_release_time: f64,
+}
+
+impl Dispenser {
+    pub fn new(capacity: u32) -> Self {
+        Self {
+            left: capacity,
+            capacity,
+            last_release_time: -DISPENSER_INTERVAL,
+        }
+    }
+
+    pub fn left(&self) -> u32 {
+        self.left
+    }
+
+    /// Use up a decoy, unless the dispenser is empty or released one too recently
+    fn release(&mut self, time: f64) -> bool {
+        if self.left == 0 || self.last_release_time + DISPENSER_INTERVAL > time {
+            return false;
+        }
+        self.left -= 1;
+        self.last_release_time = time;
+        true
+    }
+
+    fn reload(&mut self) {
+        self.left = self.capacity;
+    }
+}
+
+/// Flare and chaff dispensers of an aircraft
+#[derive(Clone, Debug)]
+pub struct Countermeasures {
+    flares: Dispenser,
+    chaff: Dispenser,
+}
+
+impl Countermeasures {
+    pub fn new(flares: u32, chaff: u32) -> Self {
+        Self {
+            flares: Dispenser::new(flares),
+            chaff: Dispenser::new(chaff),
+        }
+    }
+
+    pub fn dispenser(&self, kind: DecoyKind) -> &Dispenser {
+        match kind {
+            DecoyKind::Flare => &self.flares,
+            DecoyKind::Chaff => &self.chaff,
+        }
+    }
+
+    /// Use up a decoy of the given kind. Returns whether one was released.
+    pub fn release(&mut self, kind: DecoyKind, time: f64) -> bool {
+        match kind {
+            DecoyKind::Flare => self.flares.release(time),
+            DecoyKind::Chaff => self.chaff.release(time),
+        }
+    }
+
+    pub fn reload(&mut self) {
+        self.flares.reload();
+        self.chaff.reload();
+    }
+}
+
+/// A flare or a chaff cloud flying on its own after leaving the aircraft
+#[derive(Debug)]
+pub struct Decoy {
+    id: DecoyID,
+    kind: DecoyKind,
+    position: Point3<f32>,
+    velocity: Vector3<f32>,
+    age: f32,
+    particle_generator: ParticleGenerator,
+}
+
+impl Decoy {
+    /// Release a decoy at `position` from an aircraft flying at `velocity`.
+    /// It's thrown out along `ejection`, a unit vector.
+    pub fn new(
+        id: DecoyID,
+        kind: DecoyKind,
+        position: Point3<f32>,
+        velocity: Vector3<f32>,
+        ejection: Vector3<f32>,
+    ) -> Self {
+        Self {
+            id,
+            kind,
+            position,
+            velocity: velocity + ejection * EJECTION_SPEED,
+            age: 0.,
+            particle_generator: ParticleGenerator::new(DECOY_PARTICLES, kind.colour(), 0.),
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32, rng: &mut impl Rng) {
+        self.velocity *= (1. - self.kind.drag() * delta_time).max(0.);
+        self.velocity.y -= GRAVITY * self.kind.weight() * delta_time;
+        self.position += self.velocity * delta_time;
+        self.age += delta_time;
+        self.particle_generator.update_particles(
+            self.position,
+            1,
+            Vector3::unit_y(),
+            delta_time,
+            rng,
+        );
+    }
+
+    /// Whether the decoy burned out or dispersed
+    pub fn finished(&self) -> bool {
+        self.age > self.kind.lifetime()
+    }
+
+    pub fn id(&self) -> DecoyID {
+        self.id
+    }
+
+    pub fn kind(&self) -> DecoyKind {
+        self.kind
+    }
+
+    pub fn position(&self) -> Point3<f32> {
+        self.position
+    }
+}
+
+impl Rebase for Decoy {
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        self.position += shift;
+        self.particle_generator.rebase(shift);
+    }
+}
+
+impl Modeled for Decoy {
+    fn model(&self) -> &Model {
+        self.particle_generator.model()
+    }
+    fn model_mut(&mut self) -> &mut Model {
+        self.particle_generator.model_mut()
+    }
+}
+
+impl ParticleGeneration for Decoy {
+    fn particle_generator(&self) -> &ParticleGenerator {
+        &self.particle_generator
+    }
+    fn particle_generator_mut(&mut self) -> &mut ParticleGenerator {
+        &mut self.particle_generator
+    }
+}
@@ -1,71 +1,104 @@
 use super::{
-    enemy::Enemy,
-    flight::steerable::Steerable,
-    game::{ID_GENERATOR, TARGET_ENEMIES},
-    missile::EnemyID,
+    enemy::Enemy, flight::dynamics::Pose, id_gen::IDGenerator, missile::EnemyID, terrain::Terrain,
+    world::TARGET_ENEMIES,
 };
-use crate::game::{flight::aircraft::AircraftKind::*, id_gen::IDKind};
+use crate::game::{
+    flight::blueprint::Blueprints, id_gen::IDKind, missile_catalogue::MissileCatalogue,
+};
+use cgmath::{vec3, InnerSpace, Point2, Point3, Quaternion};
+use itertools::Itertools;
 use log::info;
-use rand::{thread_rng, Rng};
-use std::collections::HashMap;
+use rand::Rng;
+use std::{collections::BTreeMap, f32::consts::PI, ops::Range};
+
+/// Distance in m from the player at which a wave shows up, well out of reach of its guns
+const SPAWN_DISTANCE: Range<f32> = 2500.0..3500.0;
+/// Height in m above the player at which a wave shows up
+const SPAWN_HEIGHT: Range<f32> = 200.0..500.0;
+/// Height in m above the ground below it that a wave shows up at least at
+const SPAWN_GROUND_CLEARANCE: f32 = 300.;
 
+/// All enemies currently alive.
+/// Kept in a `BTreeMap` so that they are always iterated in the same order,
+/// which keeps the simulation deterministic.
 pub struct Enemies {
-    pub map: HashMap<EnemyID, Enemy>,
+    pub map: BTreeMap<EnemyID, Enemy>,
+    id_gen: IDGenerator,
+    /// Number of the current wave, starting at 1
+    wave: u32,
 }
 
-impl Default for Enemies {
-    fn default() -> Self {
+impl Enemies {
+    /// Create the enemies with their first wave around the player at `player`
+    pub fn new(
+        blueprints: &Blueprints,
+        missile_types: &MissileCatalogue,
+        terrain: &Terrain,
+        player: Point3<f32>,
+        rng: &mut impl Rng,
+    ) -> Self {
         let mut e = Self {
-            map: HashMap::with_capacity(TARGET_ENEMIES),
+            map: BTreeMap::new(),
+            id_gen: IDGenerator::default(),
+            wave: 0,
         };
-        e.respawn_enemies();
-        e.randomize_positions();
+        e.respawn_enemies(blueprints, missile_types, terrain, player, rng);
         e
     }
-}
 
-impl Enemies {
-    pub fn respawned_enemies(&mut self) -> Option<HashMap<EnemyID, Enemy>> {
-        let diff = TARGET_ENEMIES - self.map.len();
-        if diff == 0 {
+    /// Start the next wave once every enemy of the current one is shot down.
+    /// The kind of each enemy is drawn separately, so waves mix aircraft types.
+    /// The wave shows up around the player at `player`, keeping its distance
+    /// and clear of the `terrain` below it.
+    /// Returns the number of the new wave if one was started.
+    pub fn respawn_enemies(
+        &mut self,
+        blueprints: &Blueprints,
+        missile_types: &MissileCatalogue,
+        terrain: &Terrain,
+        player: Point3<f32>,
+        rng: &mut impl Rng,
+    ) -> Option<u32> {
+        if !self.map.is_empty() {
             return None;
         }
-        info!("Respawning {diff} enemies");
-        let mut id_gen = ID_GENERATOR.lock().expect("Lock IDGenerator mutex");
-        Some(
-            (0..diff)
-                .map(|_| {
-                    let id = id_gen.get_new_id_of(IDKind::Enemy);
-                    (id, Enemy::new(id, Mig21))
-                })
-                .collect(),
-        )
+        self.wave += 1;
+        let wave: BTreeMap<EnemyID, Enemy> = (0..TARGET_ENEMIES)
+            .map(|_| {
+                let id = self.id_gen.get_new_id_of(IDKind::Enemy);
+                let blueprint = blueprints.random_enemy(rng);
+                (id, Enemy::new(id, blueprint, missile_types, rng))
+            })
+            .collect();
+        info!(
+            "Wave {}: {}",
+            self.wave,
+            wave.values().map(|e| e.aircraft().kind()).join(", ")
+        );
+        self.map.extend(wave);
+        self.spread_around(terrain, player, rng);
+        Some(self.wave)
     }
 
-    pub fn respawn_enemies(&mut self) {
-        if let Some(enemies) = self.respawned_enemies() {
-            self.map.extend(enemies);
-        }
-    }
-
-    pub fn randomize_positions(&mut self) {
-        assert!(!self.map.is_empty());
+    /// Place every enemy at a random bearing from `player`, out of range and
+    /// above it and the ground, heading towards it
+    fn spread_around(&mut self, terrain: &Terrain, player: Point3<f32>, rng: &mut impl Rng) {
         self.map.values_mut().for_each(|e| {
-            // TEMPORARY
-            let a = e.aircraft_mut().model_mut();
-            let amount = thread_rng().gen_range(-360., 360.);
-            a.roll(amount);
-            a.yaw(amount);
-            a.pitch(amount);
-            a.forward(5.);
+            let bearing: f32 = rng.gen_range(0., 2. * PI);
+            let distance = rng.gen_range(SPAWN_DISTANCE.start, SPAWN_DISTANCE.end);
+            let height = rng.gen_range(SPAWN_HEIGHT.start, SPAWN_HEIGHT.end);
+            let mut offset = vec3(bearing.sin() * distance, height, bearing.cos() * distance);
+            let ground = terrain.height_at(Point2::new(player.x + offset.x, player.z + offset.z));
+            offset.y = offset.y.max(ground + SPAWN_GROUND_CLEARANCE - player.y);
+            let heading = vec3(-offset.x, 0., -offset.z).normalize();
+            let front = Pose::default().front();
+            let orientation =
+                Quaternion::from_arc(front, heading, None) * Pose::default().orientation;
+            e.place(Pose::new(player + offset, orientation), rng);
         });
     }
 
     pub fn get_by_id(&self, id: EnemyID) -> Option<&Enemy> {
         self.map.get(&id)
     }
-
-    pub fn get_mut_by_id(&mut self, id: EnemyID) -> Option<&mut Enemy> {
-        self.map.get_mut(&id)
-    }
 }
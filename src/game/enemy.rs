@@ -1,95 +1,197 @@
-use super::{flight::{aircraft::{Aircraft, AircraftKind}, steerable::Steerable}, missile::EnemyID, terrain::Terrain};
-use crate::{cg::consts::VEC_RIGHT, gen_ref_getters, DELTA_TIME};
-use cgmath::{EuclideanSpace, InnerSpace, Point3, Quaternion, Vector3};
-use rand::{thread_rng, Rng};
-use std::ops::Sub;
+use super::{
+    clock::FrameTime,
+    flight::{
+        aircraft::Aircraft,
+        blueprint::{Blueprint, Role},
+        damage::Component,
+        dynamics::{FlightState, Pose},
+        steerable::Steerable,
+    },
+    missile::EnemyID,
+    missile_catalogue::MissileCatalogue,
+    missile_spec::SeekerSpec,
+    rebase::Rebase,
+    terrain::Terrain,
+};
+use crate::{cg::consts::VEC_RIGHT, gen_ref_getters};
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Point3, Quaternion, Vector3};
+use rand::Rng;
+use std::ops::{Range, Sub};
 use vek::{QuadraticBezier3, Vec3};
 
+/// Height in m above the ground of the points enemies head for
+const PATH_HEIGHT: Range<f32> = 40.0..250.0;
+/// Share of the loaded terrain along each axis, around its middle,
+/// which an enemy that strayed off it heads back to
+const RETURN_SHARE: f32 = 0.5;
+/// Degrees off the nose within which enemies open fire with their guns
+const GUN_CONE: f32 = 10.;
+
 /// Struct representing an enemy
 pub struct Enemy {
     id: u32,
     pub aircraft: Aircraft,
+    /// What the aircraft was designed for, which decides how it fights
+    role: Role,
     start_point: Vec3<f32>,
     end_point: Vec3<f32>,
     bezier: QuadraticBezier3<f32>,
     progress: f32,
+    /// Speed along the path in m/s while undamaged
+    cruise_speed: f32,
+    /// Seconds the seeker of a missile the enemy carries has kept the player in view
+    lock_progress: f32,
+    last_launch_time: f64,
+}
+
+impl Rebase for Enemy {
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        self.aircraft.rebase(shift);
+        let shift = cgmath_to_vek(&shift);
+        self.start_point += shift;
+        self.end_point += shift;
+        self.bezier.start += shift;
+        self.bezier.ctrl += shift;
+        self.bezier.end += shift;
+    }
 }
 
 impl Enemy {
-    /// Create a new enemy with the given aircraft kind
-    pub fn new(id: EnemyID, kind: AircraftKind) -> Self {
-        let aircraft = Aircraft::new(kind);
-        let random_mid = thread_rng().gen_range(10., 20.);
-        let random_length = thread_rng().gen_range(30., 40.);
-        let mid = {
-            // Select a point in front of the launching aircraft to simulate the missile accelerating
-            let mid = aircraft.model().position() + aircraft.model().front() * random_mid;
-            Vec3::from([mid.x, mid.y, mid.z])
-        };
-        let start_point = aircraft.model().position_vek();
-        let end_point = cgmath_to_vek(
-            &(aircraft.model().position() + aircraft.model().front() * random_length).to_vec(),
-        ) + Vec3::new(random_length, 0., random_length);
-        let points = Vec3::from([start_point, mid, end_point]);
+    /// Create a new enemy flying an aircraft built from the blueprint,
+    /// armed with the blueprint's first loadout
+    pub fn new(
+        id: EnemyID,
+        blueprint: &Blueprint,
+        missile_types: &MissileCatalogue,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut aircraft = Aircraft::new(blueprint);
+        aircraft.arm(blueprint, &blueprint.loadouts()[0], missile_types);
+        let bezier = path_ahead(aircraft.pose(), rng);
 
         Self {
             id,
             aircraft,
-            start_point,
-            end_point,
-            bezier: QuadraticBezier3::from(points),
+            role: *blueprint.role(),
+            start_point: bezier.start,
+            end_point: bezier.end,
+            bezier,
             progress: 0.,
+            cruise_speed: blueprint.cruise_speed(),
+            lock_progress: 0.,
+            last_launch_time: -blueprint.role().launch_interval(),
         }
     }
+    /// Put the enemy at `pose` and start it over on a short path ahead of it
+    pub fn place(&mut self, pose: Pose, rng: &mut impl Rng) {
+        self.aircraft.place(pose);
+        self.bezier = path_ahead(&pose, rng);
+        self.start_point = self.bezier.start;
+        self.end_point = self.bezier.end;
+        self.progress = 0.;
+    }
     pub fn aircraft_mut(&mut self) -> &mut Aircraft {
         &mut self.aircraft
     }
     pub fn position(&self) -> Point3<f32> {
-        self.aircraft().model().position()
+        self.aircraft().pose().position
     }
     pub fn id(&self) -> EnemyID {
         self.id
     }
-    pub fn fly(&mut self, terrain: &Terrain) {
+    /// Velocity along the path, the aircraft is placed on it instead of flying.
+    /// A wreck falls at its own velocity.
+    pub fn velocity(&self) -> Vector3<f32> {
+        if self.aircraft.destroyed() {
+            self.aircraft.flight().velocity()
+        } else {
+            self.aircraft.pose().front() * self.speed()
+        }
+    }
+    /// Speed along the path, which drops with the thrust a damaged engine loses
+    fn speed(&self) -> f32 {
+        self.cruise_speed * self.aircraft.damage().thrust()
+    }
+    /// Take `amount` of damage from a hit on `component`.
+    /// Returns whether the hit destroyed the aircraft, which starts falling.
+    pub fn hit(&mut self, amount: f32, component: Component) -> bool {
+        self.damage(|aircraft| aircraft.hit(amount, component))
+    }
+    /// Let a holed fuel tank burn. Returns whether the fire destroyed the aircraft.
+    pub fn burn(&mut self, delta_time: f32) -> bool {
+        self.damage(|aircraft| aircraft.burn(delta_time))
+    }
+    /// Damage the aircraft with `damage`, which tells whether it destroyed it.
+    /// The wreck carries on with the velocity it had along the path.
+    fn damage(&mut self, damage: impl FnOnce(&mut Aircraft) -> bool) -> bool {
+        let velocity = self.velocity();
+        let destroyed = damage(&mut self.aircraft);
+        if destroyed {
+            *self.aircraft.flight_mut() = FlightState::new(velocity);
+        }
+        destroyed
+    }
+    /// Keep the guns firing as long as `target` is within the role's range and in front of
+    /// the nose
+    pub fn fire_guns_at(&mut self, target: &Pose, frame: &FrameTime, rng: &mut impl Rng) {
+        let to_target = target.position - self.position();
+        let in_sight = to_target.magnitude() < self.role.gun_range()
+            && to_target.angle(self.aircraft.pose().front()) < Deg(GUN_CONE).into();
+        if in_sight {
+            self.aircraft.fire_guns(frame, rng);
+        } else {
+            self.aircraft.guns_mut().stop_firing();
+        }
+    }
+    /// Count down the lock of a `seeker` which sees the player this frame, or drop it if
+    /// there is none. Returns whether the enemy launches the missile now.
+    pub fn lock_on(&mut self, seeker: Option<&SeekerSpec>, frame: &FrameTime) -> bool {
+        let Some(seeker) = seeker else {
+            self.lock_progress = 0.;
+            return false;
+        };
+        self.lock_progress += frame.delta;
+        let launch = self.lock_progress >= seeker.lock_time
+            && self.last_launch_time + self.role.launch_interval() <= frame.time;
+        if launch {
+            self.last_launch_time = frame.time;
+        }
+        launch
+    }
+    pub fn fly(&mut self, terrain: &Terrain, frame: &FrameTime, rng: &mut impl Rng) {
+        if self.aircraft.destroyed() {
+            self.aircraft.fall(frame.delta);
+            return;
+        }
         // Progress along the curve
 
         // The enemy has arrived at their destination and we should select a new one.
-        if at_destination(self.aircraft().model().position_vek(), self.end_point) {
+        // The path is flown in large steps at cruise speed, so the end of the curve
+        // is what counts, not getting close to the end point
+        if self.progress >= 1. {
             self.progress = 0.;
-            self.start_point = self.aircraft().model().position_vek();
-            let random_mid = thread_rng().gen_range(100., 200.);
-            let random_x = thread_rng().gen_range(terrain.bounds.x.start, terrain.bounds.x.end);
-            let random_z = thread_rng().gen_range(terrain.bounds.z.start, terrain.bounds.z.end);
-            let rand_height_offset = thread_rng().gen_range(40., 250.);
-            let rand_coord = Vec3::<f32>::from([
-                random_x as f32,
-                terrain.height_at(&(random_x, random_z).into()) + rand_height_offset,
-                random_z as f32,
-            ]);
+            self.start_point = cgmath_to_vek(&self.position().to_vec());
+            // Damaged control surfaces push the turn wider
+            let random_mid = rng.gen_range(100., 200.) / self.aircraft.damage().control();
+            let rand_coord = point_over(terrain, 1., rng);
             let mid = {
                 // Select a point in front of the aircraft to simulate turning
-                let mid =
-                    self.aircraft.model().position() + self.aircraft.model().front() * random_mid;
+                let mid = self.position() + self.aircraft.pose().front() * random_mid;
                 Vec3::from([mid.x, mid.y, mid.z])
             };
             self.end_point = rand_coord;
             let points = Vec3::from([self.start_point, mid, self.end_point]);
             self.bezier = QuadraticBezier3::from(points);
-        } else if !in_world_bounds(self.aircraft().model().position_vek(), terrain) {
+        } else if !in_world_bounds(cgmath_to_vek(&self.position().to_vec()), terrain) {
             self.progress = 0.;
-            self.start_point = self.aircraft().model().position_vek();
-            let random_mid_distance = thread_rng().gen_range(30., 200.);
-            let new_x = thread_rng().gen_range(-40., 40.);
-            let new_y = thread_rng().gen_range(10., 20.);
-            let new_z = thread_rng().gen_range(-40., 40.);
-
+            self.start_point = cgmath_to_vek(&self.position().to_vec());
+            let random_mid_distance = rng.gen_range(30., 200.) / self.aircraft.damage().control();
             let mid = {
-                // Select a point in front of the launching aircraft to simulate the missile accelerating
-                let mid = self.aircraft.model().position()
-                    + self.aircraft.model().front() * random_mid_distance;
+                let mid = self.position() + self.aircraft.pose().front() * random_mid_distance;
                 Vec3::from([mid.x, mid.y, mid.z])
             };
-            self.end_point = Vec3::new(new_x, new_y, new_z);
+            // Head back towards the middle of the terrain around the player
+            self.end_point = point_over(terrain, RETURN_SHARE, rng);
             let points = Vec3::from([self.start_point, mid, self.end_point]);
             self.bezier = QuadraticBezier3::from(points);
         };
@@ -99,7 +201,7 @@ impl Enemy {
             let t = 0.0005;
             let v1 = (2. * bezier.start) - (4. * bezier.ctrl) + (2. * bezier.end);
             let v2 = (-2. * bezier.start) + (2. * bezier.ctrl);
-            let l = unsafe { DELTA_TIME };
+            let l = self.speed() * frame.delta;
             t + (l / (t * v1 + v2).magnitude())
         };
 
@@ -110,25 +212,48 @@ impl Enemy {
             Vector3::from([eval.x, eval.y, eval.z])
         };
 
-        self.aircraft_mut().model_mut().set_translation(new_point);
-
-        let vec_to_point = vek_to_cgmath(&self.end_point)
-            .sub(self.position().to_vec())
-            .normalize();
+        let vec_to_point = vek_to_cgmath(&self.end_point).sub(new_point).normalize();
 
         let quat = Quaternion::from_arc(*VEC_RIGHT, vec_to_point, None);
-        self.aircraft_mut().model_mut().set_orientation(quat);
-        self.aircraft_mut().model_mut().yaw(-90.);
+        let mut pose = Pose::new(Point3::from_vec(new_point), quat);
+        pose.yaw(-90.);
+        self.aircraft_mut().place(pose);
     }
 }
 
-fn in_world_bounds(pos: Vec3<f32>, terrain: &Terrain) -> bool {
-    terrain.bounds.x.contains(&(pos.x as i32)) && terrain.bounds.z.contains(&(pos.z as i32))
+/// Short path in front of an aircraft at `pose`, on which it gets going
+fn path_ahead(pose: &Pose, rng: &mut impl Rng) -> QuadraticBezier3<f32> {
+    let random_mid = rng.gen_range(10., 20.);
+    let random_length = rng.gen_range(30., 40.);
+    let mid = {
+        // Select a point in front of the aircraft to simulate it accelerating
+        let mid = pose.position + pose.front() * random_mid;
+        Vec3::from([mid.x, mid.y, mid.z])
+    };
+    let start_point = cgmath_to_vek(&pose.position.to_vec());
+    let end_point = cgmath_to_vek(&(pose.position + pose.front() * random_length).to_vec())
+        + Vec3::new(random_length, 0., random_length);
+    QuadraticBezier3::from(Vec3::from([start_point, mid, end_point]))
 }
 
-fn at_destination(start: Vec3<f32>, end: Vec3<f32>) -> bool {
-    let difference = end - start;
-    difference[0].abs() < 0.2 && difference[1].abs() < 0.2 && difference[2].abs() < 0.2
+/// Random point where an enemy heads next, within the middle `share` of the loaded terrain
+/// along each axis and high enough above the ground below it
+pub fn point_over(terrain: &Terrain, share: f32, rng: &mut impl Rng) -> Vec3<f32> {
+    let bounds = terrain.bounds();
+    let mut pick = |range: Range<f32>| {
+        let centre = (range.start + range.end) / 2.;
+        let half = (range.end - range.start) * share / 2.;
+        rng.gen_range(centre - half, centre + half)
+    };
+    let x = pick(bounds.x);
+    let z = pick(bounds.z);
+    let height = rng.gen_range(PATH_HEIGHT.start, PATH_HEIGHT.end);
+    Vec3::new(x, terrain.height_at((x, z).into()) + height, z)
+}
+
+fn in_world_bounds(pos: Vec3<f32>, terrain: &Terrain) -> bool {
+    let bounds = terrain.bounds();
+    bounds.x.contains(&pos.x) && bounds.z.contains(&pos.z)
 }
 
 fn vek_to_cgmath(v: &Vec3<f32>) -> Vector3<f32> {
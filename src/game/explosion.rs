@@ -0,0 +1,70 @@
+use super::{modeled::Modeled, particle_generation::ParticleGeneration, rebase::Rebase};
+use crate::cg::{model::Model, particles::ParticleGenerator};
+use cgmath::{Point3, Vector3, Vector4};
+use rand::Rng;
+
+const EXPLOSION_PARTICLES: usize = 400;
+/// Speed of the fastest debris in m/s
+const EXPLOSION_SPEED: f32 = 25.;
+/// Seconds after which the explosion is removed from the world
+const EXPLOSION_DURATION: f32 = 5.;
+
+/// Fireball left behind by a crashed or destroyed aircraft
+pub struct Explosion {
+    position: Point3<f32>,
+    particle_generator: ParticleGenerator,
+    time_left: f32,
+}
+
+impl Explosion {
+    pub fn new(position: Point3<f32>, rng: &mut impl Rng) -> Self {
+        let mut particle_generator =
+            ParticleGenerator::new(EXPLOSION_PARTICLES, Vector4::new(1., 0.5, 0., 1.), 0.);
+        particle_generator.burst(position, EXPLOSION_PARTICLES, EXPLOSION_SPEED, rng);
+        Self {
+            position,
+            particle_generator,
+            time_left: EXPLOSION_DURATION,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32, rng: &mut impl Rng) {
+        self.time_left -= delta_time;
+        self.particle_generator.update_particles(
+            self.position,
+            0,
+            Vector3::unit_y(),
+            delta_time,
+            rng,
+        );
+    }
+
+    pub fn finished(&self) -> bool {
+        self.time_left <= 0.
+    }
+}
+
+impl Rebase for Explosion {
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        self.position += shift;
+        self.particle_generator.rebase(shift);
+    }
+}
+
+impl Modeled for Explosion {
+    fn model(&self) -> &Model {
+        self.particle_generator.model()
+    }
+    fn model_mut(&mut self) -> &mut Model {
+        self.particle_generator.model_mut()
+    }
+}
+
+impl ParticleGeneration for Explosion {
+    fn particle_generator(&self) -> &ParticleGenerator {
+        &self.particle_generator
+    }
+    fn particle_generator_mut(&mut self) -> &mut ParticleGenerator {
+        &mut self.particle_generator
+    }
+}
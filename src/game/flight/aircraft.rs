@@ -1,55 +1,105 @@
-use super::{control_surfaces::Controls, spec::AircraftSpec, steerable::Steerable};
+use super::{
+    blueprint::{Blueprint, Loadout},
+    control_surfaces::Controls,
+    damage::{Component, Damage},
+    dynamics::{FlightState, Pose, Trim, GRAVITY},
+    spec::AircraftSpec,
+    steerable::Steerable,
+};
 use crate::{
-    cg::{
-        camera::{Camera, ControlSurfaces},
-        model::Model,
-        particles::ParticleGenerator,
-    },
+    cg::{camera::ControlSurfaces, model::Model, particles::ParticleGenerator},
     game::{
-        drawable::Drawable, guns::Guns, modeled::Modeled, particle_generation::ParticleGeneration,
+        clock::FrameTime,
+        countermeasures::Countermeasures,
+        drawable::Drawable,
+        guns::Guns,
+        input::StickInput,
+        missile_catalogue::{MissileCatalogue, MissileKind},
+        modeled::Modeled,
+        particle_generation::ParticleGeneration,
+        rebase::Rebase,
     },
-    gen_ref_getters, DELTA_TIME,
+    gen_ref_getters,
 };
-use cgmath::Vector4;
-use lazy_static::lazy_static;
-use log::info;
-use std::collections::HashMap;
-use AircraftKind::*;
+use cgmath::{vec3, EuclideanSpace, InnerSpace, Quaternion, Vector3};
+use log::{info, warn};
+use rand::Rng;
+use std::{collections::BTreeMap, fmt};
 
 const MAX_PITCH_BIAS: f32 = 35.;
 const MAX_YAW_BIAS: f32 = 20.;
 const MAX_ROLL_BIAS: f32 = 70.;
+/// Fraction of the full throttle gained or lost per second of holding the throttle keys
+const THROTTLE_RATE: f32 = 0.5;
+/// Fraction of a wreck's speed the air takes away every second
+const WRECK_DRAG: f32 = 0.1;
+/// Degrees per second a wreck rolls while it falls
+const WRECK_ROLL_RATE: f32 = 120.;
+/// Most smoke particles a damaged aircraft trails per step, once it's destroyed
+const MAX_SMOKE: f32 = 3.;
 
 /// Struct representing an aircraft which can be steered and displayed
 #[derive(Debug)]
 pub struct Aircraft {
+    kind: AircraftKind,
+    /// Where the aircraft is. The model is only drawn there.
+    pose: Pose,
     model: Model,
+    /// Performance of the aircraft as built
+    intact: AircraftSpec,
+    /// Performance left after the damage taken. What the aircraft flies with.
     spec: AircraftSpec,
+    damage: Damage,
+    /// Seconds the aircraft has been falling since it was destroyed
+    fallen_for: f32,
     controls: Controls,
+    flight: FlightState,
     particle_generator: ParticleGenerator,
     guns: Guns,
+    /// Missiles hung on the hardpoints
+    stores: Vec<Store>,
+    countermeasures: Countermeasures,
+}
+
+/// Missiles hung on one hardpoint
+#[derive(Clone, Debug)]
+pub struct Store {
+    /// Position of the hardpoint relative to the pilot along the right, up and front axes
+    pub mount: Vector3<f32>,
+    pub kind: MissileKind,
+    /// Missiles left on the hardpoint
+    pub rounds: u32,
+    /// Weight of each missile in kg
+    pub mass: f32,
 }
 
 gen_ref_getters! {
     Aircraft,
+    kind -> &AircraftKind,
+    pose -> &Pose,
     model -> &Model,
     spec -> &AircraftSpec,
+    damage -> &Damage,
     controls -> &Controls,
+    flight -> &FlightState,
     guns -> &Guns,
+    countermeasures -> &Countermeasures,
 }
 
-#[derive(Hash, PartialEq, Eq, Debug)]
-/// Defines aircraft models available
-#[derive(Clone)]
-pub enum AircraftKind {
-    Mig21,
+/// Name of an aircraft type, the name of its file in `AIRCRAFT_DIR` without the extension
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Debug, Clone)]
+pub struct AircraftKind(String);
+
+impl AircraftKind {
+    pub fn new(name: impl Into<String>) -> Self {
+        AircraftKind(name.into())
+    }
 }
 
-lazy_static! {
-    static ref BLUEPRINTS: HashMap<AircraftKind, AircraftSpec> =
-        HashMap::from([(Mig21, AircraftSpec::new([0.03, 0.05, 0.05]))]);
-    static ref MODEL_PATHS: HashMap<AircraftKind, &'static str> =
-        HashMap::from([(Mig21, "resources/objects/mig21/mig21.obj")]);
+impl fmt::Display for AircraftKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
 impl ParticleGeneration for Aircraft {
@@ -61,6 +111,15 @@ impl ParticleGeneration for Aircraft {
     }
 }
 
+impl Rebase for Aircraft {
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        self.pose.rebase(shift);
+        self.model.rebase(shift);
+        self.particle_generator.rebase(shift);
+        self.guns.rebase(shift);
+    }
+}
+
 impl Drawable for Aircraft {
     unsafe fn draw(&self, shader: &crate::cg::shader::Shader) {
         self.model().draw(shader);
@@ -77,66 +136,277 @@ impl Modeled for Aircraft {
 }
 
 impl Aircraft {
-    pub fn new(kind: AircraftKind) -> Self {
-        info!("Creating new Aircraft of kind : {kind:?}");
-        let model = Model::new(MODEL_PATHS.get(&kind).expect("Path not found for kind"));
-        let particle_generator = ParticleGenerator::new(1500, Vector4::new(1., 0., 0., 1.), 2.);
+    pub fn new(blueprint: &Blueprint) -> Self {
+        info!(
+            "Creating new Aircraft {} of kind : {}",
+            blueprint.name(),
+            blueprint.kind()
+        );
+        let mut model = Model::new(blueprint.model_path());
+        model.set_scale(blueprint.model_scale());
+        let particle_generator = ParticleGenerator::new(1500, *blueprint.particle_colour(), 2.);
         Aircraft {
+            kind: blueprint.kind().clone(),
+            pose: Pose::default(),
             model,
-            spec: BLUEPRINTS
-                .get(&kind)
-                .expect("Blueprint not found for kind")
-                .to_owned(),
+            intact: blueprint.spec().clone(),
+            spec: blueprint.spec().clone(),
+            damage: Damage::new(blueprint.health()),
+            fallen_for: 0.,
             controls: Controls::default(),
+            flight: FlightState::default(),
             particle_generator,
-            guns: Guns::new(),
+            guns: Guns::new(blueprint.gun_mounts().clone(), blueprint.gun_rounds()),
+            stores: Vec::new(),
+            countermeasures: Countermeasures::new(blueprint.flares(), blueprint.chaff()),
         }
     }
 
+    /// Hang the missiles of `loadout` on the hardpoints, reload the guns and refill the dispensers
+    pub fn arm(
+        &mut self,
+        blueprint: &Blueprint,
+        loadout: &Loadout,
+        missile_types: &MissileCatalogue,
+    ) {
+        info!("Arming {} with {} loadout", blueprint.name(), loadout.name);
+        self.stores = loadout
+            .stations
+            .iter()
+            .zip(blueprint.hardpoints())
+            .filter_map(|(station, hardpoint)| {
+                let kind = station.as_ref()?;
+                let spec = missile_types
+                    .get(kind)
+                    .expect("Loadouts are checked against the missile catalogue")
+                    .spec();
+                Some(Store {
+                    mount: hardpoint.position,
+                    kind: kind.clone(),
+                    rounds: spec.per_hardpoint,
+                    mass: spec.mass,
+                })
+            })
+            .collect();
+        self.guns.reload();
+        self.countermeasures.reload();
+    }
+
+    /// Missiles left of each kind the aircraft was armed with
+    pub fn missiles(&self) -> BTreeMap<MissileKind, u32> {
+        let mut missiles = BTreeMap::new();
+        for store in &self.stores {
+            *missiles.entry(store.kind.clone()).or_insert(0) += store.rounds;
+        }
+        missiles
+    }
+
+    /// Take a missile of the given kind off the fullest hardpoint carrying it,
+    /// so the weight stays balanced between the wings.
+    /// Returns the position of the hardpoint, or `None` if there are none left.
+    pub fn take_missile(&mut self, kind: &MissileKind) -> Option<Vector3<f32>> {
+        let store = self
+            .stores
+            .iter_mut()
+            .filter(|s| s.kind == *kind && s.rounds > 0)
+            .rev()
+            .max_by_key(|s| s.rounds);
+        match store {
+            Some(store) => {
+                store.rounds -= 1;
+                Some(store.mount)
+            }
+            None => {
+                warn!("Out of {kind} missiles");
+                None
+            }
+        }
+    }
+
+    /// Weight in kg of the aircraft with its fuel, rounds and missiles
+    pub fn mass(&self) -> f32 {
+        let stores: f32 = self.stores.iter().map(|s| s.rounds as f32 * s.mass).sum();
+        self.spec.mass() + self.guns.mass() + stores
+    }
+
     pub fn controls_mut(&mut self) -> &mut Controls {
         &mut self.controls
     }
 
-    pub fn model_mut(&mut self) -> &mut Model {
-        &mut self.model
+    pub fn flight_mut(&mut self) -> &mut FlightState {
+        &mut self.flight
+    }
+
+    /// Deflection of the pitch, yaw and roll control surfaces in range [-1, 1]
+    pub fn deflection(&self) -> Vector3<f32> {
+        vec3(
+            (self.controls.pitch_bias() / MAX_PITCH_BIAS + self.controls.pitch_trim())
+                .clamp(-1., 1.),
+            self.controls.yaw_bias() / MAX_YAW_BIAS,
+            self.controls.roll_bias() / MAX_ROLL_BIAS,
+        )
+    }
+
+    /// Put the aircraft somewhere else, turned some other way
+    pub fn place(&mut self, pose: Pose) {
+        self.pose = pose;
+        self.model.set_translation(pose.position.to_vec());
+        self.model.set_orientation(pose.orientation);
+    }
+
+    /// Trim the aircraft for level flight at its airspeed from `level`, a pose with the wings
+    /// and the nose level. The nose is raised to the angle of attack holding the aircraft up,
+    /// the elevator trimmed to keep it there and the throttle set to make up for the drag.
+    pub fn trim(&mut self, level: Pose) {
+        let trim = Trim::level(&self.spec, self.mass(), self.flight.speed());
+        let mut pose = level;
+        pose.pitch(trim.angle_of_attack.to_degrees());
+        self.place(pose);
+        *self.controls.pitch_trim_mut() = trim.deflection;
+        *self.controls.throttle_mut() = trim.throttle;
+    }
+
+    /// Run the flight model for `delta_time` seconds and move the aircraft accordingly
+    pub fn fly(&mut self, delta_time: f32) {
+        let deflection = self.deflection();
+        let motion = self.flight.step(
+            &self.spec,
+            self.mass(),
+            deflection,
+            self.controls.throttle(),
+            &self.pose.attitude(),
+            delta_time,
+        );
+        let mut pose = self.pose;
+        pose.apply(&motion);
+        self.place(pose);
+    }
+
+    /// Take `amount` of damage from a hit on `component`, which loses performance.
+    /// Returns whether the hit destroyed the aircraft.
+    pub fn hit(&mut self, amount: f32, component: Component) -> bool {
+        if self.destroyed() {
+            return false;
+        }
+        self.damage.take(amount, component);
+        self.spec = self.damage.degrade(&self.intact);
+        info!(
+            "{} hit in the {component}, {:.0} health left",
+            self.kind,
+            self.damage.health()
+        );
+        self.destroyed()
+    }
+
+    /// Let a holed fuel tank burn for `delta_time` seconds.
+    /// Returns whether the fire destroyed the aircraft.
+    pub fn burn(&mut self, delta_time: f32) -> bool {
+        if self.destroyed() {
+            return false;
+        }
+        self.damage.burn(delta_time);
+        self.destroyed()
+    }
+
+    pub fn destroyed(&self) -> bool {
+        self.damage.destroyed()
+    }
+
+    /// Seconds the aircraft has been falling since it was destroyed
+    pub fn fallen_for(&self) -> f32 {
+        self.fallen_for
+    }
+
+    /// Let a destroyed aircraft tumble down, out of control and without thrust,
+    /// its nose following where it falls
+    pub fn fall(&mut self, delta_time: f32) {
+        let mut velocity = self.flight.velocity() * (1. - WRECK_DRAG * delta_time).max(0.);
+        velocity.y -= GRAVITY * delta_time;
+        self.flight = FlightState::new(velocity);
+        let mut pose = self.pose;
+        pose.position += velocity * delta_time;
+        if velocity.magnitude2() > f32::EPSILON {
+            let turn = Quaternion::from_arc(pose.front(), velocity.normalize(), None);
+            pose.orientation = (turn * pose.orientation).normalize();
+        }
+        pose.roll(WRECK_ROLL_RATE * delta_time);
+        self.place(pose);
+        self.fallen_for += delta_time;
+    }
+
+    /// Trail smoke the thicker the worse the aircraft is damaged
+    pub fn smoke(&mut self, delta_time: f32, rng: &mut impl Rng) {
+        let damage = 1. - self.damage.integrity();
+        let count = if damage > 0. {
+            (damage * MAX_SMOKE).ceil() as usize
+        } else {
+            0
+        };
+        let (position, front) = (self.pose.position, self.pose.front());
+        self.particle_generator
+            .update_particles(position, count, front, delta_time, rng);
     }
 
     pub fn set_decay(&mut self, c: ControlSurfaces, b: bool) {
         self.controls_mut().set_decay(c, b);
     }
 
-    pub fn fire_guns(&mut self, camera: &Camera) {
-        self.guns.fire(camera)
+    pub fn fire_guns(&mut self, frame: &FrameTime, rng: &mut impl Rng) {
+        self.guns.fire(&self.pose, frame, rng)
     }
 
     pub fn guns_mut(&mut self) -> &mut Guns {
         &mut self.guns
     }
 
+    pub fn countermeasures_mut(&mut self) -> &mut Countermeasures {
+        &mut self.countermeasures
+    }
+
     /// Mutate the control parameters of the aircraft by making all the flap
     /// biases closer to zero if that particular surface was not used by the player in this frame
-    pub fn apply_decay(&mut self) {
+    pub fn apply_decay(&mut self, delta_time: f32) {
         if self.controls().decay()[ControlSurfaces::Pitch as usize] {
-            self.controls_mut().apply_pitch_decay()
+            self.controls_mut().apply_pitch_decay(delta_time)
         }
         if self.controls().decay()[ControlSurfaces::Yaw as usize] {
-            self.controls_mut().apply_yaw_decay()
+            self.controls_mut().apply_yaw_decay(delta_time)
         }
         if self.controls().decay()[ControlSurfaces::Roll as usize] {
-            self.controls_mut().apply_roll_decay()
+            self.controls_mut().apply_roll_decay(delta_time)
         }
     }
 
-    pub fn throttle_up(&mut self) {
-        let delta_time = unsafe { DELTA_TIME };
+    /// Deflect the control surfaces in proportion to the analog controls.
+    /// Centered axes leave their surface alone.
+    pub fn deflect(&mut self, stick: &StickInput) {
+        let controls = self.controls_mut();
+        if stick.pitch != 0. {
+            *controls.pitch_bias_mut() = stick.pitch.clamp(-1., 1.) * MAX_PITCH_BIAS;
+            controls.set_decay(ControlSurfaces::Pitch, false);
+        }
+        if stick.yaw != 0. {
+            // Positive yaw bias turns the nose left
+            *controls.yaw_bias_mut() = -stick.yaw.clamp(-1., 1.) * MAX_YAW_BIAS;
+            controls.set_decay(ControlSurfaces::Yaw, false);
+        }
+        if stick.roll != 0. {
+            *controls.roll_bias_mut() = stick.roll.clamp(-1., 1.) * MAX_ROLL_BIAS;
+            controls.set_decay(ControlSurfaces::Roll, false);
+        }
+        if let Some(throttle) = stick.throttle {
+            *controls.throttle_mut() = throttle.clamp(0., 1.);
+        }
+    }
+
+    pub fn throttle_up(&mut self, delta_time: f32) {
         *self.controls_mut().throttle_mut() =
-            (self.controls().throttle() + delta_time).clamp(10., 1000.)
+            (self.controls().throttle() + THROTTLE_RATE * delta_time).clamp(0., 1.)
     }
 
-    pub fn throttle_down(&mut self) {
-        let delta_time = unsafe { DELTA_TIME };
+    pub fn throttle_down(&mut self, delta_time: f32) {
         *self.controls_mut().throttle_mut() =
-            (self.controls().throttle() - delta_time).clamp(10., 1000.)
+            (self.controls().throttle() - THROTTLE_RATE * delta_time).clamp(0., 1.)
     }
 }
 /// This implementation handles mutating the control parameters of the aircraft.
@@ -164,7 +434,7 @@ impl Steerable for Aircraft {
     }
     /// Mutate the throttle
     fn forward(&mut self, amount: f32) {
-        // TEMPORARY
-        *self.controls_mut().throttle_mut() += amount * 500. * STEERING_SENSITIVITY
+        *self.controls_mut().throttle_mut() =
+            (self.controls().throttle() + THROTTLE_RATE * amount).clamp(0., 1.)
     }
 }
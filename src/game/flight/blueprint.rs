@@ -0,0 +1,424 @@
+use super::{
+    aircraft::AircraftKind,
+    spec::{Aerodynamics, AircraftSpec, ThrustCurve},
+};
+use crate::game::missile_catalogue::MissileKind;
+use crate::gen_ref_getters;
+use cgmath::{Vector3, Vector4};
+use itertools::Itertools;
+use log::info;
+use rand::Rng;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Directory the aircraft definitions are loaded from.
+/// Every `.json` file in it defines one aircraft kind named after the file.
+pub const AIRCRAFT_DIR: &str = "resources/aircraft";
+
+#[derive(Debug)]
+pub enum BlueprintError {
+    Io(PathBuf, io::Error),
+    Format(PathBuf, serde_json::Error),
+    Invalid {
+        path: PathBuf,
+        field: &'static str,
+        reason: &'static str,
+    },
+    NoAircraft(PathBuf),
+    NoEnemies(PathBuf),
+    UnknownKind(AircraftKind, Vec<AircraftKind>),
+}
+
+impl fmt::Display for BlueprintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlueprintError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            BlueprintError::Format(path, e) => write!(f, "{}: {e}", path.display()),
+            BlueprintError::Invalid {
+                path,
+                field,
+                reason,
+            } => write!(f, "{}: invalid `{field}`: {reason}", path.display()),
+            BlueprintError::NoAircraft(dir) => {
+                write!(f, "no aircraft definitions found in {}", dir.display())
+            }
+            BlueprintError::NoEnemies(dir) => write!(
+                f,
+                "no aircraft in {} has a positive `enemy_weight`",
+                dir.display()
+            ),
+            BlueprintError::UnknownKind(kind, available) => write!(
+                f,
+                "unknown aircraft kind `{kind}`, available: {}",
+                available.iter().join(", ")
+            ),
+        }
+    }
+}
+
+/// Purpose an aircraft was designed for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Fighter,
+    Trainer,
+    Bomber,
+}
+
+impl Role {
+    /// Distance in m within which an enemy of this role opens fire with its guns.
+    /// Fighters press their attacks, bombers only defend themselves up close.
+    pub fn gun_range(self) -> f32 {
+        match self {
+            Role::Fighter => 800.,
+            Role::Trainer => 600.,
+            Role::Bomber => 400.,
+        }
+    }
+
+    /// Seconds an enemy of this role waits between two missile launches
+    pub fn launch_interval(self) -> f64 {
+        match self {
+            Role::Fighter => 15.,
+            Role::Trainer => 25.,
+            Role::Bomber => 40.,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Role::Fighter => "fighter",
+            Role::Trainer => "trainer",
+            Role::Bomber => "bomber",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Place under the wings or the fuselage where missiles or a rocket pod can be hung
+#[derive(Clone, Debug)]
+pub struct Hardpoint {
+    /// Position relative to the pilot along the right, up and front axes
+    pub position: Vector3<f32>,
+    /// Kinds of missiles the hardpoint can carry
+    pub weapons: Vec<MissileKind>,
+}
+
+/// On-disk representation of a hardpoint
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HardpointFile {
+    position: [f32; 3],
+    weapons: Vec<MissileKind>,
+}
+
+/// What an aircraft carries into the fight
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Loadout {
+    pub name: String,
+    /// Kind of missile hung on each hardpoint, in the order of the hardpoints.
+    /// `null` leaves a hardpoint empty.
+    pub stations: Vec<Option<MissileKind>>,
+}
+
+/// On-disk representation of a blueprint
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BlueprintFile {
+    name: String,
+    role: Role,
+    model: String,
+    /// Size of the aircraft relative to its model file
+    model_scale: f32,
+    /// How often the aircraft appears in enemy waves relative to the others.
+    /// Zero keeps it out of the waves.
+    enemy_weight: u32,
+    /// Speed at which the aircraft flies as an enemy in m/s
+    cruise_speed: f32,
+    /// Pitch, yaw and roll rates
+    rates: [f32; 3],
+    mass: f32,
+    /// Damage the aircraft takes before it's destroyed
+    health: f32,
+    inertia: [f32; 3],
+    aerodynamics: Aerodynamics,
+    thrust: ThrustCurve,
+    particle_colour: [f32; 4],
+    /// Gun positions relative to the pilot along the right, up and front axes
+    gun_mounts: Vec<[f32; 3]>,
+    gun_rounds: u32,
+    /// Decoys each dispenser holds
+    flares: u32,
+    chaff: u32,
+    hardpoints: Vec<HardpointFile>,
+    /// Loadouts the player can pick from, the first one by default
+    loadouts: Vec<Loadout>,
+}
+
+/// Everything needed to build an aircraft of a given kind
+#[derive(Clone, Debug)]
+pub struct Blueprint {
+    kind: AircraftKind,
+    name: String,
+    role: Role,
+    model_path: String,
+    model_scale: f32,
+    enemy_weight: u32,
+    cruise_speed: f32,
+    health: f32,
+    spec: AircraftSpec,
+    particle_colour: Vector4<f32>,
+    gun_mounts: Vec<Vector3<f32>>,
+    gun_rounds: u32,
+    flares: u32,
+    chaff: u32,
+    hardpoints: Vec<Hardpoint>,
+    loadouts: Vec<Loadout>,
+}
+
+gen_ref_getters! {
+    Blueprint,
+    kind -> &AircraftKind,
+    name -> &String,
+    role -> &Role,
+    model_path -> &String,
+    spec -> &AircraftSpec,
+    particle_colour -> &Vector4<f32>,
+    gun_mounts -> &Vec<Vector3<f32>>,
+    hardpoints -> &Vec<Hardpoint>,
+    loadouts -> &Vec<Loadout>,
+}
+
+impl Blueprint {
+    pub fn model_scale(&self) -> f32 {
+        self.model_scale
+    }
+
+    pub fn cruise_speed(&self) -> f32 {
+        self.cruise_speed
+    }
+
+    pub fn health(&self) -> f32 {
+        self.health
+    }
+
+    pub fn gun_rounds(&self) -> u32 {
+        self.gun_rounds
+    }
+
+    pub fn flares(&self) -> u32 {
+        self.flares
+    }
+
+    pub fn chaff(&self) -> u32 {
+        self.chaff
+    }
+
+    /// Loadout with the given name
+    pub fn loadout(&self, name: &str) -> Option<&Loadout> {
+        self.loadouts.iter().find(|l| l.name == name)
+    }
+
+    /// Load and validate a single aircraft definition
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BlueprintError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| BlueprintError::Io(path.into(), e))?;
+        let file: BlueprintFile =
+            serde_json::from_str(&contents).map_err(|e| BlueprintError::Format(path.into(), e))?;
+        let invalid = |field, reason| {
+            Err(BlueprintError::Invalid {
+                path: path.into(),
+                field,
+                reason,
+            })
+        };
+
+        let aero = &file.aerodynamics;
+        if file.name.trim().is_empty() {
+            return invalid("name", "must not be empty");
+        }
+        if !Path::new(&file.model).is_file() {
+            return invalid("model", "file does not exist");
+        }
+        if file.model_scale <= 0. {
+            return invalid("model_scale", "must be positive");
+        }
+        if file.cruise_speed <= 0. {
+            return invalid("cruise_speed", "must be positive");
+        }
+        if file.rates.iter().any(|r| *r <= 0.) {
+            return invalid("rates", "must be positive");
+        }
+        if file.mass <= 0. {
+            return invalid("mass", "must be positive");
+        }
+        if file.health <= 0. {
+            return invalid("health", "must be positive");
+        }
+        if file.inertia.iter().any(|i| *i <= 0.) {
+            return invalid("inertia", "must be positive");
+        }
+        if aero.wing_area <= 0. || aero.lift_slope <= 0. {
+            return invalid("aerodynamics", "wing area and lift slope must be positive");
+        }
+        if aero.stall_angle <= 0. || aero.stall_angle >= 90_f32.to_radians() {
+            return invalid(
+                "aerodynamics",
+                "stall angle must be between 0 and 90 degrees",
+            );
+        }
+        if !(0. ..=1.).contains(&aero.stall_lift_loss) {
+            return invalid("aerodynamics", "stall lift loss must be between 0 and 1");
+        }
+        if aero.zero_lift_drag < 0. || aero.induced_drag < 0. {
+            return invalid("aerodynamics", "drag coefficients must not be negative");
+        }
+        let positive = |c: &f32| *c > 0. && c.is_finite();
+        if !aero.control_power.iter().all(positive) {
+            return invalid("control_power", "must be positive and finite");
+        }
+        if !aero.damping.iter().all(positive) {
+            return invalid("damping", "must be positive and finite");
+        }
+        if ![aero.stability.0, aero.stability.1].iter().all(positive) {
+            return invalid("stability", "must be positive and finite");
+        }
+        if !file.particle_colour.iter().all(|c| (0. ..=1.).contains(c)) {
+            return invalid("particle_colour", "channels must be between 0 and 1");
+        }
+        if file.gun_mounts.is_empty() {
+            return invalid("gun_mounts", "at least one gun mount is needed");
+        }
+        if file.loadouts.is_empty() {
+            return invalid("loadouts", "at least one loadout is needed");
+        }
+        for (n, loadout) in file.loadouts.iter().enumerate() {
+            if file.loadouts[..n].iter().any(|l| l.name == loadout.name) {
+                return invalid("loadouts", "loadout names must be unique");
+            }
+            if loadout.stations.len() != file.hardpoints.len() {
+                return invalid(
+                    "loadouts",
+                    "every loadout needs a station for each hardpoint",
+                );
+            }
+            let fits = loadout
+                .stations
+                .iter()
+                .zip(&file.hardpoints)
+                .all(|(station, hardpoint)| {
+                    station
+                        .as_ref()
+                        .is_none_or(|kind| hardpoint.weapons.contains(kind))
+                });
+            if !fits {
+                return invalid("loadouts", "a hardpoint can't carry the missile hung on it");
+            }
+        }
+
+        let kind = AircraftKind::new(
+            path.file_stem()
+                .map(|s| s.to_string_lossy())
+                .unwrap_or_default(),
+        );
+        Ok(Blueprint {
+            kind,
+            name: file.name,
+            role: file.role,
+            model_path: file.model,
+            model_scale: file.model_scale,
+            enemy_weight: file.enemy_weight,
+            cruise_speed: file.cruise_speed,
+            health: file.health,
+            spec: AircraftSpec::new(
+                file.rates,
+                file.mass,
+                file.inertia,
+                file.aerodynamics,
+                file.thrust,
+            ),
+            particle_colour: file.particle_colour.into(),
+            gun_mounts: file.gun_mounts.into_iter().map(Vector3::from).collect(),
+            gun_rounds: file.gun_rounds,
+            flares: file.flares,
+            chaff: file.chaff,
+            hardpoints: file
+                .hardpoints
+                .into_iter()
+                .map(|h| Hardpoint {
+                    position: h.position.into(),
+                    weapons: h.weapons,
+                })
+                .collect(),
+            loadouts: file.loadouts,
+        })
+    }
+}
+
+/// All aircraft kinds available in the game
+#[derive(Clone, Debug)]
+pub struct Blueprints {
+    map: BTreeMap<AircraftKind, Blueprint>,
+}
+
+impl Blueprints {
+    /// Load every aircraft definition in a directory
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, BlueprintError> {
+        let dir = dir.as_ref();
+        let io_error = |e| BlueprintError::Io(dir.into(), e);
+        let mut map = BTreeMap::new();
+        for entry in fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let blueprint = Blueprint::load(&path)?;
+                info!(
+                    "Loaded {} {} from {:?}",
+                    blueprint.role(),
+                    blueprint.name(),
+                    path
+                );
+                map.insert(blueprint.kind.clone(), blueprint);
+            }
+        }
+        if map.is_empty() {
+            return Err(BlueprintError::NoAircraft(dir.into()));
+        }
+        if map.values().all(|b| b.enemy_weight == 0) {
+            return Err(BlueprintError::NoEnemies(dir.into()));
+        }
+        Ok(Blueprints { map })
+    }
+
+    /// Pick the kind of an enemy, weighted by the blueprints' `enemy_weight`
+    pub fn random_enemy(&self, rng: &mut impl Rng) -> &Blueprint {
+        let total: u32 = self.map.values().map(|b| b.enemy_weight).sum();
+        let mut pick = rng.gen_range(0, total);
+        self.map
+            .values()
+            .find(|b| {
+                if pick < b.enemy_weight {
+                    return true;
+                }
+                pick -= b.enemy_weight;
+                false
+            })
+            .expect("Pick within the total weight")
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Blueprint> {
+        self.map.values()
+    }
+
+    pub fn get(&self, kind: &AircraftKind) -> Result<&Blueprint, BlueprintError> {
+        self.map.get(kind).ok_or_else(|| {
+            BlueprintError::UnknownKind(kind.clone(), self.map.keys().cloned().collect())
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{cg::camera::ControlSurfaces, gen_getters, DELTA_TIME};
+use crate::{cg::camera::ControlSurfaces, gen_getters};
 
 const DECAY: f32 = 50.;
 
@@ -9,6 +9,9 @@ pub struct Controls {
     pitch_bias: f32,
     yaw_bias: f32,
     roll_bias: f32,
+    /// Pitch deflection in range [-1, 1] held on top of the pitch bias, which doesn't decay
+    pitch_trim: f32,
+    /// Fraction of the engine's maximum thrust in range [0, 1]
     throttle: f32,
     decay: [bool; 3],
 }
@@ -19,7 +22,8 @@ impl Default for Controls {
             pitch_bias: 0.,
             yaw_bias: 0.,
             roll_bias: 0.,
-            throttle: 0.1,
+            pitch_trim: 0.,
+            throttle: 0.7,
             decay: [true, true, true],
         }
     }
@@ -35,6 +39,9 @@ impl Controls {
     pub fn roll_bias_mut(&mut self) -> &mut f32 {
         &mut self.roll_bias
     }
+    pub fn pitch_trim_mut(&mut self) -> &mut f32 {
+        &mut self.pitch_trim
+    }
     pub fn throttle_mut(&mut self) -> &mut f32 {
         &mut self.throttle
     }
@@ -49,11 +56,10 @@ impl Controls {
         self.decay = [b, b, b]
     }
     /// Compute a new value of the aircraft's pitch based on decay and set it as the new pitch bias
-    pub fn apply_pitch_decay(&mut self) {
-        let delta_time = unsafe { DELTA_TIME };
+    pub fn apply_pitch_decay(&mut self, delta_time: f32) {
         self.pitch_bias = if self.pitch_bias.abs() > delta_time {
             round(
-                self.pitch_bias + (DECAY * delta_time) * self.pitch_bias.signum() * -1.,
+                self.pitch_bias - (DECAY * delta_time) * self.pitch_bias.signum(),
                 5,
             )
         } else {
@@ -61,11 +67,10 @@ impl Controls {
         }
     }
     /// Compute a new value of the aircraft's yaw based on decay and set it as the new yaw bias
-    pub fn apply_yaw_decay(&mut self) {
-        let delta_time = unsafe { DELTA_TIME };
+    pub fn apply_yaw_decay(&mut self, delta_time: f32) {
         self.yaw_bias = if self.yaw_bias.abs() > delta_time {
             round(
-                self.yaw_bias + (DECAY * delta_time) * self.yaw_bias.signum() * -1.,
+                self.yaw_bias - (DECAY * delta_time) * self.yaw_bias.signum(),
                 5,
             )
         } else {
@@ -73,11 +78,10 @@ impl Controls {
         }
     }
     /// Compute a new value of the aircraft's roll based on decay and set it the as the new roll bias
-    pub fn apply_roll_decay(&mut self) {
-        let delta_time = unsafe { DELTA_TIME };
+    pub fn apply_roll_decay(&mut self, delta_time: f32) {
         self.roll_bias = if self.roll_bias.abs() > delta_time {
             round(
-                self.roll_bias + (DECAY * delta_time) * self.roll_bias.signum() * -1.,
+                self.roll_bias - (DECAY * delta_time) * self.roll_bias.signum(),
                 5,
             )
         } else {
@@ -91,6 +95,7 @@ gen_getters! {
     pitch_bias -> f32,
     yaw_bias -> f32,
     roll_bias -> f32,
+    pitch_trim -> f32,
     throttle -> f32,
 }
 
@@ -0,0 +1,114 @@
+use super::spec::AircraftSpec;
+use rand::Rng;
+use std::fmt;
+
+/// How much of its strength a component loses per fraction of the aircraft's health
+/// a hit on it takes. A hit taking half the health wrecks the component it lands on.
+const COMPONENT_VULNERABILITY: f32 = 2.;
+/// Health per second an aircraft loses to the fire of a fully holed fuel tank
+const FIRE_RATE: f32 = 4.;
+/// Fraction of the control power lost with all the control surfaces shot away.
+/// The pilot can still steer a little with what's left.
+const CONTROL_LOSS: f32 = 0.8;
+
+/// Part of an aircraft which is damaged on its own
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Component {
+    /// Loses thrust
+    Engine,
+    /// Lose control power, so the aircraft turns slower
+    ControlSurfaces,
+    /// Catches fire, which keeps eating into the health
+    FuelTank,
+}
+
+impl Component {
+    pub const ALL: [Component; 3] = [
+        Component::Engine,
+        Component::ControlSurfaces,
+        Component::FuelTank,
+    ];
+
+    /// Component a hit lands on, each one as likely as the others
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self::ALL[rng.gen_range(0, Self::ALL.len())]
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Component::Engine => "engine",
+            Component::ControlSurfaces => "control surfaces",
+            Component::FuelTank => "fuel tank",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Health of an aircraft and how badly each of its components is damaged
+#[derive(Clone, Debug)]
+pub struct Damage {
+    health: f32,
+    max_health: f32,
+    /// Damage of each component in range [0, 1], indexed by `Component`.
+    /// 1 means the component is wrecked.
+    components: [f32; 3],
+}
+
+impl Damage {
+    pub fn new(health: f32) -> Self {
+        Self {
+            health,
+            max_health: health,
+            components: [0.; 3],
+        }
+    }
+
+    pub fn health(&self) -> f32 {
+        self.health
+    }
+
+    /// Fraction of the health left, in range [0, 1]
+    pub fn integrity(&self) -> f32 {
+        self.health / self.max_health
+    }
+
+    /// How badly the component is damaged, in range [0, 1]
+    pub fn component(&self, component: Component) -> f32 {
+        self.components[component as usize]
+    }
+
+    pub fn destroyed(&self) -> bool {
+        self.health <= 0.
+    }
+
+    /// Take `amount` of damage from a hit on `component`
+    pub fn take(&mut self, amount: f32, component: Component) {
+        self.health = (self.health - amount).max(0.);
+        let share = amount / self.max_health * COMPONENT_VULNERABILITY;
+        let damage = &mut self.components[component as usize];
+        *damage = (*damage + share).min(1.);
+    }
+
+    /// Let a holed fuel tank burn for `delta_time` seconds
+    pub fn burn(&mut self, delta_time: f32) {
+        let fire = self.component(Component::FuelTank) * FIRE_RATE * delta_time;
+        self.health = (self.health - fire).max(0.);
+    }
+
+    /// Fraction of the engine's thrust left
+    pub fn thrust(&self) -> f32 {
+        1. - self.component(Component::Engine)
+    }
+
+    /// Fraction of the control surfaces' power left
+    pub fn control(&self) -> f32 {
+        1. - CONTROL_LOSS * self.component(Component::ControlSurfaces)
+    }
+
+    /// Performance left to an aircraft built to `spec` with this damage
+    pub fn degrade(&self, spec: &AircraftSpec) -> AircraftSpec {
+        spec.degraded(self.thrust(), self.control())
+    }
+}
@@ -0,0 +1,260 @@
+use super::{spec::AircraftSpec, steerable::Steerable};
+use crate::{
+    cg::consts::{VEC_FRONT, VEC_RIGHT, VEC_UP},
+    game::rebase::Rebase,
+    gen_getters,
+};
+use cgmath::{
+    Deg, ElementWise, InnerSpace, One, Point2, Point3, Quaternion, Rotation, Rotation3, Vector3,
+    Zero,
+};
+
+/// Gravitational acceleration in m/s^2
+pub const GRAVITY: f32 = 9.81;
+/// Density of the air in kg/m^3
+const AIR_DENSITY: f32 = 1.225;
+/// Longest time the flight model integrates in a single step.
+/// Longer frames are split into several steps, so the integration stays stable.
+pub const MAX_FLIGHT_STEP: f32 = 1. / 120.;
+
+/// Controls holding an aircraft in steady level flight
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trim {
+    /// Angle of attack in radians at which the wing holds the aircraft up
+    pub angle_of_attack: f32,
+    /// Pitch deflection in range [-1, 1] holding the nose at that angle against the stability
+    pub deflection: f32,
+    /// Fraction of the maximum thrust making up for the drag
+    pub throttle: f32,
+}
+
+impl Trim {
+    /// Trim of an aircraft built to `spec` and weighing `mass` kg, flying level at `speed` m/s.
+    /// Too slow for the wing to hold it up, it flies at the stall angle and sinks.
+    pub fn level(spec: &AircraftSpec, mass: f32, speed: f32) -> Self {
+        let aero = spec.aerodynamics();
+        let pressure = 0.5 * AIR_DENSITY * speed.powi(2) * aero.wing_area;
+        let lift_coefficient = mass * GRAVITY / pressure.max(f32::EPSILON);
+        let angle_of_attack = (lift_coefficient / aero.lift_slope).min(aero.stall_angle);
+        let deflection = aero.stability.0 * angle_of_attack / aero.control_power[0];
+        let drag = pressure * aero.drag_coefficient(aero.lift_coefficient(angle_of_attack));
+        let thrust = spec.thrust().at(speed) * angle_of_attack.cos();
+        Self {
+            angle_of_attack,
+            deflection: deflection.clamp(-1., 1.),
+            throttle: (drag / thrust).clamp(0., 1.),
+        }
+    }
+}
+
+/// Orientation of an aircraft's body axes in the world
+#[derive(Clone, Copy, Debug)]
+pub struct Attitude {
+    pub front: Vector3<f32>,
+    pub up: Vector3<f32>,
+    pub right: Vector3<f32>,
+}
+
+/// Where a rigid body is and which way it's turned.
+/// This is the one true state of an aircraft's placement: what it fires from,
+/// what it collides with and what the cameras look through are all derived from it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pose {
+    pub position: Point3<f32>,
+    /// Rotation from the body's own axes to world space.
+    /// The body's right, up and front are x, y and -z, like a camera's.
+    pub orientation: Quaternion<f32>,
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self::new(Point3::new(0., 0., 0.), Quaternion::one())
+    }
+}
+
+impl Steerable for Pose {
+    fn pitch(&mut self, amount: f32) {
+        self.turn(*VEC_RIGHT, amount);
+    }
+
+    fn yaw(&mut self, amount: f32) {
+        self.turn(*VEC_UP, amount);
+    }
+
+    fn roll(&mut self, amount: f32) {
+        self.turn(*VEC_FRONT, amount);
+    }
+
+    fn forward(&mut self, amount: f32) {
+        self.position += self.front() * amount;
+    }
+}
+
+impl Rebase for Pose {
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        self.position += shift;
+    }
+}
+
+impl Pose {
+    pub fn new(position: Point3<f32>, orientation: Quaternion<f32>) -> Self {
+        Self {
+            position,
+            orientation,
+        }
+    }
+
+    pub fn front(&self) -> Vector3<f32> {
+        self.orientation.rotate_vector(*VEC_FRONT).normalize()
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.orientation.rotate_vector(*VEC_UP).normalize()
+    }
+
+    pub fn right(&self) -> Vector3<f32> {
+        self.orientation.rotate_vector(*VEC_RIGHT).normalize()
+    }
+
+    pub fn attitude(&self) -> Attitude {
+        Attitude {
+            front: self.front(),
+            up: self.up(),
+            right: self.right(),
+        }
+    }
+
+    /// World position of a point given along the body's right, up and front axes
+    pub fn relative(&self, offset: Vector3<f32>) -> Point3<f32> {
+        self.position + self.right() * offset.x + self.up() * offset.y + self.front() * offset.z
+    }
+
+    /// Position projected onto the xz plane
+    pub fn xz(&self) -> Point2<f32> {
+        Point2::new(self.position.x, self.position.z)
+    }
+
+    /// Move by one step of the flight model
+    pub fn apply(&mut self, motion: &Motion) {
+        let (pitch, yaw, roll) = motion.rotation.into();
+        self.pitch(pitch);
+        self.yaw(yaw);
+        self.roll(roll);
+        self.position += motion.translation;
+    }
+
+    /// Rotate around one of the body's own axes by `amount` degrees
+    fn turn(&mut self, axis: Vector3<f32>, amount: f32) {
+        self.orientation =
+            (self.orientation * Quaternion::from_axis_angle(axis, Deg(amount))).normalize();
+    }
+}
+
+/// Movement of an aircraft during a single step of the flight model
+#[derive(Clone, Copy, Debug)]
+pub struct Motion {
+    /// Change of position in world space
+    pub translation: Vector3<f32>,
+    /// Rotation around the pitch, yaw and roll axes in degrees,
+    /// to be applied in that order with `Steerable`
+    pub rotation: Vector3<f32>,
+}
+
+/// Physical state of a flying aircraft.
+/// Turns the position of the controls into forces and moments acting on
+/// the aircraft and integrates them into its motion.
+#[derive(Clone, Copy, Debug)]
+pub struct FlightState {
+    /// Velocity in world space in m/s
+    velocity: Vector3<f32>,
+    /// Rotation speed around the pitch, yaw and roll axes in rad/s
+    angular_velocity: Vector3<f32>,
+    /// Angle between the nose and the airflow in radians, as of the last step
+    angle_of_attack: f32,
+    stalled: bool,
+}
+
+gen_getters! {
+    FlightState,
+    velocity -> Vector3<f32>,
+    angle_of_attack -> f32,
+    stalled -> bool,
+}
+
+impl Default for FlightState {
+    fn default() -> Self {
+        Self::new(Vector3::zero())
+    }
+}
+
+impl FlightState {
+    pub fn new(velocity: Vector3<f32>) -> Self {
+        FlightState {
+            velocity,
+            angular_velocity: Vector3::zero(),
+            angle_of_attack: 0.,
+            stalled: false,
+        }
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.velocity.magnitude()
+    }
+
+    /// Integrate the forces and moments acting on the aircraft over `delta_time` seconds.
+    /// `deflection` holds the pitch, yaw and roll control surface deflections in range
+    /// [-1, 1] and `throttle` is a fraction of the maximum thrust.
+    /// `mass` is the weight in kg the aircraft flies with, including what it carries.
+    /// Its moments of inertia grow with it.
+    pub fn step(
+        &mut self,
+        spec: &AircraftSpec,
+        mass: f32,
+        deflection: Vector3<f32>,
+        throttle: f32,
+        attitude: &Attitude,
+        delta_time: f32,
+    ) -> Motion {
+        let aero = spec.aerodynamics();
+        let speed = self.speed();
+        let pressure = 0.5 * AIR_DENSITY * speed.powi(2) * aero.wing_area;
+
+        // Airflow relative to the body axes
+        let forward = self.velocity.dot(attitude.front);
+        let alpha = (-self.velocity.dot(attitude.up)).atan2(forward);
+        let beta = self.velocity.dot(attitude.right).atan2(forward);
+        self.angle_of_attack = alpha;
+        self.stalled = alpha.abs() > aero.stall_angle;
+
+        let lift_coefficient = aero.lift_coefficient(alpha);
+        let mut force = attitude.front * spec.thrust().at(speed) * throttle;
+        if speed > f32::EPSILON {
+            let direction = self.velocity / speed;
+            let lift_direction = attitude.right.cross(direction);
+            if lift_direction.magnitude2() > f32::EPSILON {
+                force += lift_direction.normalize() * pressure * lift_coefficient;
+            }
+            force -= direction * pressure * aero.drag_coefficient(lift_coefficient);
+            force -= attitude.right * pressure * aero.side_force_slope * beta;
+        }
+        force -= Vector3::unit_y() * mass * GRAVITY;
+        self.velocity += force / mass * delta_time;
+
+        // Control surfaces rotate the aircraft, the airflow turns its nose into the wind
+        let moment = (deflection.mul_element_wise(Vector3::from(aero.control_power))
+            - Vector3::new(aero.stability.0 * alpha, aero.stability.1 * beta, 0.))
+            * pressure;
+        let inertia = spec.inertia() * (mass / spec.mass());
+        let acceleration = moment.div_element_wise(inertia);
+        // Damping is integrated implicitly, so it can't overshoot at high speeds
+        let damping =
+            (Vector3::from(aero.damping) * pressure * delta_time).div_element_wise(inertia);
+        self.angular_velocity = (self.angular_velocity + acceleration * delta_time)
+            .div_element_wise(damping.add_element_wise(1.));
+
+        Motion {
+            translation: self.velocity * delta_time,
+            rotation: (self.angular_velocity * delta_time).map(f32::to_degrees),
+        }
+    }
+}
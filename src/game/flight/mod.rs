@@ -1,4 +1,8 @@
 pub mod aircraft;
+pub mod blueprint;
 pub mod control_surfaces;
+pub mod damage;
+pub mod dynamics;
+pub mod mouse_aim;
 pub mod spec;
 pub mod steerable;
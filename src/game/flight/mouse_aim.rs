@@ -0,0 +1,83 @@
+use super::dynamics::Attitude;
+use crate::game::input::StickInput;
+use cgmath::{Deg, InnerSpace, Quaternion, Rotation, Rotation3, Vector3};
+use std::fmt;
+
+/// Degrees the aim direction turns per unit of look input
+const AIM_SENSITIVITY: f32 = 0.1;
+/// Steepest the aim direction can point up or down, in degrees from the horizon.
+/// Keeps it away from the poles, where turning it sideways stops making sense.
+const MAX_AIM_ELEVATION: f32 = 85.;
+/// Stick deflection per radian between the nose and the aim direction
+const PITCH_GAIN: f32 = 4.;
+const YAW_GAIN: f32 = 3.;
+const ROLL_GAIN: f32 = 2.5;
+/// Angle between the nose and the aim direction in radians, under which the instructor
+/// levels the wings and corrects with the rudder instead of banking toward the aim
+const FINE_AIM_ANGLE: f32 = 0.1;
+/// Angle in radians over which banking toward the aim fully takes over from levelling the wings
+const BANK_BLEND_ANGLE: f32 = 0.2;
+
+/// How the mouse flies the aircraft
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ControlMode {
+    /// Keys and joystick fly the aircraft, the mouse turns it directly
+    #[default]
+    Keyboard,
+    /// The mouse points where the aircraft should go and an instructor flies it there
+    MouseAim,
+}
+
+impl fmt::Display for ControlMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ControlMode::Keyboard => "keyboard",
+            ControlMode::MouseAim => "mouse aim",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Turn the aim direction by the look input. The y axis goes from bottom to top.
+pub fn turn_aim(aim: Vector3<f32>, look: (f32, f32)) -> Vector3<f32> {
+    let up = Vector3::unit_y();
+    let yawed = Quaternion::from_axis_angle(up, Deg(-look.0 * AIM_SENSITIVITY)).rotate_vector(aim);
+    let elevation = yawed.y.clamp(-1., 1.).asin().to_degrees();
+    let pitch = (elevation + look.1 * AIM_SENSITIVITY).clamp(-MAX_AIM_ELEVATION, MAX_AIM_ELEVATION)
+        - elevation;
+    let right = yawed.cross(up);
+    if right.magnitude2() < f32::EPSILON {
+        return yawed;
+    }
+    Quaternion::from_axis_angle(right.normalize(), Deg(pitch))
+        .rotate_vector(yawed)
+        .normalize()
+}
+
+/// Stick deflections flying an aircraft with the given attitude toward `aim`.
+/// Far from the aim the instructor banks toward it and pulls or pushes the nose onto it,
+/// close to it the wings are levelled and the rudder makes the last corrections.
+pub fn instruct(attitude: &Attitude, aim: Vector3<f32>) -> StickInput {
+    // Aim direction in the aircraft's own axes
+    let (x, y, z) = (
+        aim.dot(attitude.right),
+        aim.dot(attitude.up),
+        aim.dot(attitude.front),
+    );
+    let off_nose = aim.angle(attitude.front).0;
+
+    // Only ever bank toward the side the aim is on, pushing the nose down to an aim below
+    // the horizon of the aircraft beats flying half a loop upside down
+    let bank_to_aim = x.atan2(y.abs());
+    // Positive when the right wing hangs low
+    let bank = (-attitude.right.y).atan2(attitude.up.y);
+    let blend = ((off_nose - FINE_AIM_ANGLE) / BANK_BLEND_ANGLE).clamp(0., 1.);
+    let roll = blend * bank_to_aim - (1. - blend) * bank;
+
+    StickInput {
+        pitch: (PITCH_GAIN * y.atan2(z)).clamp(-1., 1.),
+        roll: (ROLL_GAIN * roll).clamp(-1., 1.),
+        yaw: (YAW_GAIN * x.atan2(z)).clamp(-1., 1.),
+        throttle: None,
+    }
+}
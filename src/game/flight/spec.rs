@@ -1,17 +1,45 @@
-#[derive(Clone, Copy, Debug)]
-/// Define the aircraft's manouverability. Higher values of the attributes mean greater capability.
+use crate::gen_ref_getters;
+use cgmath::Vector3;
+use serde::{Deserialize, Deserializer};
+
+#[derive(Clone, Debug)]
+/// Define the aircraft's manouverability and physical properties.
+/// Higher values of the rates mean greater capability.
 pub struct AircraftSpec {
     pitch_rate: f32,
     yaw_rate: f32,
     roll_rate: f32,
+    /// Mass of the aircraft in kg
+    mass: f32,
+    /// Moments of inertia around the pitch, yaw and roll axes in kg*m^2
+    inertia: Vector3<f32>,
+    aerodynamics: Aerodynamics,
+    thrust: ThrustCurve,
+}
+
+gen_ref_getters! {
+    AircraftSpec,
+    inertia -> &Vector3<f32>,
+    aerodynamics -> &Aerodynamics,
+    thrust -> &ThrustCurve,
 }
 
 impl AircraftSpec {
-    pub const fn new(v: [f32; 3]) -> Self {
+    pub fn new(
+        rates: [f32; 3],
+        mass: f32,
+        inertia: [f32; 3],
+        aerodynamics: Aerodynamics,
+        thrust: ThrustCurve,
+    ) -> Self {
         AircraftSpec {
-            pitch_rate: v[0],
-            yaw_rate: v[1],
-            roll_rate: v[2],
+            pitch_rate: rates[0],
+            yaw_rate: rates[1],
+            roll_rate: rates[2],
+            mass,
+            inertia: inertia.into(),
+            aerodynamics,
+            thrust,
         }
     }
     pub fn pitch_rate(&self) -> f32 {
@@ -23,4 +51,130 @@ impl AircraftSpec {
     pub fn roll_rate(&self) -> f32 {
         self.roll_rate
     }
+    pub fn mass(&self) -> f32 {
+        self.mass
+    }
+
+    /// The same aircraft with only `thrust` and `control` left of its engine's thrust
+    /// and of the power of its control surfaces, both as fractions
+    pub fn degraded(&self, thrust: f32, control: f32) -> Self {
+        let mut spec = self.clone();
+        spec.pitch_rate *= control;
+        spec.yaw_rate *= control;
+        spec.roll_rate *= control;
+        spec.aerodynamics.control_power = self.aerodynamics.control_power.map(|c| c * control);
+        spec.thrust = self.thrust.scaled(thrust);
+        spec
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+/// Aerodynamic coefficients of an aircraft.
+/// Forces and moments are these coefficients multiplied by the dynamic pressure and the wing area.
+pub struct Aerodynamics {
+    /// Reference wing area in m^2
+    pub wing_area: f32,
+    /// Increase of the lift coefficient per radian of angle of attack
+    pub lift_slope: f32,
+    /// Angle of attack in radians above which the wing stalls.
+    /// Given in degrees in aircraft files.
+    #[serde(deserialize_with = "degrees")]
+    pub stall_angle: f32,
+    /// Fraction of the maximum lift lost once the wing is fully stalled
+    pub stall_lift_loss: f32,
+    /// Drag coefficient of the aircraft when the wing produces no lift
+    pub zero_lift_drag: f32,
+    /// Drag caused by producing lift, multiplied by the square of the lift coefficient
+    pub induced_drag: f32,
+    /// Side force coefficient per radian of sideslip
+    pub side_force_slope: f32,
+    /// Moment coefficients of the pitch, yaw and roll control surfaces at full deflection
+    pub control_power: [f32; 3],
+    /// How strongly the airflow resists rotation around the pitch, yaw and roll axes
+    pub damping: [f32; 3],
+    /// How strongly the aircraft turns its nose into the airflow, per radian of
+    /// angle of attack (pitch) and sideslip (yaw)
+    pub stability: (f32, f32),
+}
+
+impl Aerodynamics {
+    /// Lift coefficient at the given angle of attack.
+    /// Grows linearly up to the stall angle and drops off past it.
+    pub fn lift_coefficient(&self, angle_of_attack: f32) -> f32 {
+        let alpha = angle_of_attack.abs();
+        let max_lift = self.lift_slope * self.stall_angle;
+        let lift = if alpha <= self.stall_angle {
+            self.lift_slope * alpha
+        } else {
+            let stall_progress = ((alpha - self.stall_angle) / self.stall_angle).min(1.);
+            max_lift * (1. - self.stall_lift_loss * stall_progress)
+        };
+        lift * angle_of_attack.signum()
+    }
+
+    /// Drag coefficient of the aircraft producing the given lift coefficient
+    pub fn drag_coefficient(&self, lift_coefficient: f32) -> f32 {
+        self.zero_lift_drag + self.induced_drag * lift_coefficient.powi(2)
+    }
+}
+
+/// Read an angle given in degrees as radians
+pub fn degrees<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    f32::deserialize(deserializer).map(f32::to_radians)
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "Vec<(f32, f32)>")]
+/// Maximum thrust of the engine (in N) depending on the airspeed (in m/s).
+/// Thrust between the points is interpolated linearly and stays flat past the last one.
+pub struct ThrustCurve {
+    points: Vec<(f32, f32)>,
+}
+
+impl TryFrom<Vec<(f32, f32)>> for ThrustCurve {
+    type Error = String;
+
+    /// Create a curve from `(speed, thrust)` points
+    fn try_from(mut points: Vec<(f32, f32)>) -> Result<Self, Self::Error> {
+        if points.is_empty() {
+            return Err("thrust curve needs at least one point".into());
+        }
+        if points
+            .iter()
+            .any(|&(speed, thrust)| speed < 0. || thrust < 0.)
+        {
+            return Err("thrust curve can't have negative speeds or thrust".into());
+        }
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(ThrustCurve { points })
+    }
+}
+
+impl ThrustCurve {
+    pub fn at(&self, speed: f32) -> f32 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if speed <= first.0 {
+            return first.1;
+        }
+        if speed >= last.0 {
+            return last.1;
+        }
+        let (from, to) = self
+            .points
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .find(|(_, to)| speed < to.0)
+            .expect("Speed within the curve");
+        let progress = (speed - from.0) / (to.0 - from.0);
+        from.1 + (to.1 - from.1) * progress
+    }
+
+    /// The same curve with every point's thrust multiplied by `factor`
+    fn scaled(&self, factor: f32) -> Self {
+        ThrustCurve {
+            points: self.points.iter().map(|&(s, t)| (s, t * factor)).collect(),
+        }
+    }
 }
@@ -3,77 +3,101 @@ use crate::{
         audio::Audio,
         audio_manager::{AudioManager, SoundEffect},
         messages::AudioMessage,
+        sound::SoundID,
     },
     cg::light::{DirectionalLight, PointLight},
-    game::targeting_data::TargetingData,
-    DELTA_TIME, GLFW_TIME, SCR_HEIGHT, SCR_WIDTH,
+    SCR_HEIGHT, SCR_WIDTH,
 };
 use glfw::{ffi::glfwSwapInterval, Context, Glfw, Window, WindowEvent};
-use log::{info, warn};
+use log::info;
 use rayon::ThreadPoolBuilder;
 extern crate glfw;
 use self::glfw::{Action, Key};
 use super::{
-    enemies::Enemies,
-    hud::hud::Hud,
-    missile::{EnemyID, Missile, MissileMessage},
-    missile_guidance::GuidanceStatus,
-    modeled::Modeled,
+    bindings::{Bindings, BindingsError, Button},
+    camera_rig::{CameraMode, CameraRig},
+    clock::GameClock,
+    flight::{aircraft::AircraftKind, blueprint::Blueprints, mouse_aim::ControlMode},
+    hud::hud::{Ammo, Hud},
+    input::{self, InputSnapshot},
+    joystick::Joystick,
+    missile_catalogue::MissileCatalogue,
     particle_generation::ParticleGeneration,
-    player::Player,
+    replay::Replay,
+    rng::GameRng,
+    simulation::Simulation,
+    state::{GameState, StateEvent, Transition},
     targeting_sounds::TargetingSounds,
-    terrain::Terrain,
+    terrain_gen::Biome,
+    world::{World, WorldEvent},
 };
 use crate::{
     c_str,
-    cg::{camera::Movement, model::Model, shader::Shader},
+    cg::{camera::Camera, model::Model, shader::Shader},
     game::{drawable::Drawable, id_gen::IDGenerator},
-    key_pressed,
 };
 use cgmath::{vec3, Deg, EuclideanSpace, Matrix4, Point3, SquareMatrix, Vector3, Vector4};
 use lazy_static::lazy_static;
 use std::{
     ffi::CStr,
-    ops::Not,
+    path::PathBuf,
     sync::{
         mpsc::{self, Receiver},
         Mutex,
     },
 };
 
-pub const TARGET_ENEMIES: usize = 3;
-pub const MISSILE_COOLDOWN: f64 = 0.5;
-pub const SWITCH_COOLDOWN: f64 = 0.5;
-
 lazy_static! {
     pub static ref ID_GENERATOR: Mutex<IDGenerator> = Mutex::new(IDGenerator::default());
 }
 
+/// The windowed frontend of the game. Owns the `Simulation`
+/// together with everything needed to show it to the player and hear it:
+/// the GLFW window, the HUD, lights and audio.
 pub struct Game {
-    player: Player,
-    enemies: Enemies,
-    missiles: Vec<Missile>,
-    terrain: Terrain,
+    simulation: Simulation,
+    state: GameState,
+    /// Input gathered from the window during the current frame
+    snapshot: InputSnapshot,
+    /// Keys which went down during the current frame, before the state and the bindings
+    /// decide what they mean
+    pressed_keys: Vec<Key>,
+    bindings: Bindings,
+    joystick: Joystick,
+    /// Where the bindings are read from. Read again before every takeoff,
+    /// so they can be changed without restarting the game.
+    bindings_path: Option<PathBuf>,
+    /// Frames of a replay being played back instead of the window's input
+    replay: Option<std::vec::IntoIter<InputSnapshot>>,
+    /// Where to save the recording of this session
+    record_path: Option<PathBuf>,
+    last_frame_time: f64,
+    /// Where the cursor was at its previous event, unknown until it moves
+    /// after being captured
+    last_cursor: Option<(f32, f32)>,
     skybox: Model,
-    last_launch_time: f64,
-    last_target_switch_time: f64,
-    targeting_data: Option<TargetingData>,
     targeting_sounds: TargetingSounds,
+    guns_sound: SoundID,
     pub glfw: Glfw,
     pub window: Window,
     pub events: Receiver<(f64, WindowEvent)>,
     audio: Audio,
+    camera_rig: CameraRig,
     hud: Hud,
     directional_light: DirectionalLight,
     point_light: PointLight,
 }
 
 impl Game {
-    pub fn new() -> Self {
+    pub fn new(
+        clock: GameClock,
+        rng: GameRng,
+        blueprints: Blueprints,
+        missile_types: MissileCatalogue,
+        aircraft: &AircraftKind,
+        biome: Option<Biome>,
+    ) -> Self {
         let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
-
-        log4rs::init_file("log_config.yaml", Default::default()).unwrap();
-        info!("Initialized log4rs");
         info!("Initialized GLFW");
 
         glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
@@ -96,7 +120,8 @@ impl Game {
         window.set_framebuffer_size_polling(true);
         window.set_cursor_pos_polling(true);
         window.set_scroll_polling(true);
-        window.set_cursor_mode(glfw::CursorMode::Disabled);
+        window.set_key_polling(true);
+        window.set_mouse_button_polling(true);
 
         unsafe {
             glfwSwapInterval(0);
@@ -118,20 +143,16 @@ impl Game {
             AudioManager::run(rx);
         });
 
-        let mut terrain = Terrain::default();
-        terrain.model.set_translation(vec3(0.0, -150., 0.0));
-
-        let mut player = Player::default();
+        let mut world = World::new(rng, blueprints, missile_types, aircraft, biome);
         audio.play(SoundEffect::CockpitAmbient, true);
 
-        player
+        world
+            .player_mut()
             .cockpit_mut()
             .set_translation(vec3(0.0, -0.3, 0.0))
             .set_scale(0.5)
             .rotate(Vector3::unit_y(), Deg(-90.));
 
-        let enemies = Enemies::default();
-
         let mut skybox = Model::new("resources/objects/skybox/skybox.obj");
         skybox.set_scale(1000.);
 
@@ -139,102 +160,294 @@ impl Game {
 
         let point_light = PointLight::new(Point3::new(0., 0.1, 0.3));
 
+        let camera_rig = CameraRig::new(&world);
         let hud = Hud::new();
 
-        let targeting_data = None;
-
         let mut targeting_sounds = TargetingSounds::new();
         targeting_sounds.play(SoundEffect::Seeking, &audio);
 
-        Game {
-            player,
-            enemies,
-            missiles: vec![],
-            terrain,
+        let mut game = Game {
+            simulation: Simulation::new(world, clock),
+            state: GameState::Menu,
+            snapshot: InputSnapshot::default(),
+            pressed_keys: vec![],
+            bindings: Bindings::default(),
+            joystick: Joystick::default(),
+            bindings_path: None,
+            replay: None,
+            record_path: None,
+            last_frame_time: glfw.get_time(),
+            last_cursor: None,
             skybox,
-            last_target_switch_time: glfw.get_time() - SWITCH_COOLDOWN,
-            last_launch_time: glfw.get_time() - MISSILE_COOLDOWN,
-            targeting_data,
             targeting_sounds,
+            guns_sound: SoundID::MAX,
             glfw,
             window,
             events,
             audio,
+            camera_rig,
             hud,
             directional_light,
             point_light,
+        };
+        game.enter(GameState::Menu);
+        game
+    }
+
+    /// Record this session and save the replay to `path` on exit
+    pub fn start_recording(&mut self, path: PathBuf) {
+        info!("Recording replay to {path:?}");
+        self.simulation.start_recording();
+        self.record_path = Some(path);
+    }
+
+    /// Read the key and mouse bindings from `path` now and before every takeoff
+    pub fn load_bindings(&mut self, path: PathBuf) {
+        self.bindings_path = Some(path);
+        self.reload_bindings();
+    }
+
+    fn reload_bindings(&mut self) {
+        let Some(path) = &self.bindings_path else {
+            return;
+        };
+        match Bindings::load(path) {
+            Ok(bindings) => {
+                if bindings != self.bindings {
+                    info!("Loaded key bindings from {path:?}");
+                }
+                self.bindings = bindings;
+            }
+            Err(BindingsError::Io(_, e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.bindings = Bindings::default();
+            }
+            Err(e) => log::error!("Keeping the current key bindings: {e}"),
         }
     }
 
-    /// Compute new positions of all game objects based on input and state of the game
-    pub fn update(&mut self) {
-        // terrain collisions
-        if self.player.camera().altitude() < self.terrain.height_at(&self.player.camera().xz_ints())
-        {
-            log::error!("Collision!");
-            std::process::exit(1);
+    /// Drive the game with the frames of a replay instead of the window's input.
+    /// Playback starts in flight right away and the window closes once the replay ends.
+    pub fn play_replay(&mut self, replay: Replay) {
+        info!("Playing back replay of {} frames", replay.frames.len());
+        self.replay = Some(replay.frames.into_iter());
+        self.enter(GameState::Flight);
+    }
+
+    /// Let the current state react to an event. Returns whether the event was used up.
+    fn transition(&mut self, event: StateEvent) -> bool {
+        match self.state.handle(event) {
+            Transition::Ignored => false,
+            Transition::Handled => true,
+            Transition::To(state) => {
+                // Taking off again after a crash goes through the simulation's input,
+                // so that replays see it too
+                if self.state == GameState::Briefing && self.world().crashed() {
+                    self.snapshot.pressed.push(input::Action::Respawn);
+                }
+                self.enter(state);
+                true
+            }
+            Transition::Quit => {
+                self.window.set_should_close(true);
+                true
+            }
         }
+    }
 
-        self.player.apply_controls();
-        self.player.aircraft_mut().apply_decay();
-        self.respawn_enemies();
-        self.enemies.map.values_mut().for_each(|e| {
-            e.fly(&self.terrain);
-        });
-        let shot_down = self.update_missiles();
-        self.enemies.map.retain(|id, _| !shot_down.contains(id));
-        self.missiles.iter_mut().for_each(|m| {
-            let position = m.model().position();
-            let front = m.model().front();
-            m.particle_generator_mut()
-                .update_particles(position, 1, front);
-        });
-        self.missiles
-            .retain(|m| !matches!(m.guidance, GuidanceStatus::None(0)));
-        self.player.aircraft_mut().guns_mut().update();
-        if let Some(hit_enemies) = self
-            .player
-            .aircraft()
-            .guns()
-            .check_collisions(&self.enemies)
-        {
-            self.enemies.map.retain(|id, _| !hit_enemies.contains(id));
-            self.targeting_data = None;
+    /// Switch to `state` and tell the player what they can do in it
+    fn enter(&mut self, state: GameState) {
+        self.state = state;
+        self.simulation.set_paused(!state.simulates());
+        if state.simulates() {
+            self.audio.resume_all();
+        } else {
+            self.audio.pause_all();
+        }
+        // The cursor steers the aircraft in flight and is free everywhere else
+        if state == GameState::Flight {
+            self.window.set_cursor_mode(glfw::CursorMode::Disabled);
+            self.last_cursor = None;
+        } else {
+            self.window.set_cursor_mode(glfw::CursorMode::Normal);
         }
-        self.update_targeting();
-        self.hud
-            .update(self.player.camera(), &self.enemies, &self.targeting_data);
+        let text = match state {
+            GameState::Menu => "danger-zone".to_string(),
+            GameState::Briefing => {
+                self.reload_bindings();
+                let world = self.world();
+                let sortie = if world.crashed() {
+                    "New sortie"
+                } else {
+                    "Sortie in progress"
+                };
+                format!(
+                    "{sortie}: {} with {} loadout over the {}, {} enemies in the air",
+                    world.player().aircraft().kind(),
+                    world.loadout(),
+                    world.terrain().biome(),
+                    world.enemies().map.len()
+                )
+            }
+            GameState::Flight => "Fight's on".to_string(),
+            GameState::Paused => "Game paused".to_string(),
+            GameState::Debrief => format!("Sortie over: {}", self.world().score()),
+        };
+        info!("{state}: {text} ({})", state.controls());
+        self.window
+            .set_title(&format!("danger-zone - {state} - {}", state.controls()));
     }
 
-    /// Make the Enemies struct check for missing enemies and respawn them
-    pub fn respawn_enemies(&mut self) {
-        self.enemies.respawn_enemies();
+    /// Choose the next loadout before takeoff.
+    /// Goes through the simulation's input without stepping it, so that replays see it too.
+    fn cycle_loadout(&mut self) {
+        self.simulation.advance(&InputSnapshot {
+            pressed: vec![input::Action::CycleLoadout],
+            ..InputSnapshot::default()
+        });
+        self.enter(GameState::Briefing);
     }
 
-    /// If there's an enemy being targeted, countdown the lock time
-    fn update_targeting(&mut self) {
-        match &mut self.targeting_data {
-            // no lock yet
-            Some(data) if data.left_until_lock > 0. => {
-                data.left_until_lock -= unsafe { DELTA_TIME as f64 };
-                if data.left_until_lock < 0. {
-                    self.targeting_sounds.play(SoundEffect::Locked, &self.audio);
-                }
+    /// Step the simulation with the input gathered this frame
+    /// and let the frontend react to what happened
+    pub fn update(&mut self) {
+        let now = self.glfw.get_time();
+        let delta = (now - self.last_frame_time) as f32;
+        self.last_frame_time = now;
+
+        for key in std::mem::take(&mut self.pressed_keys) {
+            if self.transition(StateEvent::Pressed(key)) {
+                continue;
+            }
+            if let Some(action) = self.bindings.action(Button::Key(key)) {
+                self.snapshot.pressed.push(action);
             }
-            _ => {}
         }
-        if let Some(data) = &self.targeting_data {
-            if self
-                .player
-                .targetable_enemies(&self.enemies)
-                .unwrap_or(vec![])
-                .contains(&data.target_id)
-                .not()
+        let mut snapshot = std::mem::take(&mut self.snapshot);
+        snapshot.delta = delta;
+        if !self.state.simulates() {
+            if self.state == GameState::Briefing
+                && snapshot.pressed.contains(&input::Action::CycleLoadout)
             {
-                warn!("Target lost");
+                self.cycle_loadout();
+            }
+            return;
+        }
+        if self.state != GameState::Flight {
+            // Outside of flight the player's input doesn't reach the aircraft
+            snapshot = InputSnapshot {
+                delta,
+                pressed: snapshot.pressed,
+                ..InputSnapshot::default()
+            };
+        }
+
+        if let Some(replay) = &mut self.replay {
+            match replay.next() {
+                Some(recorded) => {
+                    if recorded.pressed.contains(&input::Action::Respawn) {
+                        self.transition(StateEvent::ReplayRespawned);
+                    }
+                    snapshot = recorded;
+                }
+                None => {
+                    info!("Replay finished");
+                    self.transition(StateEvent::ReplayFinished);
+                    return;
+                }
+            }
+        }
+
+        // The camera is the frontend's business, the simulation never sees the mouse
+        // orbiting it. Replays still switch cameras like the player did.
+        if snapshot.pressed.contains(&input::Action::CycleCamera) {
+            self.camera_rig.cycle();
+        }
+        if self.camera_rig.mode() == CameraMode::Orbit {
+            self.camera_rig.look(std::mem::take(&mut snapshot.cursor));
+        }
+        if snapshot.scroll != 0. {
+            self.camera_rig.zoom(snapshot.scroll);
+        }
+
+        let events = self.simulation.advance(&snapshot);
+        let sortie_over = events
+            .iter()
+            .any(|e| matches!(e, WorldEvent::TerrainCollision | WorldEvent::WreckCrashed));
+        events.into_iter().for_each(|e| self.handle_event(e));
+        if sortie_over {
+            self.transition(StateEvent::SortieEnded);
+        }
+        let world = self.simulation.world();
+        self.camera_rig.update(world, snapshot.delta);
+        self.hud.update(
+            self.camera_rig.view(),
+            world.enemies(),
+            world.targeting_data(),
+            (*world.control_mode() == ControlMode::MouseAim).then(|| *world.player().aim()),
+            &ammo(world),
+            self.simulation.clock().time(),
+        );
+        let incoming: Vec<_> = world.incoming_missiles().map(|m| m.position()).collect();
+        self.hud.update_warnings(
+            self.camera_rig.view(),
+            &incoming,
+            world.player().aircraft().damage().integrity(),
+        );
+    }
+
+    /// Play the sounds accompanying an event coming from the simulation
+    fn handle_event(&mut self, event: WorldEvent) {
+        match event {
+            WorldEvent::GunsStarted => {
+                self.guns_sound = self.audio.play(SoundEffect::Guns, true);
+            }
+            WorldEvent::GunsStopped => self.audio.stop(self.guns_sound),
+            WorldEvent::MissileLaunched => {
+                self.audio.play(SoundEffect::MissileLaunch, false);
                 self.targeting_sounds
                     .play(SoundEffect::Seeking, &self.audio);
-                self.targeting_data = None;
+            }
+            WorldEvent::LockingStarted => {
+                self.targeting_sounds
+                    .play(SoundEffect::Locking, &self.audio);
+            }
+            WorldEvent::TargetLocked => {
+                self.targeting_sounds.play(SoundEffect::Locked, &self.audio);
+            }
+            WorldEvent::TargetLost => {
+                self.targeting_sounds
+                    .play(SoundEffect::Seeking, &self.audio);
+            }
+            WorldEvent::EnemyDestroyed(id) => info!("Enemy {id} destroyed"),
+            WorldEvent::WaveStarted(wave) => info!("Wave {wave} incoming"),
+            WorldEvent::StallStarted => {
+                let flight = self.world().player().aircraft().flight();
+                info!(
+                    "Stall at {:.0} m/s, angle of attack {:.1} deg",
+                    flight.speed(),
+                    flight.angle_of_attack().to_degrees()
+                );
+                self.audio.play(SoundEffect::Beep, false);
+            }
+            WorldEvent::StallRecovered => info!("Recovered from stall"),
+            WorldEvent::TerrainCollision => {
+                log::error!("Collision!");
+                self.audio.play(SoundEffect::Beep, false);
+            }
+            WorldEvent::PlayerHit => {
+                let health = self.world().player().aircraft().damage().health();
+                info!("Hit! {health:.0} health left");
+            }
+            WorldEvent::ShotDown => {
+                log::error!("Shot down!");
+                self.audio.play(SoundEffect::Beep, false);
+            }
+            WorldEvent::WreckCrashed => info!("The wreck went down"),
+            WorldEvent::PlayerRespawned => info!("Taking off again"),
+            WorldEvent::MissileDecoyed(id) => info!("Missile {id} went for a decoy"),
+            WorldEvent::MissileIncoming(id) => {
+                log::warn!("Missile {id} incoming!");
+                self.audio.play(SoundEffect::Beep, false);
             }
         }
     }
@@ -243,15 +456,33 @@ impl Game {
         gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
         no_light_shader.use_program();
         self.setup_camera(no_light_shader);
-        self.missiles.iter_mut().for_each(|m| {
+        self.world_mut().missiles_mut().iter_mut().for_each(|m| {
             m.draw_particles(no_light_shader);
         });
+        self.world_mut().decoys_mut().iter_mut().for_each(|d| {
+            d.draw_particles(no_light_shader);
+        });
+        self.world_mut().explosions_mut().iter_mut().for_each(|e| {
+            e.draw_particles(no_light_shader);
+        });
+        // Smoke trailing from damaged aircraft
+        self.world_mut()
+            .player_mut()
+            .aircraft_mut()
+            .draw_particles(no_light_shader);
+        self.world_mut()
+            .enemies_mut()
+            .map
+            .values_mut()
+            .for_each(|e| {
+                e.aircraft_mut().draw_particles(no_light_shader);
+            });
         shader.use_program();
         //set light position and properties
         self.setup_directional_light(shader);
         //point light
 
-        if let Some(data) = &self.targeting_data {
+        if let Some(data) = self.world().targeting_data() {
             if data.left_until_lock <= 0. {
                 self.point_light.diffuse.x = 0.;
                 self.point_light.diffuse.y = 1.;
@@ -269,25 +500,39 @@ impl Game {
         self.setup_camera(shader);
 
         // Drawing game objects starts here
-        self.terrain.draw(shader);
+        let world = self.simulation.world();
+        world.terrain().draw(shader);
         self.skybox.draw(shader);
-        self.enemies.map.values_mut().for_each(|e| {
+        world.enemies().map.values().for_each(|e| {
             e.aircraft.draw(shader);
+            e.aircraft().guns().draw(shader);
         });
-        self.missiles.iter_mut().for_each(|m| {
+        world.missiles().iter().for_each(|m| {
             m.draw(shader);
         });
-        self.player.aircraft().guns().draw(shader);
+        world.player().aircraft().guns().draw(shader);
+        if !self.camera_rig.in_cockpit() && !world.crashed() {
+            world.player().aircraft().draw(shader);
+        }
 
         shader.use_program();
-        let time = self.glfw.get_time() as f32 * 2.0;
-        self.player.cockpit_mut().set_translation(vec3(
-            time.sin() * 0.003,
-            time.cos().sin() * 0.003 - 0.31,
-            time.cos() * 0.003,
-        ));
+        // Game time, so that the cockpit holds still while paused
+        let time = self.simulation.clock().time() as f32 * 2.0;
+        self.world_mut()
+            .player_mut()
+            .cockpit_mut()
+            .set_translation(vec3(
+                time.sin() * 0.003,
+                time.cos().sin() * 0.003 - 0.31,
+                time.cos() * 0.003,
+            ));
         shader.set_mat4(c_str!("view"), &Matrix4::identity());
-        self.player.cockpit.draw(shader);
+        if !self.world().crashed() && self.state.shows_cockpit() && self.camera_rig.in_cockpit() {
+            self.world().player().cockpit.draw(shader);
+        }
+        if !self.state.shows_cockpit() {
+            return;
+        }
 
         //Drawing hud
         no_light_shader.use_program();
@@ -295,7 +540,8 @@ impl Game {
         self.hud.draw(no_light_shader);
     }
 
-    pub fn process_events(&mut self, first_mouse: &mut bool, last_x: &mut f32, last_y: &mut f32) {
+    /// Gather mouse movement, scroll and single key presses into this frame's input
+    pub fn process_events(&mut self) {
         for (_, event) in glfw::flush_messages(&self.events) {
             match event {
                 glfw::WindowEvent::FramebufferSize(width, height) => unsafe {
@@ -303,167 +549,80 @@ impl Game {
                 },
                 glfw::WindowEvent::CursorPos(xpos, ypos) => {
                     let (xpos, ypos) = (xpos as f32, ypos as f32);
-                    if *first_mouse {
-                        *last_x = xpos;
-                        *last_y = ypos;
-                        *first_mouse = false;
-                    }
+                    let (last_x, last_y) = self.last_cursor.unwrap_or((xpos, ypos));
 
-                    let xoffset = xpos - *last_x;
+                    let xoffset = xpos - last_x;
                     // reversed since y-coordinates go from bottom to top
-                    let yoffset = *last_y - ypos;
+                    let yoffset = last_y - ypos;
 
-                    *last_x = xpos;
-                    *last_y = ypos;
+                    self.last_cursor = Some((xpos, ypos));
 
-                    self.player
-                        .camera_mut()
-                        .process_mouse_movement(xoffset, yoffset);
+                    let (look, zoom) = self.bindings.mouse_motion(xoffset, yoffset, 0.);
+                    self.snapshot.cursor.0 += look.0;
+                    self.snapshot.cursor.1 += look.1;
+                    self.snapshot.scroll += zoom;
                 }
                 glfw::WindowEvent::Scroll(_xoffset, yoffset) => {
-                    self.player
-                        .camera_mut()
-                        .process_mouse_scroll(yoffset as f32);
+                    let (look, zoom) = self.bindings.mouse_motion(0., 0., yoffset as f32);
+                    self.snapshot.cursor.0 += look.0;
+                    self.snapshot.cursor.1 += look.1;
+                    self.snapshot.scroll += zoom;
+                }
+                glfw::WindowEvent::Key(key, _, Action::Press, _) => self.pressed_keys.push(key),
+                glfw::WindowEvent::MouseButton(button, Action::Press, _)
+                    if self.state == GameState::Flight =>
+                {
+                    if let Some(action) = self.bindings.action(Button::Mouse(button)) {
+                        self.snapshot.pressed.push(action);
+                    }
                 }
                 _ => {}
             }
         }
     }
 
-    /// First level of controls. Captures pressed keys and calls appropriate functions.
-    /// Additionaly, set all decays on the aircraft as true.
+    /// First level of controls. Captures the actions whose keys or buttons are held down
+    /// this frame and the position of the joystick.
     pub fn process_key(&mut self) {
-        self.player_mut()
-            .aircraft_mut()
-            .controls_mut()
-            .set_all_decays(true);
-        key_pressed!(self.window, Key::Escape, self.window.set_should_close(true));
-        key_pressed!(
-            self.window,
-            Key::W,
-            self.player.process_key(Movement::PitchDown)
-        );
-        key_pressed!(
-            self.window,
-            Key::S,
-            self.player.process_key(Movement::PitchUp)
-        );
-        key_pressed!(
-            self.window,
-            Key::A,
-            self.player.process_key(Movement::RollLeft)
-        );
-        key_pressed!(
-            self.window,
-            Key::D,
-            self.player.process_key(Movement::RollRight)
-        );
-        key_pressed!(
-            self.window,
-            Key::E,
-            self.player.process_key(Movement::YawRight)
-        );
-        key_pressed!(
-            self.window,
-            Key::Q,
-            self.player.process_key(Movement::YawLeft)
-        );
-        key_pressed!(
-            self.window,
-            Key::LeftShift,
-            self.player.process_key(Movement::ThrottleUp)
-        );
-        key_pressed!(
-            self.window,
-            Key::LeftControl,
-            self.player.process_key(Movement::ThrottleDown)
-        );
-        key_pressed!(self.window, Key::M, {
-            if !self.player.aircraft().guns().firing {
-                self.player.guns_sound = self.audio.play(SoundEffect::Guns, true);
-            }
-            self.fire_guns();
-        });
-        if self.window.get_key(Key::M) == Action::Release && self.player.aircraft().guns().firing {
-            self.audio.stop(self.player.guns_sound);
-            self.player.aircraft_mut().guns_mut().stop_firing();
-        }
-        key_pressed!(self.window, Key::Space, self.launch_missile());
-        key_pressed!(self.window, Key::K, self.switch_target());
-    }
-
-    pub fn player_mut(&mut self) -> &mut Player {
-        &mut self.player
-    }
-
-    pub fn switch_target(&mut self) {
-        if self.last_target_switch_time + SWITCH_COOLDOWN > self.glfw.get_time() {
+        if self.state != GameState::Flight {
             return;
         }
-        if let Some(new_id) = self.player.targeted_enemy_id_nth(&self.enemies, 0) {
-            self.targeting_data = Some(TargetingData::new(new_id));
-            self.targeting_sounds
-                .play(SoundEffect::Locking, &self.audio);
-        }
-        self.last_target_switch_time = self.glfw.get_time();
+        self.snapshot.held = self.bindings.held(|button| match button {
+            Button::Key(key) => self.window.get_key(key) == Action::Press,
+            Button::Mouse(button) => self.window.get_mouse_button(button) == Action::Press,
+        });
+        self.snapshot.stick = self.joystick.read(&self.glfw, self.bindings.joystick());
     }
 
-    /// Perform all actions necessary to launch a missile.
-    /// The game keeps track of the time of last missile launch
-    /// and doesn't let the player do it again before a specified time has passed.
-    /// Modify MISSILE_COOLDOWN to adjust.
-    pub fn launch_missile(&mut self) {
-        if self.last_launch_time + MISSILE_COOLDOWN > self.glfw.get_time() {
-            return;
-        }
-        if let Some(data) = &self.targeting_data {
-            if data.left_until_lock > 0. {
-                warn!("No lock");
-                self.last_launch_time = unsafe { GLFW_TIME };
-                return;
+    /// Save the recording of this session, if there is one
+    fn save_recording(&self) {
+        if let (Some(recording), Some(path)) = (self.simulation.recording(), &self.record_path) {
+            if let Err(e) = recording.save(path) {
+                log::error!("Failed to save replay to {path:?}: {e}");
             }
-            let enemy = self.enemies.get_by_id(data.target_id);
-            let missile = Missile::new(self.player.camera(), enemy);
-            self.missiles.push(missile);
-
-            self.audio.play(SoundEffect::MissileLaunch, false);
-            self.targeting_sounds
-                .play(SoundEffect::Seeking, &self.audio);
-
-            self.last_launch_time = unsafe { GLFW_TIME };
-            self.targeting_data = None;
         }
     }
 
-    /// Give the missiles a reference to the Enemy they are currently
-    /// targeting so they can mutate their state accordingly.
-    /// Returns a vector of IDs of shot down enemies
-    pub fn update_missiles(&mut self) -> Vec<EnemyID> {
-        let mut shot_down = Vec::with_capacity(self.missiles.len());
-        self.missiles.iter_mut().for_each(|missile| {
-            let enemy = missile
-                .target()
-                .and_then(|id| self.enemies.get_mut_by_id(id))
-                .or(None);
-            if let Some(MissileMessage::HitEnemy(id)) = missile.update(enemy.as_deref()) {
-                shot_down.push(id);
-                self.targeting_data = None;
-            }
-        });
-        shot_down
+    fn world(&self) -> &World {
+        self.simulation.world()
     }
 
-    pub fn fire_guns(&mut self) {
-        let camera = self.player.camera().clone();
-        self.player.aircraft_mut().fire_guns(&camera);
+    fn world_mut(&mut self) -> &mut World {
+        self.simulation.world_mut()
+    }
+
+    /// Camera the world is seen through
+    fn camera(&self) -> &Camera {
+        self.camera_rig.view()
     }
 
     pub fn exit_hook(&mut self) {
+        self.save_recording();
         self.audio.exit_hook();
     }
 
     pub unsafe fn setup_directional_light(&self, shader: &Shader) {
-        shader.set_vector3(c_str!("viewPos"), &self.player.camera().position().to_vec());
+        shader.set_vector3(c_str!("viewPos"), &self.camera().position().to_vec());
         shader.set_vector3(
             c_str!("dirLight.direction"),
             &self.directional_light.direction,
@@ -492,10 +651,22 @@ impl Game {
     }
 
     pub unsafe fn setup_camera(&self, shader: &Shader) {
-        shader.set_mat4(
-            c_str!("projection"),
-            self.player.camera().projection_matrix(),
-        );
-        shader.set_mat4(c_str!("view"), &self.player.camera().view_matrix());
+        shader.set_mat4(c_str!("projection"), self.camera().projection_matrix());
+        shader.set_mat4(c_str!("view"), &self.camera().view_matrix());
+    }
+}
+
+/// Weapons the player has left, for the HUD
+fn ammo(world: &World) -> Ammo {
+    let aircraft = world.player().aircraft();
+    let missiles = world
+        .selected_missile()
+        .as_ref()
+        .and_then(|kind| aircraft.missiles().get(kind).copied())
+        .unwrap_or(0);
+    let guns = aircraft.guns();
+    Ammo {
+        missiles,
+        gun_rounds: guns.rounds() as f32 / guns.capacity().max(1) as f32,
     }
 }
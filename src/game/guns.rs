@@ -1,16 +1,29 @@
-use super::{drawable::Drawable, enemies::Enemies, flight::steerable::Steerable, missile::EnemyID};
-use crate::{
-    cg::{camera::Camera, model::Model},
-    DELTA_TIME, GLFW_TIME,
+use super::{
+    clock::FrameTime,
+    drawable::Drawable,
+    enemies::Enemies,
+    flight::{dynamics::Pose, steerable::Steerable},
+    missile::EnemyID,
+    rebase::Rebase,
+};
+use crate::cg::model::Model;
+use cgmath::{
+    Deg, EuclideanSpace, InnerSpace, MetricSpace, Point3, Quaternion, Rotation3, Vector3,
 };
-use cgmath::{Deg, EuclideanSpace, MetricSpace, Quaternion, Rotation3, Vector3};
 use itertools::Itertools;
-use log::info;
-use rand::{thread_rng, Rng};
+use log::{info, warn};
+use rand::Rng;
+use std::collections::BTreeMap;
 
 const BULLET_SPEED: f32 = 1000.;
 const BULLET_TERMINATION_TIME: f64 = 3.;
 const BULLET_COOLDOWN: f64 = 0.02;
+/// Weight of one round in kg
+pub const ROUND_MASS: f32 = 0.35;
+/// How close in m a bullet has to pass to an aircraft to hit it
+const HIT_RADIUS: f32 = 2.;
+/// Damage a round does to the aircraft it hits
+pub const BULLET_DAMAGE: f32 = 10.;
 
 #[derive(Debug)]
 pub struct Bullet {
@@ -26,35 +39,77 @@ pub struct Guns {
     bullets: Vec<Bullet>,
     last_fire_time: f64,
     pub firing: bool,
+    /// Gun positions relative to the aircraft's pose along the right, up and front axes
+    mounts: Vec<Vector3<f32>>,
+    /// Mount the next bullet is fired from. The guns take turns.
+    next_mount: usize,
+    rounds: u32,
+    /// Rounds the guns hold when fully loaded
+    capacity: u32,
 }
 
 impl Guns {
-    pub fn new() -> Self {
+    pub fn new(mounts: Vec<Vector3<f32>>, rounds: u32) -> Self {
+        assert!(!mounts.is_empty(), "Guns need at least one mount");
         Self {
             bullets: Vec::with_capacity(1000),
             last_fire_time: 0.,
             firing: false,
+            mounts,
+            next_mount: 0,
+            rounds,
+            capacity: rounds,
         }
     }
 
-    pub fn fire(&mut self, camera: &Camera) {
-        let time = unsafe { GLFW_TIME };
+    /// Rounds left
+    pub fn rounds(&self) -> u32 {
+        self.rounds
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Weight in kg of the rounds left
+    pub fn mass(&self) -> f32 {
+        self.rounds as f32 * ROUND_MASS
+    }
+
+    /// Fill the guns up again
+    pub fn reload(&mut self) {
+        self.rounds = self.capacity;
+    }
+
+    /// Fire a round from the next gun of an aircraft placed at `pose`
+    pub fn fire(&mut self, pose: &Pose, frame: &FrameTime, rng: &mut impl Rng) {
+        let time = frame.time;
         if self.last_fire_time + BULLET_COOLDOWN > time {
             return;
         }
-        let position = camera.position().to_vec() + camera.right * 2.5 + camera.up * -1.5;
+        if self.rounds == 0 {
+            if self.firing {
+                warn!("Out of gun rounds");
+            }
+            self.firing = false;
+            return;
+        }
+        let mount = self.mounts[self.next_mount];
+        self.next_mount = (self.next_mount + 1) % self.mounts.len();
+        let position = pose.relative(mount).to_vec();
         let rand_quat = {
             let rands: (Deg<f32>, Deg<f32>, Deg<f32>) = (0..3)
-                .map(|_| Deg(thread_rng().gen_range(-0.3, 0.3)))
+                .map(|_| Deg(rng.gen_range(-0.3, 0.3)))
                 .collect_tuple()
                 .unwrap();
             Quaternion::from_angle_x(rands.0)
                 * Quaternion::from_angle_y(rands.1)
                 * Quaternion::from_angle_z(rands.2)
         };
-        let orientation = camera.orientation_quat() * rand_quat;
-        self.bullets.push(Bullet::new(position, orientation));
+        let orientation = pose.orientation * rand_quat;
+        self.bullets.push(Bullet::new(position, orientation, time));
         self.last_fire_time = time;
+        self.rounds -= 1;
         self.firing = true;
     }
 
@@ -62,29 +117,58 @@ impl Guns {
         self.firing = false
     }
 
-    pub fn update(&mut self) {
+    pub fn update(&mut self, frame: &FrameTime) {
         self.bullets.iter_mut().for_each(|b| {
-            b.update();
+            b.update(frame.delta);
         });
-        self.bullets
-            .retain(|b| b.termination_time > unsafe { GLFW_TIME });
+        self.bullets.retain(|b| b.termination_time > frame.time);
     }
 
-    pub fn check_collisions(&self, enemies: &Enemies) -> Option<Vec<EnemyID>> {
-        let mut hit_enemies = Vec::with_capacity(enemies.map.len());
-        for enemy in enemies.map.values() {
-            for bullet in &self.bullets {
-                if enemy.position().distance(bullet.model.position()) < 2. {
-                    info!("Hit enemy {}", enemy.id());
-                    hit_enemies.push(enemy.id());
-                }
-            }
-        }
-        if !hit_enemies.is_empty() {
-            Some(hit_enemies)
-        } else {
-            None
+    /// Count the bullets which hit each enemy still in the air during the last step
+    /// of `delta_time` seconds. Bullets which hit are used up.
+    pub fn check_collisions(
+        &mut self,
+        enemies: &Enemies,
+        delta_time: f32,
+    ) -> BTreeMap<EnemyID, u32> {
+        enemies
+            .map
+            .values()
+            .filter(|e| !e.aircraft().destroyed())
+            .filter_map(|e| {
+                let hits = self.take_hits(e.position(), delta_time);
+                (hits > 0).then_some((e.id(), hits))
+            })
+            .collect()
+    }
+
+    /// Count the bullets which passed close enough to an aircraft at `position`
+    /// during the last step of `delta_time` seconds. Bullets which hit are used up.
+    pub fn take_hits(&mut self, position: Point3<f32>, delta_time: f32) -> u32 {
+        let before = self.bullets.len();
+        self.bullets.retain(|b| {
+            let end = b.model.position();
+            let path = b.model.front() * BULLET_SPEED * delta_time;
+            let start = end - path;
+            let length = path.magnitude2();
+            let along = if length > f32::EPSILON {
+                ((position - start).dot(path) / length).clamp(0., 1.)
+            } else {
+                0.
+            };
+            (start + path * along).distance(position) >= HIT_RADIUS
+        });
+        let hits = (before - self.bullets.len()) as u32;
+        if hits > 0 {
+            info!("{hits} bullets hit");
         }
+        hits
+    }
+}
+
+impl Rebase for Guns {
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        self.bullets.iter_mut().for_each(|b| b.model.rebase(shift));
     }
 }
 
@@ -95,17 +179,16 @@ impl Drawable for Guns {
 }
 
 impl Bullet {
-    pub fn new(position: Vector3<f32>, orientation: Quaternion<f32>) -> Self {
+    pub fn new(position: Vector3<f32>, orientation: Quaternion<f32>, time: f64) -> Self {
         let mut model = Model::new("resources/objects/bullet/bullet.obj");
         model.set_translation(position);
         model.set_orientation(orientation);
         Self {
             model,
-            termination_time: unsafe { GLFW_TIME + BULLET_TERMINATION_TIME },
+            termination_time: time + BULLET_TERMINATION_TIME,
         }
     }
-    fn update(&mut self) {
-        let delta_time = unsafe { DELTA_TIME };
+    fn update(&mut self, delta_time: f32) {
         self.model.forward(BULLET_SPEED * delta_time);
     }
 }
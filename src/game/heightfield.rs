@@ -0,0 +1,149 @@
+use cgmath::{vec2, vec3, InnerSpace, Point2, Vector2, Vector3};
+
+/// Upper limit of samples along each side of a heightfield
+const MAX_SAMPLES: usize = 2048;
+
+/// Heights of a terrain mesh sampled on a regular grid.
+/// Built once from the mesh triangles, so that the height of any point can be
+/// looked up and interpolated quickly, no matter how the mesh is laid out.
+/// All coordinates are in the mesh's local space.
+#[derive(Debug, Clone)]
+pub struct Heightfield {
+    /// Smallest x and z covered by the grid
+    origin: Point2<f32>,
+    /// Distance between neighbouring samples along x and z
+    cell: Vector2<f32>,
+    /// Number of samples along x and z
+    samples: (usize, usize),
+    /// Heights stored row by row, rows going along z
+    heights: Vec<f32>,
+}
+
+impl Heightfield {
+    /// Sample the triangles of a mesh. `indices` hold three vertex indices per triangle.
+    /// The grid gets roughly as many samples as the mesh has vertices.
+    pub fn from_mesh(positions: &[Vector3<f32>], indices: &[u32]) -> Self {
+        assert!(!positions.is_empty(), "Heightfield of an empty mesh");
+        let (min, max) = positions.iter().fold(
+            (
+                vec2(f32::INFINITY, f32::INFINITY),
+                vec2(f32::NEG_INFINITY, f32::NEG_INFINITY),
+            ),
+            |(min, max), p| {
+                (
+                    vec2(min.x.min(p.x), min.y.min(p.z)),
+                    vec2(max.x.max(p.x), max.y.max(p.z)),
+                )
+            },
+        );
+        let lowest = positions.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let side = ((positions.len() as f32).sqrt().ceil() as usize).clamp(2, MAX_SAMPLES);
+        let samples = (side, side);
+        let extent = max - min;
+        let cell = vec2(
+            (extent.x / (side - 1) as f32).max(f32::EPSILON),
+            (extent.y / (side - 1) as f32).max(f32::EPSILON),
+        );
+
+        let mut heights = vec![f32::NEG_INFINITY; side * side];
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+            let to_sample = |v: f32, origin: f32, cell: f32| (v - origin) / cell;
+            let (x_from, x_to) = (
+                to_sample(a.x.min(b.x).min(c.x), min.x, cell.x)
+                    .ceil()
+                    .max(0.) as usize,
+                to_sample(a.x.max(b.x).max(c.x), min.x, cell.x).floor() as usize,
+            );
+            let (z_from, z_to) = (
+                to_sample(a.z.min(b.z).min(c.z), min.y, cell.y)
+                    .ceil()
+                    .max(0.) as usize,
+                to_sample(a.z.max(b.z).max(c.z), min.y, cell.y).floor() as usize,
+            );
+            for z in z_from..=z_to.min(side - 1) {
+                for x in x_from..=x_to.min(side - 1) {
+                    let point = vec2(min.x + x as f32 * cell.x, min.y + z as f32 * cell.y);
+                    if let Some(height) = height_in_triangle(point, a, b, c) {
+                        let sample = &mut heights[z * side + x];
+                        // Where triangles overlap, the top one is the ground
+                        *sample = sample.max(height);
+                    }
+                }
+            }
+        }
+        // Holes in the mesh are filled with its lowest point
+        heights
+            .iter_mut()
+            .filter(|h| h.is_infinite())
+            .for_each(|h| *h = lowest);
+
+        Heightfield {
+            origin: Point2::new(min.x, min.y),
+            cell,
+            samples,
+            heights,
+        }
+    }
+
+    /// Smallest and largest x and z covered by the heightfield
+    pub fn extent(&self) -> (Point2<f32>, Point2<f32>) {
+        let size = vec2(
+            self.cell.x * (self.samples.0 - 1) as f32,
+            self.cell.y * (self.samples.1 - 1) as f32,
+        );
+        (self.origin, self.origin + size)
+    }
+
+    /// Height of the ground at the given point, interpolated bilinearly between
+    /// the four nearest samples. Points outside take the height of the nearest edge.
+    pub fn height(&self, x: f32, z: f32) -> f32 {
+        let (x, tx) = Self::cell_of(x, self.origin.x, self.cell.x, self.samples.0);
+        let (z, tz) = Self::cell_of(z, self.origin.y, self.cell.y, self.samples.1);
+        let at = |x: usize, z: usize| self.heights[z * self.samples.0 + x];
+        let near = at(x, z) + (at(x + 1, z) - at(x, z)) * tx;
+        let far = at(x, z + 1) + (at(x + 1, z + 1) - at(x, z + 1)) * tx;
+        near + (far - near) * tz
+    }
+
+    /// Unit vector perpendicular to the ground at the given point
+    pub fn normal(&self, x: f32, z: f32) -> Vector3<f32> {
+        let (min, max) = self.extent();
+        // Neighbours one cell away, kept on the grid so the edges don't look flat
+        let (x0, x1) = ((x - self.cell.x).max(min.x), (x + self.cell.x).min(max.x));
+        let (z0, z1) = ((z - self.cell.y).max(min.y), (z + self.cell.y).min(max.y));
+        let slope_x = (self.height(x1, z) - self.height(x0, z)) / (x1 - x0).max(f32::EPSILON);
+        let slope_z = (self.height(x, z1) - self.height(x, z0)) / (z1 - z0).max(f32::EPSILON);
+        vec3(-slope_x, 1., -slope_z).normalize()
+    }
+
+    /// Index of the grid cell containing `v` along one axis and how far into the cell it is
+    fn cell_of(v: f32, origin: f32, cell: f32, samples: usize) -> (usize, f32) {
+        let position = ((v - origin) / cell).clamp(0., (samples - 1) as f32);
+        let index = (position.floor() as usize).min(samples - 2);
+        (index, position - index as f32)
+    }
+}
+
+/// Height of the triangle `abc` above `point` on the xz plane,
+/// or `None` if the point lies outside of it
+fn height_in_triangle(
+    point: Vector2<f32>,
+    a: Vector3<f32>,
+    b: Vector3<f32>,
+    c: Vector3<f32>,
+) -> Option<f32> {
+    const EPSILON: f32 = 1e-5;
+    let det = (b.z - c.z) * (a.x - c.x) + (c.x - b.x) * (a.z - c.z);
+    if det.abs() < EPSILON {
+        // Triangle seen edge-on from above
+        return None;
+    }
+    let l1 = ((b.z - c.z) * (point.x - c.x) + (c.x - b.x) * (point.y - c.z)) / det;
+    let l2 = ((c.z - a.z) * (point.x - c.x) + (a.x - c.x) * (point.y - c.z)) / det;
+    let l3 = 1. - l1 - l2;
+    if l1 < -EPSILON || l2 < -EPSILON || l3 < -EPSILON {
+        return None;
+    }
+    Some(l1 * a.y + l2 * b.y + l3 * c.y)
+}
@@ -5,9 +5,8 @@ use crate::{
         drawable::Drawable, enemies::Enemies, flight::steerable::Steerable,
         targeting_data::TargetingData,
     },
-    GLFW_TIME,
 };
-use cgmath::{Deg, InnerSpace, Matrix4, MetricSpace, SquareMatrix};
+use cgmath::{vec2, vec3, Deg, InnerSpace, Matrix4, MetricSpace, Point3, SquareMatrix, Vector3};
 use lazy_static::lazy_static;
 use std::{
     ffi::CStr,
@@ -30,15 +29,65 @@ lazy_static! {
         model.pitch(90.);
         model.scale(0.).deref().clone()
     };
+    static ref AIM_CIRCLE: Model = {
+        let mut model = Model::new("resources/objects/hud/target_circle.obj");
+        model.pitch(90.);
+        model.scale(0.).deref().clone()
+    };
+    static ref THREAT_MARKER: Model = {
+        let mut model = Model::new("resources/objects/hud/target_circle.obj");
+        model.pitch(90.);
+        model.scale(0.).deref().clone()
+    };
 }
 
 const UPDATE_INTERVAL: f64 = 0.1;
+/// Size of the mouse aim marker
+const AIM_SCALE: f32 = 0.03;
+/// How far ahead of the camera the mouse aim marker is placed
+const AIM_DISTANCE: f32 = 1000.;
+/// Most missiles shown, rocket pods carry more than fit on the screen
+const MAX_MISSILE_PIPS: usize = 16;
+/// Number of marks showing the gun rounds left when the guns are full
+const ROUND_PIPS: usize = 10;
+const PIP_SCALE: f32 = 0.012;
+const PIP_SPACING: f32 = 0.035;
+/// Where the ammo counters start in clip space, in the bottom left corner
+const AMMO_LEFT: f32 = -0.9;
+const MISSILE_ROW: f32 = -0.82;
+const ROUNDS_ROW: f32 = -0.9;
+/// Number of marks showing the health of the player's aircraft when it's undamaged
+const HEALTH_PIPS: usize = 10;
+const HEALTH_ROW: f32 = -0.74;
+/// Size of the markers on missiles coming at the player
+const THREAT_SCALE: f32 = 0.05;
+/// Distance from the middle of the screen at which missiles coming from behind are marked
+const THREAT_RING: f32 = 0.7;
+
+/// Weapons left to show on the HUD
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ammo {
+    /// Missiles of the selected kind left
+    pub missiles: u32,
+    /// Fraction of the gun rounds left
+    pub gun_rounds: f32,
+}
 
 pub struct Hud {
     enabled: bool,
     target_rectangles: Vec<Model>,
     target_circle: Model,
     lock_rectangle: Model,
+    /// Where the mouse aim points, shown only in mouse aim mode
+    aim_circle: Model,
+    /// One mark per missile of the selected kind left
+    missile_pips: Vec<Model>,
+    /// Marks filling up in proportion to the gun rounds left
+    round_pips: Vec<Model>,
+    /// Marks filling up in proportion to the health of the player's aircraft
+    health_pips: Vec<Model>,
+    /// One marker per missile homing in on the player
+    threat_markers: Vec<Model>,
     last_update_time: f64,
 }
 
@@ -49,6 +98,11 @@ impl Hud {
             target_rectangles: vec![],
             target_circle: TARGET_CIRCLE.clone(),
             lock_rectangle: LOCK_RECTANGLE.clone(),
+            aim_circle: AIM_CIRCLE.clone(),
+            missile_pips: vec![],
+            round_pips: vec![],
+            health_pips: vec![],
+            threat_markers: vec![],
             last_update_time: 0.,
         }
     }
@@ -58,8 +112,14 @@ impl Hud {
         camera: &Camera,
         enemies: &Enemies,
         targeting_data: &Option<TargetingData>,
+        aim: Option<Vector3<f32>>,
+        ammo: &Ammo,
+        time: f64,
     ) {
-        if self.last_update_time + UPDATE_INTERVAL > unsafe { GLFW_TIME } || !self.enabled {
+        // The aim marker follows the mouse on every frame, it would lag behind otherwise
+        self.update_aim(camera, aim);
+        self.update_ammo(ammo);
+        if self.last_update_time + UPDATE_INTERVAL > time || !self.enabled {
             return;
         }
 
@@ -106,7 +166,72 @@ impl Hud {
                 }
             }
         }
-        self.last_update_time = unsafe { GLFW_TIME };
+        self.last_update_time = time;
+    }
+
+    fn update_aim(&mut self, camera: &Camera, aim: Option<Vector3<f32>>) {
+        self.aim_circle.set_scale(0.);
+        let Some(aim) = aim.filter(|aim| aim.angle(camera.front) < Deg(80.).into()) else {
+            return;
+        };
+        let clip_space = camera.projection_matrix()
+            * camera.view_matrix()
+            * (camera.position() + aim * AIM_DISTANCE).to_homogeneous();
+        self.aim_circle
+            .set_translation(clip_space.div(clip_space.w).truncate())
+            .set_scale(AIM_SCALE);
+    }
+
+    fn update_ammo(&mut self, ammo: &Ammo) {
+        let missiles = (ammo.missiles as usize).min(MAX_MISSILE_PIPS);
+        let rounds = (ammo.gun_rounds.clamp(0., 1.) * ROUND_PIPS as f32).ceil() as usize;
+        self.missile_pips
+            .resize_with(missiles, || LOCK_RECTANGLE.clone());
+        self.round_pips
+            .resize_with(rounds, || LOCK_RECTANGLE.clone());
+        lay_out_pips(&mut self.missile_pips, MISSILE_ROW);
+        lay_out_pips(&mut self.round_pips, ROUNDS_ROW);
+    }
+
+    /// Show how much damage the player's aircraft can still take, as a fraction of its
+    /// full `health`, and mark the missiles `incoming` at it.
+    /// Missiles outside the view are marked around the middle of the screen,
+    /// in the direction they come from.
+    /// Updated on every frame, missiles move too fast to be marked every now and then.
+    pub fn update_warnings(&mut self, camera: &Camera, incoming: &[Point3<f32>], health: f32) {
+        let health = (health.clamp(0., 1.) * HEALTH_PIPS as f32).ceil() as usize;
+        self.health_pips
+            .resize_with(health, || LOCK_RECTANGLE.clone());
+        lay_out_pips(&mut self.health_pips, HEALTH_ROW);
+
+        self.threat_markers
+            .resize_with(incoming.len(), || THREAT_MARKER.clone());
+        for (marker, position) in self.threat_markers.iter_mut().zip(incoming) {
+            let direction = (position - camera.position()).normalize();
+            let element_pos = if direction.angle(camera.front) < Deg(80.).into() {
+                let clip_space =
+                    camera.projection_matrix() * camera.view_matrix() * position.to_homogeneous();
+                clip_space.div(clip_space.w).truncate()
+            } else {
+                let across = vec2(direction.dot(camera.right), direction.dot(camera.up));
+                // Straight behind, the marker goes to the bottom
+                let across = if across.magnitude() > f32::EPSILON {
+                    across.normalize()
+                } else {
+                    vec2(0., -1.)
+                };
+                (across * THREAT_RING).extend(0.)
+            };
+            marker.set_translation(element_pos).set_scale(THREAT_SCALE);
+        }
+    }
+}
+
+/// Place marks in a row from the left edge of the ammo counters
+fn lay_out_pips(pips: &mut [Model], row: f32) {
+    for (i, pip) in pips.iter_mut().enumerate() {
+        pip.set_translation(vec3(AMMO_LEFT + i as f32 * PIP_SPACING, row, 0.))
+            .set_scale(PIP_SCALE);
     }
 }
 
@@ -118,6 +243,11 @@ impl Drawable for Hud {
         self.target_rectangles.iter().for_each(|r| r.draw(shader));
         self.target_circle.draw(shader);
         self.lock_rectangle.draw(shader);
+        self.aim_circle.draw(shader);
+        self.missile_pips.iter().for_each(|p| p.draw(shader));
+        self.round_pips.iter().for_each(|p| p.draw(shader));
+        self.health_pips.iter().for_each(|p| p.draw(shader));
+        self.threat_markers.iter().for_each(|m| m.draw(shader));
         gl::Enable(gl::DEPTH_TEST);
     }
 }
@@ -0,0 +1,196 @@
+use crate::cg::camera::Movement;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Something the player can do by pressing a bound key or button
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    PitchUp,
+    PitchDown,
+    RollLeft,
+    RollRight,
+    YawLeft,
+    YawRight,
+    ThrottleUp,
+    ThrottleDown,
+    FireGuns,
+    LaunchMissile,
+    SwitchTarget,
+    /// Take off again after a crash
+    Respawn,
+    /// Halve the speed of game time
+    SlowDown,
+    /// Double the speed of game time
+    SpeedUp,
+    /// Switch between flying with the keyboard and with mouse aim
+    ToggleMouseAim,
+    /// Look through the next camera. Changes only what is shown, not the simulation.
+    CycleCamera,
+    /// Select the next kind of missile the aircraft carries
+    CycleWeapon,
+    /// Choose the next loadout of the aircraft, fitted right away while it's on the ground
+    CycleLoadout,
+    ReleaseFlares,
+    ReleaseChaff,
+}
+
+impl Action {
+    pub const ALL: [Action; 20] = [
+        Action::PitchUp,
+        Action::PitchDown,
+        Action::RollLeft,
+        Action::RollRight,
+        Action::YawLeft,
+        Action::YawRight,
+        Action::ThrottleUp,
+        Action::ThrottleDown,
+        Action::FireGuns,
+        Action::LaunchMissile,
+        Action::SwitchTarget,
+        Action::Respawn,
+        Action::SlowDown,
+        Action::SpeedUp,
+        Action::ToggleMouseAim,
+        Action::CycleCamera,
+        Action::CycleWeapon,
+        Action::CycleLoadout,
+        Action::ReleaseFlares,
+        Action::ReleaseChaff,
+    ];
+
+    /// How the action steers the aircraft, if it does
+    pub fn movement(self) -> Option<Movement> {
+        match self {
+            Action::PitchUp => Some(Movement::PitchUp),
+            Action::PitchDown => Some(Movement::PitchDown),
+            Action::RollLeft => Some(Movement::RollLeft),
+            Action::RollRight => Some(Movement::RollRight),
+            Action::YawLeft => Some(Movement::YawLeft),
+            Action::YawRight => Some(Movement::YawRight),
+            Action::ThrottleUp => Some(Movement::ThrottleUp),
+            Action::ThrottleDown => Some(Movement::ThrottleDown),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Action::PitchUp => "pitch_up",
+            Action::PitchDown => "pitch_down",
+            Action::RollLeft => "roll_left",
+            Action::RollRight => "roll_right",
+            Action::YawLeft => "yaw_left",
+            Action::YawRight => "yaw_right",
+            Action::ThrottleUp => "throttle_up",
+            Action::ThrottleDown => "throttle_down",
+            Action::FireGuns => "fire_guns",
+            Action::LaunchMissile => "launch_missile",
+            Action::SwitchTarget => "switch_target",
+            Action::Respawn => "respawn",
+            Action::SlowDown => "slow_down",
+            Action::SpeedUp => "speed_up",
+            Action::ToggleMouseAim => "toggle_mouse_aim",
+            Action::CycleCamera => "cycle_camera",
+            Action::CycleWeapon => "cycle_weapon",
+            Action::CycleLoadout => "cycle_loadout",
+            Action::ReleaseFlares => "release_flares",
+            Action::ReleaseChaff => "release_chaff",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .into_iter()
+            .find(|a| a.to_string() == s)
+            .ok_or_else(|| format!("unknown action `{s}`"))
+    }
+}
+
+/// Position of analog flight controls, already past dead zones and response curves.
+/// Axes at zero are centered or not bound and leave their surface to the keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct StickInput {
+    /// Deflection in range [-1, 1], positive pulls the nose up
+    #[serde(default)]
+    pub pitch: f32,
+    /// Deflection in range [-1, 1], positive rolls right
+    #[serde(default)]
+    pub roll: f32,
+    /// Deflection in range [-1, 1], positive yaws right
+    #[serde(default)]
+    pub yaw: f32,
+    /// Throttle lever position in range [0, 1]. Only given when the lever moved,
+    /// so that it doesn't fight the throttle keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<f32>,
+}
+
+impl StickInput {
+    pub fn is_idle(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Everything the player asked for during a single frame, already
+/// translated from actions and mouse movement into what the simulation does.
+/// This is the only way the simulation receives input.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameInput {
+    pub movements: Vec<Movement>,
+    pub fire_guns: bool,
+    pub launch_missile: bool,
+    pub switch_target: bool,
+    pub release_flares: bool,
+    pub release_chaff: bool,
+    pub stick: StickInput,
+    /// Accumulated cursor offset. The y axis goes from bottom to top.
+    pub look: (f32, f32),
+}
+
+/// The player's input during a single frame, with keys and buttons already
+/// translated into actions through their bindings.
+/// This is what replays are made of, so a replay played back goes through
+/// exactly the same code as live input, whatever the bindings are at the time.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputSnapshot {
+    /// Real seconds elapsed since the previous frame
+    pub delta: f32,
+    /// Actions whose keys or buttons were held down during the frame
+    pub held: Vec<Action>,
+    /// Actions whose keys or buttons went down during the frame, once per press
+    pub pressed: Vec<Action>,
+    /// Analog flight controls at the end of the frame
+    pub stick: StickInput,
+    /// Accumulated look offset. The y axis goes from bottom to top.
+    pub cursor: (f32, f32),
+    /// Accumulated zoom offset. Only changes the view, the simulation never sees it.
+    pub scroll: f32,
+}
+
+impl InputSnapshot {
+    pub fn is_held(&self, action: Action) -> bool {
+        self.held.contains(&action)
+    }
+
+    /// Translate the actions and mouse movement into input for the simulation
+    pub fn frame_input(&self) -> FrameInput {
+        FrameInput {
+            movements: self.held.iter().filter_map(|a| a.movement()).collect(),
+            fire_guns: self.is_held(Action::FireGuns),
+            launch_missile: self.is_held(Action::LaunchMissile),
+            switch_target: self.is_held(Action::SwitchTarget),
+            release_flares: self.is_held(Action::ReleaseFlares),
+            release_chaff: self.is_held(Action::ReleaseChaff),
+            stick: self.stick,
+            look: self.cursor,
+        }
+    }
+}
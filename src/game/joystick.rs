@@ -0,0 +1,179 @@
+use super::input::StickInput;
+use glfw::{GamepadAxis, Glfw, JoystickId};
+use log::info;
+use serde::Deserialize;
+
+/// Joysticks checked for one to fly with, in order
+const JOYSTICKS: [JoystickId; 4] = [
+    JoystickId::Joystick1,
+    JoystickId::Joystick2,
+    JoystickId::Joystick3,
+    JoystickId::Joystick4,
+];
+/// Axes of a gamepad in the order their indices refer to
+const GAMEPAD_AXES: [GamepadAxis; 6] = [
+    GamepadAxis::AxisLeftX,
+    GamepadAxis::AxisLeftY,
+    GamepadAxis::AxisRightX,
+    GamepadAxis::AxisRightY,
+    GamepadAxis::AxisLeftTrigger,
+    GamepadAxis::AxisRightTrigger,
+];
+/// How far the throttle lever has to move before it takes over from the throttle keys
+const THROTTLE_THRESHOLD: f32 = 0.01;
+
+/// How a single joystick axis drives a flight control
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct JoystickAxis {
+    /// Index of the axis on the device. Gamepads use the standard layout:
+    /// 0 and 1 are the left stick's x and y, 2 and 3 the right stick's, 4 and 5 the triggers.
+    pub index: usize,
+    #[serde(default)]
+    pub inverted: bool,
+    /// Part of the range around the center which is ignored, so a worn stick doesn't drift
+    #[serde(default = "JoystickAxis::default_dead_zone")]
+    pub dead_zone: f32,
+    /// Exponent of the response curve. Above 1 small deflections get gentler,
+    /// which makes fine corrections easier while keeping the full range.
+    #[serde(default = "JoystickAxis::default_curve")]
+    pub curve: f32,
+}
+
+impl JoystickAxis {
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            inverted: false,
+            dead_zone: Self::default_dead_zone(),
+            curve: Self::default_curve(),
+        }
+    }
+
+    fn default_dead_zone() -> f32 {
+        0.1
+    }
+
+    fn default_curve() -> f32 {
+        1.5
+    }
+
+    /// Deflection in range [-1, 1] of a centered axis, like a stick or rudder pedals
+    pub fn deflection(&self, axes: &[f32]) -> f32 {
+        let raw = axes.get(self.index).copied().unwrap_or(0.).clamp(-1., 1.);
+        let beyond_dead_zone =
+            ((raw.abs() - self.dead_zone) / (1. - self.dead_zone).max(f32::EPSILON)).max(0.);
+        let deflection = beyond_dead_zone.powf(self.curve) * raw.signum();
+        if self.inverted {
+            -deflection
+        } else {
+            deflection
+        }
+    }
+
+    /// Position in range [0, 1] of a lever-like axis, like a throttle or a trigger
+    pub fn position(&self, axes: &[f32]) -> f32 {
+        let raw = axes.get(self.index).copied().unwrap_or(-1.).clamp(-1., 1.);
+        let raw = if self.inverted { -raw } else { raw };
+        (raw + 1.) / 2.
+    }
+}
+
+/// Which joystick axes drive which flight controls
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct JoystickBindings {
+    #[serde(default)]
+    pub pitch: Option<JoystickAxis>,
+    #[serde(default)]
+    pub roll: Option<JoystickAxis>,
+    #[serde(default)]
+    pub yaw: Option<JoystickAxis>,
+    #[serde(default)]
+    pub throttle: Option<JoystickAxis>,
+}
+
+impl Default for JoystickBindings {
+    /// A gamepad's left stick. Yaw and throttle axes differ too much between devices to guess.
+    fn default() -> Self {
+        Self {
+            pitch: Some(JoystickAxis::new(1)),
+            roll: Some(JoystickAxis::new(0)),
+            yaw: None,
+            throttle: None,
+        }
+    }
+}
+
+impl JoystickBindings {
+    /// Every bound axis together with the name of the control it drives
+    pub fn bound(&self) -> impl Iterator<Item = (&'static str, JoystickAxis)> {
+        [
+            ("pitch", self.pitch),
+            ("roll", self.roll),
+            ("yaw", self.yaw),
+            ("throttle", self.throttle),
+        ]
+        .into_iter()
+        .filter_map(|(name, axis)| axis.map(|axis| (name, axis)))
+    }
+
+    /// Position of the flight controls given the values of the device's axes.
+    /// The throttle lever is always reported, it's up to the caller to tell if it moved.
+    pub fn stick(&self, axes: &[f32]) -> StickInput {
+        let deflection = |axis: Option<JoystickAxis>| axis.map_or(0., |a| a.deflection(axes));
+        StickInput {
+            pitch: deflection(self.pitch),
+            roll: deflection(self.roll),
+            yaw: deflection(self.yaw),
+            throttle: self.throttle.map(|a| a.position(axes)),
+        }
+    }
+}
+
+/// Reads the flight controls from the first connected joystick or gamepad
+#[derive(Default)]
+pub struct Joystick {
+    /// Name of the device being read, if there is one
+    device: Option<String>,
+    /// Throttle lever position last passed on to the simulation
+    last_throttle: Option<f32>,
+}
+
+impl Joystick {
+    pub fn read(&mut self, glfw: &Glfw, bindings: &JoystickBindings) -> StickInput {
+        let joystick = JOYSTICKS
+            .iter()
+            .map(|id| glfw.get_joystick(*id))
+            .find(|j| j.is_present());
+        let device = joystick
+            .as_ref()
+            .map(|j| j.get_name().unwrap_or_else(|| "joystick".to_string()));
+        if device != self.device {
+            match &device {
+                Some(name) => info!("Flying with {name}"),
+                None => info!("Joystick disconnected"),
+            }
+            self.device = device;
+            self.last_throttle = None;
+        }
+        let Some(joystick) = joystick else {
+            return StickInput::default();
+        };
+
+        // Gamepads get their axes remapped to the standard layout
+        let axes = match joystick.get_gamepad_state() {
+            Some(state) => GAMEPAD_AXES.iter().map(|a| state.get_axis(*a)).collect(),
+            None => joystick.get_axes(),
+        };
+        let mut stick = bindings.stick(&axes);
+        stick.throttle = stick.throttle.filter(|throttle| {
+            let moved = self
+                .last_throttle
+                .is_none_or(|last| (throttle - last).abs() > THROTTLE_THRESHOLD);
+            if moved {
+                self.last_throttle = Some(*throttle);
+            }
+            moved
+        });
+        stick
+    }
+}
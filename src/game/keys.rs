@@ -0,0 +1,34 @@
+use glfw::Key;
+
+/// Generate conversions between the known keys and the names used for them in files
+macro_rules! keys {
+    ($($key:ident),+ $(,)?) => {
+        /// Name of a key as written in config files
+        pub fn key_name(key: Key) -> &'static str {
+            match key {
+                $(Key::$key => stringify!($key),)+
+                Key::Unknown => "Unknown",
+            }
+        }
+
+        /// Key with the given name as written in config files
+        pub fn key_from_name(name: &str) -> Option<Key> {
+            match name {
+                $(stringify!($key) => Some(Key::$key),)+
+                _ => None,
+            }
+        }
+    };
+}
+
+keys! {
+    Space, Apostrophe, Comma, Minus, Period, Slash, Num0, Num1, Num2, Num3, Num4, Num5, Num6,
+    Num7, Num8, Num9, Semicolon, Equal, A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R,
+    S, T, U, V, W, X, Y, Z, LeftBracket, Backslash, RightBracket, GraveAccent, World1, World2,
+    Escape, Enter, Tab, Backspace, Insert, Delete, Right, Left, Down, Up, PageUp, PageDown,
+    Home, End, CapsLock, ScrollLock, NumLock, PrintScreen, Pause, F1, F2, F3, F4, F5, F6, F7,
+    F8, F9, F10, F11, F12, F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24, F25,
+    Kp0, Kp1, Kp2, Kp3, Kp4, Kp5, Kp6, Kp7, Kp8, Kp9, KpDecimal, KpDivide, KpMultiply,
+    KpSubtract, KpAdd, KpEnter, KpEqual, LeftShift, LeftControl, LeftAlt, LeftSuper,
+    RightShift, RightControl, RightAlt, RightSuper, Menu,
+}
@@ -0,0 +1,107 @@
+use super::chunk::ChunkCoord;
+use std::collections::BTreeMap;
+
+/// Coarsest level of detail. At level `l` only every `2^l`-th vertex
+/// of a chunk's grid is drawn along each side.
+pub const MAX_LEVEL: u32 = 5;
+/// Distance from the camera in meters up to which chunks are drawn at full detail.
+/// Every time the distance doubles past it, chunks drop one level.
+const FULL_DETAIL_DISTANCE: f32 = 1000.;
+
+/// Which triangles of a chunk's grid get drawn
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LodKey {
+    pub level: u32,
+    /// Levels of the north (smallest z), east (largest x), south and west edges.
+    /// An edge next to a coarser chunk takes its level, so that both chunks
+    /// draw the same vertices along it and no cracks open between them.
+    pub edges: [u32; 4],
+}
+
+/// Level of detail of a chunk at the given distance from the camera
+pub fn level_for_distance(distance: f32) -> u32 {
+    if distance <= FULL_DETAIL_DISTANCE {
+        return 0;
+    }
+    ((distance / FULL_DETAIL_DISTANCE).log2() as u32 + 1).min(MAX_LEVEL)
+}
+
+/// Match the edges of neighbouring chunks with different levels
+pub fn stitch(levels: &BTreeMap<ChunkCoord, u32>) -> BTreeMap<ChunkCoord, LodKey> {
+    levels
+        .iter()
+        .map(|(&(x, z), &level)| {
+            let neighbours = [(x, z - 1), (x + 1, z), (x, z + 1), (x - 1, z)];
+            let edges = neighbours.map(|n| levels.get(&n).map_or(level, |&l| l.max(level)));
+            ((x, z), LodKey { level, edges })
+        })
+        .collect()
+}
+
+/// Triangles of a square grid with `resolution` vertices per side drawn at the given detail.
+/// The inside is a regular grid with the level's spacing, each edge is zipped
+/// to it with triangles using only the vertices of the edge's level.
+pub fn indices(key: LodKey, resolution: usize) -> Vec<u32> {
+    let cells = resolution - 1;
+    let step = 1 << key.level;
+    assert!(
+        cells.is_multiple_of(2 << MAX_LEVEL),
+        "Grids need at least two cells per side at the coarsest level"
+    );
+    let index = |(x, z): (usize, usize)| (z * resolution + x) as u32;
+    let mut indices = vec![];
+    let mut push_triangle = |[a, b, c]: [(usize, usize); 3]| {
+        // Keep all triangles counter-clockwise when looked at from above
+        let (ab, ac) = (
+            (b.0 as i64 - a.0 as i64, b.1 as i64 - a.1 as i64),
+            (c.0 as i64 - a.0 as i64, c.1 as i64 - a.1 as i64),
+        );
+        if ab.1 * ac.0 - ab.0 * ac.1 > 0 {
+            indices.extend([index(a), index(b), index(c)]);
+        } else {
+            indices.extend([index(a), index(c), index(b)]);
+        }
+    };
+
+    for z in (step..cells - step).step_by(step) {
+        for x in (step..cells - step).step_by(step) {
+            push_triangle([(x, z), (x, z + step), (x + step, z)]);
+            push_triangle([(x + step, z), (x, z + step), (x + step, z + step)]);
+        }
+    }
+
+    for (side, &edge_level) in key.edges.iter().enumerate() {
+        // Position along the edge and distance from it to grid coordinates
+        let point = |along: usize, depth: usize| match side {
+            0 => (along, depth),
+            1 => (cells - depth, along),
+            2 => (along, cells - depth),
+            _ => (depth, along),
+        };
+        let outer: Vec<usize> = (0..=cells)
+            .step_by(1 << edge_level.max(key.level))
+            .collect();
+        let inner: Vec<usize> = (step..=cells - step).step_by(step).collect();
+        let (mut o, mut i) = (0, 0);
+        while o + 1 < outer.len() || i + 1 < inner.len() {
+            let along_outer =
+                i + 1 == inner.len() || (o + 1 < outer.len() && outer[o + 1] <= inner[i + 1]);
+            if along_outer {
+                push_triangle([
+                    point(outer[o], 0),
+                    point(outer[o + 1], 0),
+                    point(inner[i], step),
+                ]);
+                o += 1;
+            } else {
+                push_triangle([
+                    point(outer[o], 0),
+                    point(inner[i], step),
+                    point(inner[i + 1], step),
+                ]);
+                i += 1;
+            }
+        }
+    }
+    indices
+}
@@ -1,58 +1,98 @@
-use super::{drawable::Drawable, enemy::Enemy, missile_guidance::GuidanceStatus, modeled::Modeled};
+use super::{
+    clock::FrameTime,
+    countermeasures::{Decoy, DecoyID},
+    drawable::Drawable,
+    missile_catalogue::MissileType,
+    missile_guidance::GuidanceStatus,
+    missile_spec::{MissileSpec, SeekerSpec},
+    modeled::Modeled,
+    rebase::Rebase,
+};
 use crate::{
-    cg::{camera::Camera, consts::VEC_RIGHT, model::Model, particles::ParticleGenerator},
-    game::{flight::steerable::Steerable, particle_generation::ParticleGeneration},
-    DELTA_TIME,
+    cg::{consts::VEC_UP, model::Model, particles::ParticleGenerator},
+    game::{
+        flight::dynamics::{Pose, GRAVITY},
+        particle_generation::ParticleGeneration,
+    },
 };
-use cgmath::{EuclideanSpace, InnerSpace, MetricSpace, Point3, Quaternion, Vector3, Vector4};
+use cgmath::{EuclideanSpace, InnerSpace, MetricSpace, Point3, Quaternion, Vector3, Vector4, Zero};
 use log::info;
-use vek::{QuadraticBezier3, Vec3};
+use rand::Rng;
 
 pub type EnemyID = u32;
+pub type MissileID = u32;
+
+/// What a missile can be launched at: an enemy, or the player's aircraft when an enemy
+/// launches it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetID {
+    Enemy(EnemyID),
+    Player,
+}
 
 pub enum MissileMessage {
-    HitEnemy(EnemyID),
-    BeganTermination,
+    Hit(TargetID),
+    /// The target left the seeker's view or the missile got too slow to steer
+    LostTarget,
 }
 
-/// Struct representing a missile fired by the player
-/// The missile only knows what ID the Enemy it targets has.
-/// Each frame it receives a reference to the enemy it targets so it can update its state.
+/// Struct representing a missile fired by the player or by an enemy
+/// The missile only knows the ID of what it targets.
+/// Each frame it receives the pose of its target so it can update its state.
+/// It flies on its own motor and fins, so it can miss: by running out of energy,
+/// by not turning hard enough or by losing sight of the target.
 pub struct Missile {
+    id: MissileID,
+    spec: MissileSpec,
+    pose: Pose,
+    /// Position before the last step
+    previous: Point3<f32>,
+    /// Velocity in world space in m/s
+    velocity: Vector3<f32>,
+    /// Seconds since launch
+    age: f32,
+    detonated: bool,
+    /// Enemy which launched the missile, `None` if the player did
+    launched_by: Option<EnemyID>,
     pub model: Model,
     pub guidance: GuidanceStatus,
     particle_generator: ParticleGenerator,
 }
 
 impl Missile {
-    /// Create a new missile.
-    /// Uses player's position to spawn the missile at the right coordinates.
-    pub fn new(camera: &Camera, target: Option<&Enemy>) -> Self {
-        let mut model = Model::new("resources/objects/missile/missile.obj");
+    /// Create a new missile of the given type.
+    /// Spawns the missile at `pose`, the hardpoint it's launched from,
+    /// flying at the `velocity` of the launching aircraft, which is an enemy
+    /// if `launched_by` is given and the player otherwise.
+    pub fn new(
+        id: MissileID,
+        missile_type: &MissileType,
+        pose: &Pose,
+        velocity: Vector3<f32>,
+        target: Option<TargetID>,
+        launched_by: Option<EnemyID>,
+    ) -> Self {
+        let mut model = Model::new(missile_type.model_path());
         let particle_generator = ParticleGenerator::new(1500, Vector4::new(1., 0., 0., 1.), 2.);
 
-        model.apply_quaternion(camera.orientation_quat());
-
-        let pos = camera.position().to_vec() + camera.up * -0.5;
-        model.set_translation(pos);
-
-        let guidance = if let Some(enemy) = target {
-            let target = enemy.id();
-            let start = model.position_vek();
-            let end = enemy.aircraft().model().position_vek();
-            let mid = {
-                // Select a point in front of the launching aircraft to simulate the missile accelerating
-                let mid = model.position() + camera.front * 50.;
-                Vec3::from([mid.x, mid.y, mid.z])
-            };
-            let points = Vec3::from([start, mid, end]);
-            let bezier = QuadraticBezier3::from(points);
-            GuidanceStatus::new(target, bezier)
-        } else {
-            GuidanceStatus::none()
+        let pose = *pose;
+        model.apply_quaternion(pose.orientation);
+        model.set_translation(pose.position.to_vec());
+
+        let guidance = match target {
+            Some(target) if missile_type.spec().seeker.is_some() => GuidanceStatus::new(target),
+            _ => GuidanceStatus::none(),
         };
 
         Self {
+            id,
+            spec: *missile_type.spec(),
+            pose,
+            previous: pose.position,
+            velocity,
+            age: 0.,
+            detonated: false,
+            launched_by,
             model,
             guidance,
             particle_generator,
@@ -60,85 +100,266 @@ impl Missile {
     }
 
     /// Report on what the missile is doing this frame
-    /// based on the information from the Enemy reference
-    pub fn update(&mut self, enemy: Option<&Enemy>) -> Option<MissileMessage> {
-        match (enemy, self.guidance) {
-            (Some(e), GuidanceStatus::Active(_)) => {
-                self.try_hit_target(e).or_else(|| self.guide_towards(e))
-            }
-            (None, GuidanceStatus::Active(_)) => self.begin_terminate(),
-            (_, GuidanceStatus::None(timer)) => self.termination_countdown(timer),
-        }
-    }
+    /// based on the pose of its target, if it's still around.
+    /// A seeker fooled by a decoy homes in on `decoy` instead,
+    /// though passing close to the target still sets off the fuse.
+    /// `supported` tells whether the launching aircraft still keeps its lock on the target,
+    /// which radar missiles need until they're close enough to see it themselves.
+    pub fn update(
+        &mut self,
+        target: Option<&Pose>,
+        decoy: Option<&Decoy>,
+        supported: bool,
+        frame: &FrameTime,
+    ) -> Option<MissileMessage> {
+        let delta = frame.delta;
+        let target = target.copied();
+        let homing = match self.decoy() {
+            Some(_) => decoy.map(|d| Homing::Decoy(d.position())),
+            None => target.map(Homing::Target),
+        };
+        let mut message = None;
 
-    /// See if the missile should hit, return A message containing the enemy ID if it did.
-    fn try_hit_target(&mut self, target: &Enemy) -> Option<MissileMessage> {
-        if self.position().distance(target.position()) < 2. {
-            info!("MISSILE HIT");
+        if self.target().is_some() && !self.can_guide(homing, supported) {
+            info!("Missile lost its target");
             self.guidance = GuidanceStatus::none();
-            return Some(MissileMessage::HitEnemy(target.id()));
+            message = Some(MissileMessage::LostTarget);
+        }
+        let command = match (&mut self.guidance, homing, self.spec.seeker) {
+            (GuidanceStatus::Active(data), Some(homing), Some(seeker)) => {
+                let track = data.sight(homing.position(), delta);
+                seeker.guidance.command(
+                    seeker.navigation_constant,
+                    self.pose.position,
+                    self.velocity,
+                    &track,
+                )
+            }
+            _ => Vector3::zero(),
+        };
+
+        self.previous = self.pose.position;
+        self.fly(command, delta);
+        self.age += delta;
+
+        match (self.target(), target, homing) {
+            (Some(id), Some(target), _) if self.fuzed_by(target.position) => {
+                info!("MISSILE HIT");
+                self.fuze(target.position);
+                Some(MissileMessage::Hit(id))
+            }
+            (_, _, Some(Homing::Decoy(position))) if self.fuzed_by(position) => {
+                info!("Missile went off on a decoy");
+                self.fuze(position);
+                message
+            }
+            _ => message,
         }
-        None
     }
 
-    /// Move the missile towards its target along the bezier curve contained in GuidanceData
-    fn guide_towards(&mut self, target: &Enemy) -> Option<MissileMessage> {
-        assert!(matches!(self.guidance, GuidanceStatus::Active(_)));
+    /// Whether the missile can still see what it homes in on, or be told where it is,
+    /// and steer towards it
+    fn can_guide(&self, homing: Option<Homing>, supported: bool) -> bool {
+        let spent = self.age > self.spec.burn_time && self.speed() < self.spec.min_speed;
+        let seen = match (homing, self.spec.seeker) {
+            (Some(homing), Some(seeker)) => {
+                if !seeker.is_active(self.pose.position, homing.position()) {
+                    supported
+                } else {
+                    match homing {
+                        Homing::Target(target) => seeker.sees(&self.pose, &target),
+                        Homing::Decoy(position) => seeker.in_view(&self.pose, position),
+                    }
+                }
+            }
+            _ => false,
+        };
+        seen && !spent
+    }
 
-        let guidance_data = if let GuidanceStatus::Active(data) = &mut self.guidance {
-            data
+    /// Give the seeker a chance to take a decoy released by its target, placed at `target`,
+    /// for the target itself. Only a seeker looking on its own can be fooled,
+    /// and only once. Returns whether the missile homes in on the decoy now.
+    pub fn spoof(&mut self, decoy: &Decoy, target: &Pose, rng: &mut impl Rng) -> bool {
+        let (GuidanceStatus::Active(data), Some(seeker)) = (&mut self.guidance, self.spec.seeker)
+        else {
+            return false;
+        };
+        if data.decoy.is_some()
+            || !seeker.is_active(self.pose.position, target.position)
+            || !seeker.in_view(&self.pose, decoy.position())
+        {
+            return false;
+        }
+        let to_missile = self.pose.position - target.position;
+        let aspect = target.front().angle(to_missile).0;
+        let target_velocity = data.track.map_or(Vector3::zero(), |t| t.velocity);
+        let closing_speed = (self.velocity - target_velocity).dot(-to_missile.normalize());
+        let time_to_go = if closing_speed > 0. {
+            to_missile.magnitude() / closing_speed
         } else {
-            unreachable!()
+            f32::INFINITY
         };
+        let chance = decoy.kind().seduction(&seeker.kind, aspect, time_to_go);
+        if rng.gen::<f32>() >= chance {
+            return false;
+        }
+        info!("Missile fooled by a {}", decoy.kind());
+        data.seduce(decoy.id());
+        true
+    }
 
-        // Progress along the curve
-        let t = {
-            let bezier = guidance_data.bezier;
-            let t = 0.001;
-            let v1 = (2. * bezier.start) - (4. * bezier.ctrl) + (2. * bezier.end);
-            let v2 = (-2. * bezier.start) + (2. * bezier.ctrl);
-            let l = unsafe { DELTA_TIME };
-            t + (l / (t * v1 + v2).magnitude())
+    /// Integrate the motor, drag, gravity and the steering `command` over one step.
+    /// The fins can only pull sideways, and only so hard.
+    fn fly(&mut self, command: Vector3<f32>, delta_time: f32) {
+        let speed = self.speed();
+        let front = if speed > f32::EPSILON {
+            self.velocity / speed
+        } else {
+            self.pose.front()
         };
-        guidance_data.progress += t;
 
-        let new_point = {
-            let eval = guidance_data.bezier.evaluate(guidance_data.progress);
-            Vector3::from([eval.x, eval.y, eval.z])
+        let limit = if speed < self.spec.min_speed {
+            0.
+        } else {
+            self.spec.max_g * GRAVITY
         };
+        let lateral = command - front * command.dot(front);
+        let lateral = if lateral.magnitude() > limit {
+            lateral.normalize_to(limit)
+        } else {
+            lateral
+        };
+        let thrust = if self.age < self.spec.burn_time {
+            self.spec.thrust
+        } else {
+            0.
+        };
+        let drag = self.spec.drag * speed * speed + self.spec.induced_drag * lateral.magnitude();
 
-        guidance_data.bezier.end = target.aircraft().model().position_vek();
-        self.model.set_translation(new_point);
+        self.velocity += (lateral + front * (thrust - drag) - *VEC_UP * GRAVITY) * delta_time;
+        self.pose.position += self.velocity * delta_time;
 
-        let vec_to_enemy = (target.position() - self.position()).normalize();
-        let quat = Quaternion::from_arc(*VEC_RIGHT, vec_to_enemy, None);
-        self.model.set_orientation(quat);
-        self.model.yaw(-90.);
-        None
+        // The nose follows the velocity
+        if self.velocity.magnitude() > f32::EPSILON {
+            let turn = Quaternion::from_arc(front, self.velocity.normalize(), None);
+            self.pose.orientation = (turn * self.pose.orientation).normalize();
+        }
+        self.model.set_orientation(self.pose.orientation);
+        self.model.set_translation(self.pose.position.to_vec());
+    }
+
+    /// Whether the missile came close enough to something at `position`
+    /// to set off the fuse during the last step
+    pub fn fuzed_by(&self, position: Point3<f32>) -> bool {
+        self.closest_approach(position).distance(position) < self.spec.fuse_radius
     }
 
-    /// Missile has flown without a target for too long, start the countdown
-    pub fn begin_terminate(&mut self) -> Option<MissileMessage> {
+    /// Point of the last step's path closest to `position`
+    fn closest_approach(&self, position: Point3<f32>) -> Point3<f32> {
+        let start = self.previous;
+        let path = self.pose.position - start;
+        let length = path.magnitude2();
+        let along = if length > f32::EPSILON {
+            ((position - start).dot(path) / length).clamp(0., 1.)
+        } else {
+            0.
+        };
+        start + path * along
+    }
+
+    /// Blow the missile up where it passed closest to something at `position`
+    /// which set off the fuse
+    pub fn fuze(&mut self, position: Point3<f32>) {
+        self.pose.position = self.closest_approach(position);
+        self.detonate();
+    }
+
+    /// Blow the missile up where it is
+    pub fn detonate(&mut self) {
         self.guidance = GuidanceStatus::none();
-        Some(MissileMessage::BeganTermination)
+        self.detonated = true;
+    }
+
+    /// Whether the warhead went off, as opposed to the missile running out of time
+    pub fn detonated(&self) -> bool {
+        self.detonated
+    }
+
+    /// Whether the missile blew up or flew for as long as it can
+    pub fn finished(&self) -> bool {
+        self.detonated || self.age > self.spec.lifetime
+    }
+
+    pub fn id(&self) -> MissileID {
+        self.id
     }
 
-    /// Decrement the termination countdown
-    fn termination_countdown(&mut self, timer: u32) -> Option<MissileMessage> {
-        self.model.forward(100. * unsafe { DELTA_TIME });
-        self.guidance = GuidanceStatus::None(timer - 1);
-        None
+    pub fn spec(&self) -> &MissileSpec {
+        &self.spec
     }
 
-    pub fn target(&self) -> Option<EnemyID> {
+    pub fn target(&self) -> Option<TargetID> {
         match &self.guidance {
-            GuidanceStatus::None(_) => None,
+            GuidanceStatus::None => None,
             GuidanceStatus::Active(data) => Some(data.target_id),
         }
     }
 
+    pub fn launched_by(&self) -> Option<EnemyID> {
+        self.launched_by
+    }
+
+    /// Decoy the seeker homes in on instead of the target, if it was fooled by one
+    pub fn decoy(&self) -> Option<DecoyID> {
+        match &self.guidance {
+            GuidanceStatus::None => None,
+            GuidanceStatus::Active(data) => data.decoy,
+        }
+    }
+
+    /// Seeker of a guided missile which still homes in on something
+    pub fn seeker(&self) -> Option<&SeekerSpec> {
+        match self.guidance {
+            GuidanceStatus::None => None,
+            GuidanceStatus::Active(_) => self.spec.seeker.as_ref(),
+        }
+    }
+
     pub fn position(&self) -> Point3<f32> {
-        self.model.position()
+        self.pose.position
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.velocity.magnitude()
+    }
+}
+
+/// What a missile's seeker homes in on during a frame
+#[derive(Clone, Copy)]
+enum Homing {
+    Target(Pose),
+    Decoy(Point3<f32>),
+}
+
+impl Homing {
+    fn position(&self) -> Point3<f32> {
+        match self {
+            Homing::Target(pose) => pose.position,
+            Homing::Decoy(position) => *position,
+        }
+    }
+}
+
+impl Rebase for Missile {
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        self.pose.rebase(shift);
+        self.previous += shift;
+        self.model.rebase(shift);
+        self.particle_generator.rebase(shift);
+        if let GuidanceStatus::Active(data) = &mut self.guidance {
+            data.rebase(shift);
+        }
     }
 }
 
@@ -0,0 +1,260 @@
+use super::{
+    flight::blueprint::Blueprints,
+    missile_spec::{MissileSpec, SeekerKind, SeekerSpec},
+};
+use crate::gen_ref_getters;
+use itertools::Itertools;
+use log::info;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Directory the missile definitions are loaded from.
+/// Every `.json` file in it defines one missile kind named after the file.
+pub const MISSILE_DIR: &str = "resources/missiles";
+
+#[derive(Debug)]
+pub enum CatalogueError {
+    Io(PathBuf, io::Error),
+    Format(PathBuf, serde_json::Error),
+    Invalid {
+        path: PathBuf,
+        field: &'static str,
+        reason: &'static str,
+    },
+    NoMissiles(PathBuf),
+    UnknownKind(MissileKind, Vec<MissileKind>),
+}
+
+impl fmt::Display for CatalogueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogueError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            CatalogueError::Format(path, e) => write!(f, "{}: {e}", path.display()),
+            CatalogueError::Invalid {
+                path,
+                field,
+                reason,
+            } => write!(f, "{}: invalid `{field}`: {reason}", path.display()),
+            CatalogueError::NoMissiles(dir) => {
+                write!(f, "no missile definitions found in {}", dir.display())
+            }
+            CatalogueError::UnknownKind(kind, available) => write!(
+                f,
+                "unknown missile kind `{kind}`, available: {}",
+                available.iter().join(", ")
+            ),
+        }
+    }
+}
+
+/// Name of a missile type, the name of its file in `MISSILE_DIR` without the extension
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Deserialize)]
+pub struct MissileKind(String);
+
+impl MissileKind {
+    pub fn new(name: impl Into<String>) -> Self {
+        MissileKind(name.into())
+    }
+}
+
+impl fmt::Display for MissileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// On-disk representation of a missile type.
+/// The fields are described in `MissileSpec`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MissileFile {
+    name: String,
+    model: String,
+    mass: f32,
+    per_hardpoint: u32,
+    burn_time: f32,
+    thrust: f32,
+    drag: f32,
+    induced_drag: f32,
+    max_g: f32,
+    min_speed: f32,
+    fuse_radius: f32,
+    warhead: f32,
+    blast_radius: f32,
+    lifetime: f32,
+    #[serde(default)]
+    seeker: Option<SeekerSpec>,
+}
+
+/// Everything needed to launch a missile of a given kind
+#[derive(Clone, Debug)]
+pub struct MissileType {
+    kind: MissileKind,
+    name: String,
+    model_path: String,
+    spec: MissileSpec,
+}
+
+gen_ref_getters! {
+    MissileType,
+    name -> &String,
+    model_path -> &String,
+    spec -> &MissileSpec,
+}
+
+impl MissileType {
+    pub fn new(kind: MissileKind, name: String, model_path: String, spec: MissileSpec) -> Self {
+        Self {
+            kind,
+            name,
+            model_path,
+            spec,
+        }
+    }
+
+    /// Load and validate a single missile definition
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CatalogueError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| CatalogueError::Io(path.into(), e))?;
+        let file: MissileFile =
+            serde_json::from_str(&contents).map_err(|e| CatalogueError::Format(path.into(), e))?;
+        let invalid = |field, reason| {
+            Err(CatalogueError::Invalid {
+                path: path.into(),
+                field,
+                reason,
+            })
+        };
+
+        if file.name.trim().is_empty() {
+            return invalid("name", "must not be empty");
+        }
+        if !Path::new(&file.model).is_file() {
+            return invalid("model", "file does not exist");
+        }
+        if file.mass < 0. {
+            return invalid("mass", "must not be negative");
+        }
+        if file.per_hardpoint == 0 {
+            return invalid(
+                "per_hardpoint",
+                "at least one missile must fit on a hardpoint",
+            );
+        }
+        if file.burn_time < 0. {
+            return invalid("burn_time", "must not be negative");
+        }
+        if file.thrust < 0. {
+            return invalid("thrust", "must not be negative");
+        }
+        if file.drag < 0. {
+            return invalid("drag", "must not be negative");
+        }
+        if file.induced_drag < 0. {
+            return invalid("induced_drag", "must not be negative");
+        }
+        if file.fuse_radius <= 0. {
+            return invalid("fuse_radius", "must be positive");
+        }
+        if file.lifetime <= 0. {
+            return invalid("lifetime", "must be positive");
+        }
+        if file.warhead <= 0. {
+            return invalid("warhead", "must be positive");
+        }
+        if file.blast_radius < file.fuse_radius {
+            return invalid(
+                "blast_radius",
+                "the blast must reach at least as far as the fuse",
+            );
+        }
+        if let Some(seeker) = &file.seeker {
+            if seeker.fov <= 0. || seeker.fov > 180_f32.to_radians() {
+                return invalid("seeker", "field of view must be between 0 and 180 degrees");
+            }
+            if seeker.range <= 0. || seeker.lock_time < 0. {
+                return invalid(
+                    "seeker",
+                    "range must be positive and lock time not negative",
+                );
+            }
+            if let SeekerKind::Radar { active_range } = seeker.kind {
+                if active_range > seeker.range {
+                    return invalid("seeker", "active range must not exceed the range");
+                }
+            }
+        }
+
+        let kind = MissileKind::new(
+            path.file_stem()
+                .map(|s| s.to_string_lossy())
+                .unwrap_or_default(),
+        );
+        Ok(MissileType::new(
+            kind,
+            file.name,
+            file.model,
+            MissileSpec {
+                mass: file.mass,
+                per_hardpoint: file.per_hardpoint,
+                burn_time: file.burn_time,
+                thrust: file.thrust,
+                drag: file.drag,
+                induced_drag: file.induced_drag,
+                max_g: file.max_g,
+                min_speed: file.min_speed,
+                fuse_radius: file.fuse_radius,
+                warhead: file.warhead,
+                blast_radius: file.blast_radius,
+                lifetime: file.lifetime,
+                seeker: file.seeker,
+            },
+        ))
+    }
+}
+
+/// All missile kinds available in the game
+#[derive(Clone, Debug)]
+pub struct MissileCatalogue {
+    map: BTreeMap<MissileKind, MissileType>,
+}
+
+impl MissileCatalogue {
+    /// Load every missile definition in a directory
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, CatalogueError> {
+        let dir = dir.as_ref();
+        let io_error = |e| CatalogueError::Io(dir.into(), e);
+        let mut map = BTreeMap::new();
+        for entry in fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let missile = MissileType::load(&path)?;
+                info!("Loaded {} from {:?}", missile.name(), path);
+                map.insert(missile.kind.clone(), missile);
+            }
+        }
+        if map.is_empty() {
+            return Err(CatalogueError::NoMissiles(dir.into()));
+        }
+        Ok(MissileCatalogue { map })
+    }
+
+    pub fn get(&self, kind: &MissileKind) -> Result<&MissileType, CatalogueError> {
+        self.map.get(kind).ok_or_else(|| {
+            CatalogueError::UnknownKind(kind.clone(), self.map.keys().cloned().collect())
+        })
+    }
+
+    /// Make sure every missile the hardpoints of the aircraft in `blueprints` can carry
+    /// is in the catalogue. Loadouts only hang missiles their hardpoints can carry.
+    pub fn check_loadouts(&self, blueprints: &Blueprints) -> Result<(), CatalogueError> {
+        blueprints
+            .iter()
+            .flat_map(|b| b.hardpoints().iter().flat_map(|h| &h.weapons))
+            .try_for_each(|kind| self.get(kind).map(|_| ()))
+    }
+}
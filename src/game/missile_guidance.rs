@@ -1,34 +1,164 @@
-use super::missile::EnemyID;
-use vek::QuadraticBezier3;
+use super::{countermeasures::DecoyID, missile::TargetID, rebase::Rebase};
+use cgmath::{InnerSpace, Point3, Vector3, Zero};
+use serde::Deserialize;
+use std::fmt;
 
-/// Number of frames after which a missile without a target gets deleted
-const TERMINATION_TIME: u32 = 5000;
+/// How a missile turns the motion of its target into a steering command
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuidanceLaw {
+    /// Always turn the nose straight at the target.
+    /// Ends up in a tail chase and needs a lot of G against crossing targets.
+    PurePursuit,
+    /// Steer in proportion to how fast the line of sight to the target rotates,
+    /// flying a collision course towards where the target is going to be
+    ProportionalNavigation,
+    /// Proportional navigation that also leads the target's own acceleration,
+    /// so it keeps up better with turning targets
+    #[serde(rename = "augmented_proportional_navigation")]
+    AugmentedPN,
+}
+
+impl fmt::Display for GuidanceLaw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GuidanceLaw::PurePursuit => "pure pursuit",
+            GuidanceLaw::ProportionalNavigation => "proportional navigation",
+            GuidanceLaw::AugmentedPN => "augmented proportional navigation",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// What the seeker has seen of a target
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Track {
+    pub position: Point3<f32>,
+    pub velocity: Vector3<f32>,
+    pub acceleration: Vector3<f32>,
+}
+
+impl GuidanceLaw {
+    /// Acceleration in m/s^2 the missile should pull to hit the target.
+    /// `navigation_constant` scales the proportional navigation laws,
+    /// and how sharply pure pursuit swings the nose at the target.
+    pub fn command(
+        &self,
+        navigation_constant: f32,
+        position: Point3<f32>,
+        velocity: Vector3<f32>,
+        target: &Track,
+    ) -> Vector3<f32> {
+        let line_of_sight = target.position - position;
+        let range = line_of_sight.magnitude();
+        if range < f32::EPSILON {
+            return Vector3::zero();
+        }
+        let direction = line_of_sight / range;
+        match self {
+            GuidanceLaw::PurePursuit => {
+                // Turn the velocity towards the line of sight, faster the further off it is
+                let speed = velocity.magnitude();
+                (direction * speed - velocity) * navigation_constant
+            }
+            GuidanceLaw::ProportionalNavigation | GuidanceLaw::AugmentedPN => {
+                let relative_velocity = target.velocity - velocity;
+                let closing_speed = -relative_velocity.dot(direction);
+                // How fast the direction to the target turns
+                let rotation =
+                    (relative_velocity - direction * relative_velocity.dot(direction)) / range;
+                let command = rotation * navigation_constant * closing_speed;
+                if *self == GuidanceLaw::AugmentedPN {
+                    let across =
+                        target.acceleration - direction * target.acceleration.dot(direction);
+                    command + across * navigation_constant / 2.
+                } else {
+                    command
+                }
+            }
+        }
+    }
+}
 
+/// What the missile knows about the target it's homing in on
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct GuidanceData {
-    pub target_id: EnemyID,
-    pub bezier: QuadraticBezier3<f32>,
-    pub progress: f32,
+    pub target_id: TargetID,
+    /// Decoy the seeker took for the target and homes in on instead of it
+    pub decoy: Option<DecoyID>,
+    /// Last sighting of the target.
+    /// Its velocity and acceleration are estimated from how it moved between sightings.
+    pub track: Option<Track>,
+    /// Whether the track's velocity comes from two sightings yet
+    /// and can be used to estimate the acceleration
+    tracking: bool,
 }
 
-type TerminationTimer = u32;
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GuidanceStatus {
-    /// Contains an integer representing the number of ticks left until termination.
-    None(TerminationTimer),
+    /// Flying ballistic, either without a target or after losing it
+    None,
     /// Contains data necessary for guidance
     Active(GuidanceData),
 }
 
 impl GuidanceStatus {
     pub fn none() -> Self {
-        GuidanceStatus::None(TERMINATION_TIME)
+        GuidanceStatus::None
     }
-    pub fn new(target_id: EnemyID, bezier: QuadraticBezier3<f32>) -> Self {
+    pub fn new(target_id: TargetID) -> Self {
         Self::Active(GuidanceData {
             target_id,
-            bezier,
-            progress: 0.,
+            decoy: None,
+            track: None,
+            tracking: false,
         })
     }
 }
+
+impl GuidanceData {
+    /// Update the track with where the target is now
+    pub fn sight(&mut self, position: Point3<f32>, delta_time: f32) -> Track {
+        let track = match self.track {
+            Some(last) if delta_time > 0. => {
+                let velocity = (position - last.position) / delta_time;
+                let acceleration = if self.tracking {
+                    (velocity - last.velocity) / delta_time
+                } else {
+                    Vector3::zero()
+                };
+                self.tracking = true;
+                Track {
+                    position,
+                    velocity,
+                    acceleration,
+                }
+            }
+            _ => Track {
+                position,
+                velocity: Vector3::zero(),
+                acceleration: Vector3::zero(),
+            },
+        };
+        self.track = Some(track);
+        track
+    }
+}
+
+impl GuidanceData {
+    /// Home in on a decoy from now on. The track starts over,
+    /// the jump to the decoy's position isn't a movement of the target.
+    pub fn seduce(&mut self, decoy: DecoyID) {
+        self.decoy = Some(decoy);
+        self.track = None;
+        self.tracking = false;
+    }
+}
+
+impl Rebase for GuidanceData {
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        if let Some(track) = &mut self.track {
+            track.position += shift;
+        }
+    }
+}
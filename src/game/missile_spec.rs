@@ -0,0 +1,105 @@
+use super::{
+    flight::{dynamics::Pose, spec::degrees},
+    missile_guidance::GuidanceLaw,
+};
+use cgmath::{InnerSpace, Point3};
+use serde::Deserialize;
+
+/// Physical properties of a missile and of its seeker
+#[derive(Clone, Copy, Debug)]
+pub struct MissileSpec {
+    /// Weight in kg each missile adds to the aircraft carrying it
+    pub mass: f32,
+    /// How many fit on one hardpoint, like rockets in a pod
+    pub per_hardpoint: u32,
+    /// Seconds the rocket motor burns after launch
+    pub burn_time: f32,
+    /// Acceleration the motor gives the missile in m/s^2 while it burns
+    pub thrust: f32,
+    /// Deceleration in m/s^2 per square of the speed in m/s
+    pub drag: f32,
+    /// Extra deceleration per unit of sideways acceleration the missile pulls.
+    /// Hard turns bleed speed.
+    pub induced_drag: f32,
+    /// Most sideways acceleration the fins can pull, in multiples of gravity
+    pub max_g: f32,
+    /// Speed in m/s below which the fins can't steer any more
+    pub min_speed: f32,
+    /// Distance to the target in m that sets off the proximity fuse
+    pub fuse_radius: f32,
+    /// Damage the warhead does to an aircraft right where it goes off.
+    /// It gets weaker further away and does none past `blast_radius`.
+    pub warhead: f32,
+    /// Furthest in m the blast reaches
+    pub blast_radius: f32,
+    /// Seconds after launch the missile destroys itself
+    pub lifetime: f32,
+    /// Unguided rockets have no seeker
+    pub seeker: Option<SeekerSpec>,
+}
+
+/// How a guided missile finds its target and steers towards it
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeekerSpec {
+    pub kind: SeekerKind,
+    /// Angle in radians between the missile's nose and the edge of the seeker's view.
+    /// Given in degrees in missile files.
+    #[serde(deserialize_with = "degrees")]
+    pub fov: f32,
+    /// Furthest the seeker can see a target from, in m
+    pub range: f32,
+    /// Seconds the launching aircraft needs to keep its nose on a target to lock on
+    pub lock_time: f32,
+    pub guidance: GuidanceLaw,
+    /// Gain of the guidance law
+    pub navigation_constant: f32,
+}
+
+/// What the seeker homes in on
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum SeekerKind {
+    /// Sees the hot exhaust of the target, so only from behind.
+    /// Fire and forget.
+    Infrared {
+        /// Largest angle in radians between the target's tail and the seeker
+        /// at which the exhaust can still be seen.
+        /// Given in degrees in missile files.
+        #[serde(deserialize_with = "degrees")]
+        rear_aspect: f32,
+    },
+    /// Sees the target from any side, but only once it's within `active_range` in m.
+    /// Until then the launching aircraft has to keep its lock to guide the missile.
+    Radar { active_range: f32 },
+}
+
+impl SeekerSpec {
+    /// Whether a seeker placed at `from` can see a target placed at `target`
+    pub fn sees(&self, from: &Pose, target: &Pose) -> bool {
+        let in_view = self.in_view(from, target.position);
+        match self.kind {
+            SeekerKind::Infrared { rear_aspect } => {
+                let to_target = target.position - from.position;
+                in_view && target.front().angle(to_target).0 <= rear_aspect
+            }
+            SeekerKind::Radar { .. } => in_view,
+        }
+    }
+
+    /// Whether something at `position` is within the range and the field of view
+    /// of a seeker placed at `from`. Decoys can be seen from any side.
+    pub fn in_view(&self, from: &Pose, position: Point3<f32>) -> bool {
+        let to_target = position - from.position;
+        to_target.magnitude() <= self.range && from.front().angle(to_target).0 <= self.fov
+    }
+
+    /// Whether the missile guides itself from `position` on,
+    /// instead of relying on the launching aircraft's lock
+    pub fn is_active(&self, position: Point3<f32>, target: Point3<f32>) -> bool {
+        match self.kind {
+            SeekerKind::Infrared { .. } => true,
+            SeekerKind::Radar { active_range } => (target - position).magnitude() <= active_range,
+        }
+    }
+}
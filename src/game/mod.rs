@@ -1,18 +1,39 @@
+pub mod bindings;
+pub mod camera_rig;
+pub mod chunk;
+pub mod clock;
+pub mod countermeasures;
 pub mod drawable;
 pub mod enemies;
 pub mod enemy;
+pub mod explosion;
 pub mod flight;
 #[allow(clippy::module_inception)]
 pub mod game;
 pub mod guns;
+pub mod heightfield;
 pub mod hud;
 pub mod id_gen;
+pub mod input;
+pub mod joystick;
+pub mod keys;
+pub mod lod;
 pub mod matrix_fmt;
 pub mod missile;
+pub mod missile_catalogue;
 pub mod missile_guidance;
+pub mod missile_spec;
 pub mod modeled;
 pub mod particle_generation;
 pub mod player;
+pub mod rebase;
+pub mod replay;
+pub mod rng;
+pub mod score;
+pub mod simulation;
+pub mod state;
 pub mod targeting_data;
 pub mod targeting_sounds;
 pub mod terrain;
+pub mod terrain_gen;
+pub mod world;
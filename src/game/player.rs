@@ -1,77 +1,141 @@
-use super::{enemies::Enemies, flight::aircraft::{Aircraft, AircraftKind}, missile::EnemyID};
-use crate::{audio::sound::SoundID, game::flight::steerable::Steerable, gen_ref_getters, cg::{
-        camera::{Camera, ControlSurfaces, Movement, Movement::*},
+use super::{
+    enemies::Enemies,
+    flight::{
+        aircraft::Aircraft,
+        blueprint::Blueprint,
+        dynamics::{FlightState, Pose, MAX_FLIGHT_STEP},
+        mouse_aim,
+    },
+    missile::EnemyID,
+    missile_spec::SeekerSpec,
+    rebase::Rebase,
+};
+use crate::{
+    cg::{
+        camera::{ControlSurfaces, Movement, Movement::*},
         model::Model,
-    }, DELTA_TIME};
-use cgmath::InnerSpace;
+    },
+    game::flight::steerable::Steerable,
+    gen_ref_getters,
+};
+use cgmath::{InnerSpace, Point3, Vector3};
 use itertools::Itertools;
 use log::warn;
 
+/// Airspeed of the player's aircraft at the start of the game in m/s
+const INITIAL_SPEED: f32 = 150.;
+/// Where the player's aircraft starts, level and heading along -z
+const START_POSITION: Point3<f32> = Point3 {
+    x: 0.,
+    y: 0.,
+    z: 5.,
+};
+/// Seconds of holding a steering key that a unit of look input amounts to in keyboard mode
+const LOOK_SENSITIVITY: f32 = 0.002;
+
 #[derive(Debug)]
 pub struct Player {
     aircraft: Aircraft,
-    camera: Camera,
+    /// Direction the player points the aircraft at with the mouse in mouse aim mode
+    aim: Vector3<f32>,
     pub cockpit: Model,
-    pub guns_sound: SoundID,
 }
 
 gen_ref_getters! {
     Player,
     aircraft -> &Aircraft,
-    camera -> &Camera,
+    aim -> &Vector3<f32>,
 }
 
-impl Default for Player {
-    fn default() -> Self {
-        Player {
-            aircraft: Aircraft::new(AircraftKind::Mig21),
-            camera: Camera::default(),
-            cockpit: Model::new("resources/objects/cockpit/cockpit_old.obj"),
-            guns_sound: SoundID::MAX,
-        }
+impl Rebase for Player {
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        self.aircraft.rebase(shift);
     }
 }
 
 impl Player {
+    /// Create a player flying an aircraft built from the blueprint
+    pub fn new(blueprint: &Blueprint) -> Self {
+        let mut player = Player {
+            aircraft: Aircraft::new(blueprint),
+            aim: Pose::default().front(),
+            cockpit: Model::new("resources/objects/cockpit/cockpit_old.obj"),
+        };
+        player.trim();
+        player
+    }
+
+    /// Put the aircraft back at the start, trimmed for level flight with what it carries
+    pub fn trim(&mut self) {
+        let level = Pose {
+            position: START_POSITION,
+            ..Pose::default()
+        };
+        *self.aircraft.flight_mut() = FlightState::new(level.front() * INITIAL_SPEED);
+        self.aircraft.trim(level);
+        self.aim = level.front();
+    }
+
     pub fn aircraft_mut(&mut self) -> &mut Aircraft {
         &mut self.aircraft
     }
     pub fn cockpit_mut(&mut self) -> &mut Model {
         &mut self.cockpit
     }
-    pub fn camera_mut(&mut self) -> &mut Camera {
-        &mut self.camera
+    /// Where the player's aircraft is and which way it's turned
+    pub fn pose(&self) -> &Pose {
+        self.aircraft.pose()
     }
 
-    /// Modify the player's position and camera based on the Controls
-    pub fn apply_controls(&mut self) {
-        let delta_time = unsafe { DELTA_TIME };
-        let c = self.aircraft.controls().clone();
-        self.camera_mut().pitch(c.pitch_bias() * delta_time);
-        self.camera_mut().yaw(c.yaw_bias() * delta_time);
-        self.camera_mut().roll(c.roll_bias() * delta_time);
-        self.camera_mut().forward(c.throttle() * delta_time);
-
-        let model = self.aircraft_mut().model_mut();
-
-        model.pitch(c.pitch_bias() * delta_time);
-        model.yaw(c.yaw_bias() * delta_time);
-        model.roll(c.roll_bias() * delta_time);
-        model.forward(c.throttle() * delta_time);
-
-        //Third person camera (not looking really good now)
-        // self.camera.position = self.aircraft().model().position()
-        //     + (self
-        //         .aircraft()
-        //         .model()
-        //         .orientation
-        //         .rotate_vector(*VEC_FRONT - vec3(-0.05, -0.5, -5.0)))
+    /// Deflect the rudder and elevator by the mouse's movement on top of what the keys and
+    /// the stick did, and let the flight model turn the aircraft.
+    /// The y axis goes from bottom to top.
+    pub fn look(&mut self, x: f32, y: f32) {
+        if y != 0. {
+            self.aircraft.pitch(y * LOOK_SENSITIVITY);
+            self.aircraft.set_decay(ControlSurfaces::Pitch, false);
+        }
+        if x != 0. {
+            // Positive yaw turns the nose left
+            self.aircraft.yaw(-x * LOOK_SENSITIVITY);
+            self.aircraft.set_decay(ControlSurfaces::Yaw, false);
+        }
+    }
+
+    /// Point the mouse aim direction somewhere else by the mouse's movement
+    pub fn turn_aim(&mut self, x: f32, y: f32) {
+        self.aim = mouse_aim::turn_aim(self.aim, (x, y));
+    }
+
+    /// Start aiming where the nose points
+    pub fn reset_aim(&mut self) {
+        self.aim = self.pose().front();
+    }
+
+    /// Let the instructor deflect the controls to fly toward the aim direction
+    pub fn fly_to_aim(&mut self) {
+        let stick = mouse_aim::instruct(&self.pose().attitude(), self.aim);
+        self.aircraft.deflect(&stick);
+    }
+
+    /// Move the player's aircraft by running the flight model with the Controls.
+    /// Long frames are flown in several steps to keep the flight model stable.
+    pub fn apply_controls(&mut self, delta_time: f32) {
+        let steps = (delta_time / MAX_FLIGHT_STEP).ceil().max(1.);
+        for _ in 0..steps as u32 {
+            self.aircraft.fly(delta_time / steps);
+        }
     }
 
-    /// Check if the player aims their nose at an enemy, triggering a missile lock
-    /// countdown on one of them (lock not implemented yet)
-    pub fn targeted_enemy_id_nth(&self, enemies: &Enemies, n: usize) -> Option<EnemyID> {
-        if let Some(enemies) = self.targetable_enemies(enemies) {
+    /// Check if the player aims their nose at an enemy the seeker can see,
+    /// triggering a missile lock countdown on one of them
+    pub fn targeted_enemy_id_nth(
+        &self,
+        enemies: &Enemies,
+        seeker: &SeekerSpec,
+        n: usize,
+    ) -> Option<EnemyID> {
+        if let Some(enemies) = self.targetable_enemies(enemies, seeker) {
             if enemies.len() <= n {
                 warn!("Requested enemy {n}, but there are only {}. Returning the last enemy available", enemies.len());
             }
@@ -81,22 +145,25 @@ impl Player {
         None
     }
 
-    pub fn targetable_enemies(&self, enemies: &Enemies) -> Option<Vec<EnemyID>> {
-        let player_front = self.camera().front;
-        let player_position = self.camera().position;
+    /// Enemies the seeker sees from the player's aircraft, closest to the nose first
+    pub fn targetable_enemies(
+        &self,
+        enemies: &Enemies,
+        seeker: &SeekerSpec,
+    ) -> Option<Vec<EnemyID>> {
+        let player_front = self.pose().front();
+        let player_position = self.pose().position;
 
         let targeted = enemies
             .map
             .iter()
-            .map(|tuple| {
-                let enemy = tuple.1;
-                let pos = enemy.aircraft().model().position();
-                let direction = (pos - player_position).normalize();
-                let deg = direction.angle(player_front).0.to_degrees();
-                (tuple.0, (deg, enemy))
+            .filter(|(_, enemy)| !enemy.aircraft().destroyed())
+            .filter(|(_, enemy)| seeker.sees(self.pose(), enemy.aircraft().pose()))
+            .map(|(id, enemy)| {
+                let direction = (enemy.position() - player_position).normalize();
+                (id, direction.angle(player_front).0)
             })
-            .filter(|&(_, (deg, _))| deg < 20.)
-            .sorted_by(|t1, t2| t1.1 .0.partial_cmp(&t2.1 .0).unwrap())
+            .sorted_by(|t1, t2| t1.1.partial_cmp(&t2.1).unwrap())
             .map(|(id, _)| *id)
             .collect_vec();
         if targeted.is_empty() {
@@ -107,8 +174,8 @@ impl Player {
     }
 
     /// Handle key events meant for player controls.
-    pub fn process_key(&mut self, direction: Movement) {
-        let velocity = unsafe { DELTA_TIME };
+    pub fn process_key(&mut self, direction: Movement, delta_time: f32) {
+        let velocity = delta_time;
         match direction {
             PitchUp => {
                 self.aircraft_mut().pitch(velocity);
@@ -135,10 +202,10 @@ impl Player {
                 self.aircraft_mut().set_decay(ControlSurfaces::Roll, false);
             }
             ThrottleUp => {
-                self.aircraft_mut().throttle_up();
+                self.aircraft_mut().throttle_up(delta_time);
             }
             ThrottleDown => {
-                self.aircraft_mut().throttle_down();
+                self.aircraft_mut().throttle_down(delta_time);
             }
         }
     }
@@ -0,0 +1,10 @@
+use cgmath::Vector3;
+
+/// Anything placed in world space.
+/// To keep coordinates small, and therefore precise, around the player,
+/// the origin of the world follows them in big jumps.
+/// Everything in the world then gets moved by the same shift.
+pub trait Rebase {
+    /// Move by `shift`: the position of the old origin relative to the new one
+    fn rebase(&mut self, shift: Vector3<f32>);
+}
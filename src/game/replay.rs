@@ -0,0 +1,172 @@
+use super::{
+    clock::GameClock,
+    flight::aircraft::AircraftKind,
+    input::{Action, InputSnapshot, StickInput},
+    rng::GameRng,
+    terrain_gen::Biome,
+};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, io, path::Path};
+
+/// Version of the replay file format.
+/// Bump it whenever the format or anything affecting determinism changes,
+/// so that old replays are rejected instead of silently playing out differently.
+pub const REPLAY_VERSION: u32 = 20;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "{e}"),
+            ReplayError::Format(e) => write!(f, "malformed replay: {e}"),
+            ReplayError::UnsupportedVersion(v) => write!(
+                f,
+                "replay version {v} is not supported, expected {REPLAY_VERSION}"
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(e: serde_json::Error) -> Self {
+        ReplayError::Format(e)
+    }
+}
+
+/// A recorded game session: everything needed to play it out again
+/// exactly the same way. The seed and clock settings recreate the initial
+/// state, the per-frame input snapshots drive it from there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub aircraft: AircraftKind,
+    pub biome: Biome,
+    pub timestep: Option<f32>,
+    pub time_scale: f32,
+    pub frames: Vec<InputSnapshot>,
+}
+
+/// On-disk representation of a replay
+#[derive(Serialize, Deserialize)]
+struct ReplayFile {
+    version: u32,
+    seed: u64,
+    aircraft: String,
+    biome: Biome,
+    timestep: Option<f32>,
+    time_scale: f32,
+    frames: Vec<ReplayFrame>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReplayFrame {
+    delta: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    held: Vec<Action>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pressed: Vec<Action>,
+    #[serde(default, skip_serializing_if = "StickInput::is_idle")]
+    stick: StickInput,
+    #[serde(default)]
+    cursor: (f32, f32),
+    #[serde(default)]
+    scroll: f32,
+}
+
+impl Replay {
+    /// Start an empty replay of a session using the given seed, player aircraft, biome and clock
+    pub fn new(seed: u64, aircraft: AircraftKind, biome: Biome, clock: &GameClock) -> Self {
+        Self {
+            seed,
+            aircraft,
+            biome,
+            timestep: clock.fixed_step(),
+            time_scale: clock.scale(),
+            frames: vec![],
+        }
+    }
+
+    pub fn push(&mut self, snapshot: &InputSnapshot) {
+        self.frames.push(snapshot.clone());
+    }
+
+    /// The clock the recorded session was started with
+    pub fn clock(&self) -> GameClock {
+        GameClock::new(self.timestep, self.time_scale)
+    }
+
+    /// The RNG the recorded session was started with
+    pub fn rng(&self) -> GameRng {
+        GameRng::new(self.seed)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let file: ReplayFile = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if file.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(file.version));
+        }
+        let frames = file
+            .frames
+            .into_iter()
+            .map(|f| InputSnapshot {
+                delta: f.delta,
+                held: f.held,
+                pressed: f.pressed,
+                stick: f.stick,
+                cursor: f.cursor,
+                scroll: f.scroll,
+            })
+            .collect();
+        Ok(Self {
+            seed: file.seed,
+            aircraft: AircraftKind::new(file.aircraft),
+            biome: file.biome,
+            timestep: file.timestep,
+            time_scale: file.time_scale,
+            frames,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let file = ReplayFile {
+            version: REPLAY_VERSION,
+            seed: self.seed,
+            aircraft: self.aircraft.to_string(),
+            biome: self.biome,
+            timestep: self.timestep,
+            time_scale: self.time_scale,
+            frames: self
+                .frames
+                .iter()
+                .map(|s| ReplayFrame {
+                    delta: s.delta,
+                    held: s.held.clone(),
+                    pressed: s.pressed.clone(),
+                    stick: s.stick,
+                    cursor: s.cursor,
+                    scroll: s.scroll,
+                })
+                .collect(),
+        };
+        fs::write(&path, serde_json::to_string(&file)?)?;
+        info!(
+            "Saved replay of {} frames to {:?}",
+            self.frames.len(),
+            path.as_ref()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use log::info;
+use rand::{prng::ChaChaRng, FromEntropy, Rng, SeedableRng};
+
+/// Random number generator used by every subsystem.
+/// ChaCha is used instead of `StdRng` because its output is guaranteed
+/// to stay the same across platforms and versions of `rand`.
+pub type RngStream = ChaChaRng;
+
+/// The single source of randomness of a game session.
+/// Every subsystem draws from its own stream, so e.g. firing the guns
+/// more often doesn't change where the enemies will fly.
+/// Two sessions started with the same seed and fed the same input play out identically.
+#[derive(Debug, Clone)]
+pub struct GameRng {
+    seed: u64,
+    pub enemies: RngStream,
+    pub guns: RngStream,
+    pub particles: RngStream,
+    pub terrain: RngStream,
+    pub countermeasures: RngStream,
+    pub damage: RngStream,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        info!("Seeding game RNG with {seed}");
+        let mut master = RngStream::seed_from_u64(seed);
+        // The order of the streams must never change, otherwise old seeds
+        // (and replays recorded with them) stop reproducing the same game
+        Self {
+            seed,
+            enemies: RngStream::from_rng(&mut master).expect("Seed enemies RNG stream"),
+            guns: RngStream::from_rng(&mut master).expect("Seed guns RNG stream"),
+            particles: RngStream::from_rng(&mut master).expect("Seed particles RNG stream"),
+            terrain: RngStream::from_rng(&mut master).expect("Seed terrain RNG stream"),
+            countermeasures: RngStream::from_rng(&mut master)
+                .expect("Seed countermeasures RNG stream"),
+            damage: RngStream::from_rng(&mut master).expect("Seed damage RNG stream"),
+        }
+    }
+
+    /// Seed the game from system entropy. The seed is logged,
+    /// so that the session can still be reproduced later.
+    pub fn from_entropy() -> Self {
+        Self::new(RngStream::from_entropy().gen())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
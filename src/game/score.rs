@@ -0,0 +1,42 @@
+use super::world::WorldEvent;
+use std::fmt;
+
+/// Results of the player's sorties, shown when they crash or are shot down
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Score {
+    pub enemies_destroyed: u32,
+    pub missiles_fired: u32,
+    pub crashes: u32,
+    /// Times the player's aircraft was destroyed by enemy fire
+    pub shot_down: u32,
+    /// Seconds spent in the air
+    pub flight_time: f64,
+}
+
+impl Score {
+    /// Count everything worth counting among a step's events
+    pub fn record(&mut self, events: &[WorldEvent]) {
+        events.iter().for_each(|event| match event {
+            WorldEvent::EnemyDestroyed(_) => self.enemies_destroyed += 1,
+            WorldEvent::MissileLaunched => self.missiles_fired += 1,
+            WorldEvent::TerrainCollision => self.crashes += 1,
+            WorldEvent::ShotDown => self.shot_down += 1,
+            _ => {}
+        });
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} enemies destroyed, {} missiles fired, {} crashes, shot down {} times, \
+            {:.0}s in the air",
+            self.enemies_destroyed,
+            self.missiles_fired,
+            self.crashes,
+            self.shot_down,
+            self.flight_time
+        )
+    }
+}
@@ -0,0 +1,126 @@
+use super::{
+    clock::GameClock,
+    input::{Action, FrameInput, InputSnapshot},
+    replay::Replay,
+    world::{World, WorldEvent},
+};
+use crate::gen_ref_getters;
+use log::info;
+
+/// Slowest and fastest speed of game time reachable with the time scale keys
+const MIN_TIME_SCALE: f32 = 0.125;
+const MAX_TIME_SCALE: f32 = 8.;
+
+/// Drives the `World` with raw input. Each frame's `InputSnapshot` is
+/// translated into actions and spent in as many steps as the clock says
+/// the frame is worth. Shared by the windowed game and headless runs,
+/// so both (and replays of both) behave the same.
+pub struct Simulation {
+    world: World,
+    clock: GameClock,
+    /// Mouse and throttle lever movement which couldn't be applied yet
+    /// because the clock produced no steps
+    pending: FrameInput,
+    recording: Option<Replay>,
+}
+
+gen_ref_getters! {
+    Simulation,
+    world -> &World,
+    clock -> &GameClock,
+    recording -> &Option<Replay>,
+}
+
+impl Simulation {
+    pub fn new(world: World, clock: GameClock) -> Self {
+        Self {
+            world,
+            clock,
+            pending: FrameInput::default(),
+            recording: None,
+        }
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /// Stop or restart game time. Nothing happens in the world while it's stopped.
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused == self.clock.is_paused() {
+            return;
+        }
+        if paused {
+            info!("Paused");
+            self.clock.pause();
+        } else {
+            info!("Resumed");
+            self.clock.resume();
+        }
+    }
+
+    /// Start recording every snapshot passed to `advance` into a replay
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Replay::new(
+            self.world.seed(),
+            self.world.player().aircraft().kind().clone(),
+            self.world.terrain().biome(),
+            &self.clock,
+        ));
+    }
+
+    /// Run all simulation steps a single frame of input is worth.
+    /// Returns the events of all of them.
+    pub fn advance(&mut self, snapshot: &InputSnapshot) -> Vec<WorldEvent> {
+        if let Some(recording) = &mut self.recording {
+            recording.push(snapshot);
+        }
+        snapshot
+            .pressed
+            .iter()
+            .for_each(|action| self.process_press(*action));
+
+        let mut input = snapshot.frame_input();
+        input.look.0 += self.pending.look.0;
+        input.look.1 += self.pending.look.1;
+        input.stick.throttle = input.stick.throttle.or(self.pending.stick.throttle);
+
+        // Presses made while time stands still, like choosing a loadout
+        // before takeoff, don't step the world
+        let frames = if snapshot.delta > 0. {
+            self.clock.tick(snapshot.delta)
+        } else {
+            vec![]
+        };
+        if frames.is_empty() {
+            self.pending = input;
+            return vec![];
+        }
+        self.pending = FrameInput::default();
+
+        let mut events = vec![];
+        for frame in frames {
+            events.extend(self.world.update(&input, &frame));
+            // Mouse movement is applied once, not on every step
+            input.look = (0., 0.);
+        }
+        events
+    }
+
+    /// Handle actions which happen once per press instead of for as long as they are held
+    fn process_press(&mut self, action: Action) {
+        match action {
+            Action::Respawn if self.world.crashed() => self.world.respawn_player(),
+            Action::SlowDown | Action::SpeedUp => {
+                let factor = if action == Action::SlowDown { 0.5 } else { 2. };
+                let scale = (self.clock.scale() * factor).clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+                info!("Time scale: {scale}");
+                self.clock.set_scale(scale);
+            }
+            Action::ToggleMouseAim => self.world.toggle_control_mode(),
+            Action::CycleWeapon => self.world.cycle_weapon(),
+            Action::CycleLoadout => self.world.cycle_loadout(),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use glfw::Key;
+use std::fmt;
+
+/// Screens of the windowed game. Each one reads input and draws in its own way,
+/// the `Game` only ever is in one of them.
+///
+/// ```text
+/// Menu -> Briefing -> Flight <-> Paused
+///  ^ ^                  |          |
+///  | +---- Debrief <----+          |
+///  +-------------------------------+
+/// ```
+///
+/// A replay goes straight from the debrief back to flight when it takes off again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    /// Title screen, waiting for the player to start a sortie
+    Menu,
+    /// Description of the upcoming sortie
+    Briefing,
+    /// The player is flying
+    Flight,
+    /// The sortie is frozen until the player comes back to it
+    Paused,
+    /// Summary of the sortie which just ended
+    Debrief,
+}
+
+/// What makes the game move between states
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateEvent {
+    /// A key went down
+    Pressed(Key),
+    /// The player's aircraft was lost
+    SortieEnded,
+    /// The replay being played back ran out of frames
+    ReplayFinished,
+    /// A frame of the replay being played back takes off again after a crash.
+    /// Replays only record the simulation's input, not the screens the player went through.
+    ReplayRespawned,
+}
+
+/// Result of handling a `StateEvent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// The event means nothing to the current state and should be passed on
+    Ignored,
+    /// The event was used up without changing the state
+    Handled,
+    To(GameState),
+    Quit,
+}
+
+impl GameState {
+    /// Where the game goes from this state after `event`
+    pub fn handle(self, event: StateEvent) -> Transition {
+        use GameState::*;
+        use StateEvent::*;
+        match (self, event) {
+            (_, ReplayFinished) => Transition::Quit,
+            (Menu, Pressed(Key::Enter)) => Transition::To(Briefing),
+            (Menu, Pressed(Key::Escape)) => Transition::Quit,
+            (Briefing, Pressed(Key::Enter)) => Transition::To(Flight),
+            (Briefing, Pressed(Key::Escape)) => Transition::To(Menu),
+            (Flight, Pressed(Key::Escape | Key::P)) => Transition::To(Paused),
+            (Flight | Paused, SortieEnded) => Transition::To(Debrief),
+            (Paused, Pressed(Key::Escape | Key::P)) => Transition::To(Flight),
+            (Paused, Pressed(Key::Q)) => Transition::To(Menu),
+            (Debrief, Pressed(Key::Enter | Key::Escape)) => Transition::To(Menu),
+            (Debrief, ReplayRespawned) => Transition::To(Flight),
+            // Flight passes keys on to the simulation,
+            // and the briefing lets bound actions such as changing the loadout through
+            (Flight | Briefing, _) => Transition::Ignored,
+            _ => Transition::Handled,
+        }
+    }
+
+    /// Whether the simulation keeps running in this state.
+    /// It goes on during the debrief, so that the wreck finishes burning behind it.
+    pub fn simulates(self) -> bool {
+        matches!(self, GameState::Flight | GameState::Debrief)
+    }
+
+    /// Whether the cockpit and the HUD are drawn over the world
+    pub fn shows_cockpit(self) -> bool {
+        matches!(self, GameState::Flight | GameState::Paused)
+    }
+
+    /// Keys the player can press in this state and what they do
+    pub fn controls(self) -> &'static str {
+        match self {
+            GameState::Menu => "Enter: start a sortie, Escape: quit",
+            GameState::Briefing => "Enter: take off, L: change loadout, Escape: back to the menu",
+            GameState::Flight => "P/Escape: pause",
+            GameState::Paused => "P/Escape: resume, Q: back to the menu",
+            GameState::Debrief => "Enter: back to the menu",
+        }
+    }
+}
+
+impl fmt::Display for GameState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GameState::Menu => "Main menu",
+            GameState::Briefing => "Briefing",
+            GameState::Flight => "Flight",
+            GameState::Paused => "Paused",
+            GameState::Debrief => "Debrief",
+        };
+        write!(f, "{name}")
+    }
+}
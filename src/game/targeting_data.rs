@@ -1,7 +1,5 @@
 use super::missile::EnemyID;
 
-const LOCKING_TIME: f64 = 4.0;
-
 #[derive(Debug)]
 pub struct TargetingData {
     pub target_id: EnemyID,
@@ -9,10 +7,15 @@ pub struct TargetingData {
 }
 
 impl TargetingData {
-    pub fn new(id: EnemyID) -> Self {
+    /// Start locking on to an enemy, which takes `lock_time` seconds
+    pub fn new(id: EnemyID, lock_time: f64) -> Self {
         Self {
             target_id: id,
-            left_until_lock: LOCKING_TIME,
+            left_until_lock: lock_time,
         }
     }
+
+    pub fn locked(&self) -> bool {
+        self.left_until_lock <= 0.
+    }
 }
@@ -1,79 +1,211 @@
-use super::{drawable::Drawable, modeled::Modeled};
-use crate::cg::{model::Model, shader::Shader};
-use cgmath::Point2;
-use lazy_static::lazy_static;
+use super::{
+    chunk::{chunk_corner, Chunk, ChunkCoord, CHUNK_RESOLUTION, CHUNK_SIZE},
+    drawable::Drawable,
+    lod::{self, LodKey},
+    terrain_gen::{Biome, TerrainGenerator},
+};
+use crate::cg::{index_buffer::IndexBuffer, model::is_headless, shader::Shader};
+use cgmath::{vec3, InnerSpace, Point2, Point3, Vector2, Vector3};
 use log::info;
-use std::collections::HashMap;
-use std::ops::Range;
+use std::{collections::BTreeMap, ops::Range};
 
-lazy_static! {
-    static ref TERRAINS: HashMap<TerrainType, &'static str> =
-        //HashMap::from([(TerrainType::Desert, "resources/objects/terrain/teren2.obj")]);
-        HashMap::from([(TerrainType::Desert, "resources/objects/terrain/terrain.obj")]);
-}
-
-#[derive(Hash, PartialEq, Eq)]
-pub enum TerrainType {
-    Desert,
-}
+/// How many chunks around the player's one are kept loaded in each direction
+const LOAD_RADIUS: i32 = 2;
+/// Chunks further than this from the player's one get unloaded.
+/// Larger than `LOAD_RADIUS`, so that flying along the edge of a chunk
+/// doesn't load and unload the same chunks over and over.
+const UNLOAD_RADIUS: i32 = 3;
+/// How many chunks away from the origin the player can get before the origin jumps to them
+const REBASE_RADIUS: i32 = 2;
 
+/// Area covered by the terrain in world coordinates
 pub struct Bounds {
-    pub x: Range<i32>,
-    pub z: Range<i32>,
+    pub x: Range<f32>,
+    pub z: Range<f32>,
 }
 
+/// The ground. An endless grid of chunks generated from noise,
+/// of which only the ones around the player are kept loaded.
+///
+/// World coordinates are f32 and relative to the corner of the origin chunk.
+/// The origin follows the player, so that the coordinates around them never get large
+/// enough to lose precision. The absolute position of anything is the origin chunk's corner
+/// plus its world coordinates, which the generator samples in f64.
 pub struct Terrain {
-    pub model: Model,
-    pub heights: HashMap<Point2<i32>, f32>,
-    pub bounds: Bounds,
+    generator: TerrainGenerator,
+    biome: Biome,
+    chunks: BTreeMap<ChunkCoord, Chunk>,
+    /// Chunk whose corner the origin of world coordinates sits at
+    origin: ChunkCoord,
+    /// Height of the lowest possible ground in world coordinates
+    base: f32,
+    /// Level of detail each chunk is drawn with
+    lods: BTreeMap<ChunkCoord, LodKey>,
+    /// Triangles of every level of detail used so far.
+    /// All chunks share them, since their grids are laid out the same way.
+    index_buffers: BTreeMap<LodKey, IndexBuffer>,
 }
 
 impl Terrain {
-    pub fn new(path: &str, type_: TerrainType) -> Self {
-        info!("Creating new Terrain with template: {path}",);
-        let model = Model::new(TERRAINS.get(&type_).expect("Path for terrain kind exists"));
-        let heights = Terrain::heights_of(&model);
-        let bounds = model.bounds();
-        Terrain {
-            model,
-            heights,
-            bounds,
+    /// Generate the terrain of a biome with its lowest possible ground at height `base`.
+    /// The same seed always gives the same terrain.
+    pub fn new(biome: Biome, seed: u64, base: f32) -> Self {
+        info!("Generating {biome} terrain with seed {seed}");
+        let mut terrain = Terrain {
+            generator: TerrainGenerator::new(biome, seed),
+            biome,
+            chunks: BTreeMap::new(),
+            origin: (0, 0),
+            base,
+            lods: BTreeMap::new(),
+            index_buffers: BTreeMap::new(),
+        };
+        terrain.stream(Point2::new(0., 0.));
+        terrain
+    }
+
+    pub fn biome(&self) -> Biome {
+        self.biome
+    }
+
+    /// Load the chunks around `focus` and unload the far ones.
+    /// When `focus` gets too far from the origin, the origin jumps next to it.
+    /// Returns the shift everything else in the world has to be moved by in that case.
+    pub fn stream(&mut self, focus: Point2<f32>) -> Option<Vector3<f32>> {
+        let center = self.chunk_at(focus);
+        let shift = self.rebase(center);
+
+        let far = |coord: &ChunkCoord, radius| {
+            (coord.0 - center.0).abs() > radius || (coord.1 - center.1).abs() > radius
+        };
+        self.chunks.retain(|coord, _| !far(coord, UNLOAD_RADIUS));
+        for x in center.0 - LOAD_RADIUS..=center.0 + LOAD_RADIUS {
+            for z in center.1 - LOAD_RADIUS..=center.1 + LOAD_RADIUS {
+                if !self.chunks.contains_key(&(x, z)) {
+                    let corner = chunk_corner((x, z), self.origin, self.base);
+                    let chunk = Chunk::generate(&self.generator, (x, z), corner);
+                    self.chunks.insert((x, z), chunk);
+                }
+            }
         }
+        shift
     }
-    fn heights_of(terrain_model: &Model) -> HashMap<Point2<i32>, f32> {
-        let positions = terrain_model.vertices.iter().map(|v| v.position);
-        let defined_points: HashMap<Point2<i32>, f32> = HashMap::from_iter(
-            positions
-                .clone()
-                .map(|v| ((v.x as i32, v.z as i32).into(), v.y)),
-        );
-        defined_points
+
+    /// Pick the level of detail of every chunk based on its distance from `eye`
+    pub fn update_lod(&mut self, eye: Point3<f32>) {
+        let levels = self
+            .chunks
+            .iter()
+            .map(|(coord, chunk)| (*coord, lod::level_for_distance(chunk.distance_to(eye))))
+            .collect();
+        self.lods = lod::stitch(&levels);
+        if is_headless() {
+            return;
+        }
+        for key in self.lods.values() {
+            self.index_buffers.entry(*key).or_insert_with(|| unsafe {
+                IndexBuffer::new(&lod::indices(*key, CHUNK_RESOLUTION))
+            });
+        }
     }
-    pub fn height_at(&self, pos: &Point2<i32>) -> f32 {
-        *self.heights.get(pos).unwrap_or(&0.) + self.model.transformation.translation.y + 0.8
+
+    /// Move the origin to `center` if it's too far from it
+    fn rebase(&mut self, center: ChunkCoord) -> Option<Vector3<f32>> {
+        let (dx, dz) = (center.0 - self.origin.0, center.1 - self.origin.1);
+        if dx.abs() < REBASE_RADIUS && dz.abs() < REBASE_RADIUS {
+            return None;
+        }
+        info!("Moving the world's origin to chunk {center:?}");
+        self.origin = center;
+        for (coord, chunk) in self.chunks.iter_mut() {
+            chunk.set_corner(chunk_corner(*coord, self.origin, self.base));
+        }
+        Some(vec3(-dx as f32 * CHUNK_SIZE, 0., -dz as f32 * CHUNK_SIZE))
     }
-}
 
-impl Default for Terrain {
-    fn default() -> Self {
-        let path = TERRAINS
-            .get(&TerrainType::Desert)
-            .expect("No path for that terrain");
-        Terrain::new(path, TerrainType::Desert)
+    /// Chunk containing the given world (x, z)
+    fn chunk_at(&self, pos: Point2<f32>) -> ChunkCoord {
+        (
+            self.origin.0 + (pos.x / CHUNK_SIZE).floor() as i32,
+            self.origin.1 + (pos.y / CHUNK_SIZE).floor() as i32,
+        )
     }
-}
 
-impl Drawable for Terrain {
-    unsafe fn draw(&self, shader: &Shader) {
-        self.model().draw(shader);
+    /// Absolute position of a point given in world coordinates
+    pub fn absolute(&self, pos: Point3<f32>) -> Point3<f64> {
+        Point3::new(
+            self.origin.0 as f64 * CHUNK_SIZE as f64 + pos.x as f64,
+            pos.y as f64,
+            self.origin.1 as f64 * CHUNK_SIZE as f64 + pos.z as f64,
+        )
+    }
+
+    /// Height of the ground straight from the generator, for places with no chunk loaded
+    fn generated_height(&self, pos: Point2<f32>) -> f32 {
+        let absolute = self.absolute(Point3::new(pos.x, 0., pos.y));
+        self.base + self.generator.height(absolute.x, absolute.z)
+    }
+
+    /// Height of the ground in world coordinates at the given world (x, z)
+    pub fn height_at(&self, pos: Point2<f32>) -> f32 {
+        match self.chunks.get(&self.chunk_at(pos)) {
+            Some(chunk) => {
+                let (x, z) = chunk.to_local(pos.x, pos.y);
+                chunk.height(x, z)
+            }
+            None => self.generated_height(pos),
+        }
+    }
+
+    /// Unit vector perpendicular to the ground at the given world (x, z)
+    pub fn normal_at(&self, pos: Point2<f32>) -> Vector3<f32> {
+        match self.chunks.get(&self.chunk_at(pos)) {
+            Some(chunk) => {
+                let (x, z) = chunk.to_local(pos.x, pos.y);
+                chunk.normal(x, z)
+            }
+            None => {
+                let d = CHUNK_SIZE / (CHUNK_RESOLUTION - 1) as f32;
+                let height = |dx, dz| self.generated_height(pos + Vector2::new(dx, dz));
+                let slope_x = (height(d, 0.) - height(-d, 0.)) / (2. * d);
+                let slope_z = (height(0., d) - height(0., -d)) / (2. * d);
+                vec3(-slope_x, 1., -slope_z).normalize()
+            }
+        }
     }
-}
 
-impl Modeled for Terrain {
-    fn model(&self) -> &Model {
-        &self.model
+    /// Steepness of the ground at the given world (x, z) in radians, 0 being flat
+    pub fn slope_at(&self, pos: Point2<f32>) -> f32 {
+        self.normal_at(pos).y.clamp(-1., 1.).acos()
     }
-    fn model_mut(&mut self) -> &mut Model {
-        &mut self.model
+
+    /// Area covered by the loaded chunks in world coordinates
+    pub fn bounds(&self) -> Bounds {
+        let (min, max) = self.chunks.keys().fold(
+            ((i32::MAX, i32::MAX), (i32::MIN, i32::MIN)),
+            |(min, max), c| {
+                (
+                    (min.0.min(c.0), min.1.min(c.1)),
+                    (max.0.max(c.0), max.1.max(c.1)),
+                )
+            },
+        );
+        let min = chunk_corner(min, self.origin, self.base);
+        let max = chunk_corner(max, self.origin, self.base) + vec3(CHUNK_SIZE, 0., CHUNK_SIZE);
+        Bounds {
+            x: min.x..max.x,
+            z: min.z..max.z,
+        }
+    }
+}
+
+impl Drawable for Terrain {
+    unsafe fn draw(&self, shader: &Shader) {
+        for (coord, chunk) in self.chunks.iter() {
+            // Chunks loaded since the last update of the levels wait for the next one
+            if let Some(indices) = self.lods.get(coord).and_then(|k| self.index_buffers.get(k)) {
+                chunk.draw(shader, indices);
+            }
+        }
     }
 }
@@ -0,0 +1,236 @@
+use crate::cg::vertex::Vertex;
+use cgmath::{vec2, vec3, InnerSpace};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+use worldgen::noise::{
+    perlin::{Frequency, Lacunarity, Octaves, PerlinNoise, Persistence},
+    NoiseProvider,
+};
+
+/// Kind of landscape a sortie takes place over
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Biome {
+    Desert,
+    Mountains,
+    Coast,
+    Arctic,
+}
+
+/// Noise settings and colours a biome is generated with
+pub struct BiomeParams {
+    octaves: u32,
+    /// Frequency of the largest features in 1/m
+    frequency: f64,
+    persistence: f64,
+    lacunarity: f64,
+    /// Height difference between the lowest and the highest point in meters
+    amplitude: f32,
+    /// Raising the noise to this power flattens the lowlands and sharpens the peaks
+    sharpness: f32,
+    /// Fraction of the amplitude below which the ground is flat water
+    sea_level: Option<f32>,
+    /// Colours of the ground from the lowest to the highest point
+    palette: &'static [[u8; 3]],
+}
+
+impl Biome {
+    pub const ALL: [Biome; 4] = [Biome::Desert, Biome::Mountains, Biome::Coast, Biome::Arctic];
+
+    /// Highest the ground can get in meters above the lowest possible point
+    pub fn max_height(&self) -> f32 {
+        self.params().amplitude
+    }
+
+    pub fn params(&self) -> BiomeParams {
+        match self {
+            Biome::Desert => BiomeParams {
+                octaves: 4,
+                frequency: 1. / 900.,
+                persistence: 0.45,
+                lacunarity: 2.1,
+                amplitude: 120.,
+                sharpness: 1.,
+                sea_level: None,
+                palette: &[
+                    [194, 160, 100],
+                    [214, 182, 120],
+                    [230, 205, 150],
+                    [200, 150, 95],
+                ],
+            },
+            Biome::Mountains => BiomeParams {
+                octaves: 6,
+                frequency: 1. / 1400.,
+                persistence: 0.5,
+                lacunarity: 2.,
+                amplitude: 700.,
+                sharpness: 1.8,
+                sea_level: None,
+                palette: &[
+                    [70, 100, 50],
+                    [95, 110, 70],
+                    [120, 110, 100],
+                    [150, 145, 140],
+                    [240, 240, 245],
+                ],
+            },
+            Biome::Coast => BiomeParams {
+                octaves: 5,
+                frequency: 1. / 1200.,
+                persistence: 0.5,
+                lacunarity: 2.,
+                amplitude: 200.,
+                sharpness: 1.2,
+                sea_level: Some(0.3),
+                palette: &[
+                    [30, 70, 130],
+                    [220, 205, 150],
+                    [90, 140, 70],
+                    [70, 110, 60],
+                    [120, 115, 100],
+                ],
+            },
+            Biome::Arctic => BiomeParams {
+                octaves: 5,
+                frequency: 1. / 1000.,
+                persistence: 0.4,
+                lacunarity: 2.,
+                amplitude: 250.,
+                sharpness: 1.3,
+                sea_level: None,
+                palette: &[[200, 215, 230], [225, 235, 245], [245, 250, 255]],
+            },
+        }
+    }
+}
+
+impl fmt::Display for Biome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Biome::Desert => "desert",
+            Biome::Mountains => "mountains",
+            Biome::Coast => "coast",
+            Biome::Arctic => "arctic",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Biome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Biome::ALL
+            .into_iter()
+            .find(|b| b.to_string() == s)
+            .ok_or_else(|| format!("unknown biome `{s}`"))
+    }
+}
+
+/// Builds terrain meshes out of noise.
+/// The same biome and seed always give the same terrain.
+pub struct TerrainGenerator {
+    params: BiomeParams,
+    noise: PerlinNoise,
+    seed: u64,
+}
+
+impl TerrainGenerator {
+    pub fn new(biome: Biome, seed: u64) -> Self {
+        let params = biome.params();
+        let noise = PerlinNoise::new()
+            .set(Octaves::of(params.octaves))
+            .set(Frequency::of(params.frequency))
+            .set(Persistence::of(params.persistence))
+            .set(Lacunarity::of(params.lacunarity));
+        TerrainGenerator {
+            params,
+            noise,
+            seed,
+        }
+    }
+
+    pub fn palette(&self) -> &'static [[u8; 3]] {
+        self.params.palette
+    }
+
+    /// Height of the ground at the given point as a fraction of the biome's amplitude.
+    /// Takes absolute coordinates in meters, which stay precise however far they are from the origin.
+    fn relative_height(&self, x: f64, z: f64) -> f32 {
+        let p = &self.params;
+        // The noise sums octaves in [-1, 1] with decreasing weights, scale it back into [0, 1]
+        let total_weight: f64 = (0..p.octaves).map(|o| p.persistence.powi(o as i32)).sum();
+        let noise = self.noise.generate(x, z, self.seed) / total_weight;
+        let height = ((noise as f32 + 1.) / 2.).clamp(0., 1.).powf(p.sharpness);
+        height.max(p.sea_level.unwrap_or(0.))
+    }
+
+    /// Height of the ground in meters above the lowest possible point,
+    /// at the given absolute coordinates
+    pub fn height(&self, x: f64, z: f64) -> f32 {
+        self.relative_height(x, z) * self.params.amplitude
+    }
+
+    /// Build a square grid mesh of `samples` vertices per side, `step` meters apart.
+    /// The grid starts at sample `first` of an infinite grid laid over the whole world,
+    /// vertex positions are relative to that first sample.
+    /// Meshes of neighbouring parts of the grid share their edge vertices exactly.
+    ///
+    /// Texture coordinates pick the colour from the palette by height,
+    /// so the palette is meant to be used as a one pixel high texture.
+    pub fn mesh(&self, first: (i64, i64), samples: usize, step: f64) -> (Vec<Vertex>, Vec<u32>) {
+        let n = samples;
+        // One extra sample on each side, so that normals on the edges match the neighbours'
+        let padded = n + 2;
+        let heights: Vec<f32> = (0..padded * padded)
+            .map(|i| {
+                let x = first.0 + (i % padded) as i64 - 1;
+                let z = first.1 + (i / padded) as i64 - 1;
+                self.relative_height(x as f64 * step, z as f64 * step)
+            })
+            .collect();
+        let at = |x: isize, z: isize| {
+            heights[((z + 1) * padded as isize + x + 1) as usize] * self.params.amplitude
+        };
+        let step = step as f32;
+
+        let vertices = (0..n * n)
+            .map(|i| {
+                let (x, z) = ((i % n) as isize, (i / n) as isize);
+                // Central differences reaching into the padding
+                let slope_x = (at(x + 1, z) - at(x - 1, z)) / (2. * step);
+                let slope_z = (at(x, z + 1) - at(x, z - 1)) / (2. * step);
+                Vertex {
+                    position: vec3(x as f32 * step, at(x, z), z as f32 * step),
+                    normal: vec3(-slope_x, 1., -slope_z).normalize(),
+                    tex_coords: vec2(
+                        self.palette_coordinate(at(x, z) / self.params.amplitude),
+                        0.5,
+                    ),
+                    ..Vertex::default()
+                }
+            })
+            .collect();
+
+        let n = n as u32;
+        let indices = (0..n - 1)
+            .flat_map(|z| (0..n - 1).map(move |x| z * n + x))
+            .flat_map(|i| [i, i + n, i + 1, i + 1, i + n, i + n + 1])
+            .collect();
+        (vertices, indices)
+    }
+
+    /// Horizontal texture coordinate of the palette colour for a relative height.
+    /// With a sea the first colour is the water's and the rest cover the land.
+    fn palette_coordinate(&self, height: f32) -> f32 {
+        let colours = self.params.palette.len() as f32;
+        let texel = match self.params.sea_level {
+            Some(sea) if height <= sea => 0.,
+            Some(sea) => 1. + (height - sea) / (1. - sea) * (colours - 2.),
+            None => height * (colours - 1.),
+        };
+        // Sample the middle of the texel so neighbouring colours don't bleed in
+        (texel + 0.5) / colours
+    }
+}
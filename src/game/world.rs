@@ -0,0 +1,960 @@
+use super::{
+    clock::FrameTime,
+    countermeasures::{Decoy, DecoyID, DecoyKind},
+    enemies::Enemies,
+    explosion::Explosion,
+    flight::{
+        aircraft::{Aircraft, AircraftKind},
+        blueprint::Blueprints,
+        damage::Component,
+        dynamics::Pose,
+        mouse_aim::ControlMode,
+    },
+    guns::BULLET_DAMAGE,
+    input::FrameInput,
+    missile::{EnemyID, Missile, MissileID, MissileMessage, TargetID},
+    missile_catalogue::{MissileCatalogue, MissileKind, MissileType},
+    missile_spec::{SeekerKind, SeekerSpec},
+    modeled::Modeled,
+    particle_generation::ParticleGeneration,
+    player::Player,
+    rebase::Rebase,
+    rng::GameRng,
+    score::Score,
+    targeting_data::TargetingData,
+    terrain::Terrain,
+    terrain_gen::Biome,
+};
+use crate::gen_ref_getters;
+use cgmath::{MetricSpace, Point2, Point3, Vector3};
+use log::{info, warn};
+use rand::Rng;
+
+pub const TARGET_ENEMIES: usize = 3;
+pub const MISSILE_COOLDOWN: f64 = 0.5;
+pub const SWITCH_COOLDOWN: f64 = 0.5;
+/// How close to the ground the player's eyes can get before the aircraft hits it
+const CRASH_MARGIN: f32 = 0.8;
+/// How far below the player's starting altitude the highest point of the terrain lies
+const SPAWN_CLEARANCE: f32 = 150.;
+/// Aircraft kind flown by the player unless they choose another one
+pub const DEFAULT_AIRCRAFT: &str = "mig21";
+/// Distance in m at which enemies notice a missile homing in on them and release decoys
+const DEFENCE_RANGE: f32 = 2500.;
+/// Where decoys leave an aircraft relative to the pilot along the right, up and front axes
+const DISPENSER_MOUNT: [f32; 3] = [0., -1., -4.];
+/// Seconds a wreck falls at most before it breaks up in the air
+const WRECK_TIME: f32 = 10.;
+
+/// Things that happened during a single simulation step.
+/// Frontends react to them (e.g. by playing sounds) so that the
+/// simulation itself doesn't have to know about audio or rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorldEvent {
+    GunsStarted,
+    GunsStopped,
+    MissileLaunched,
+    /// A missile's seeker took a decoy for its target
+    MissileDecoyed(MissileID),
+    /// An enemy launched a missile at the player
+    MissileIncoming(MissileID),
+    LockingStarted,
+    TargetLocked,
+    TargetLost,
+    EnemyDestroyed(EnemyID),
+    WaveStarted(u32),
+    TerrainCollision,
+    /// Enemy fire damaged the player's aircraft without bringing it down
+    PlayerHit,
+    /// Enemy fire destroyed the player's aircraft, which falls until it hits the ground
+    ShotDown,
+    /// The wreck of the player's aircraft hit the ground or broke up, ending the sortie
+    WreckCrashed,
+    PlayerRespawned,
+    StallStarted,
+    StallRecovered,
+}
+
+/// The simulation core. Owns every object taking part in the fight
+/// and steps them forward based on `FrameInput`.
+/// Does not depend on a window, an OpenGL context or an audio device,
+/// so it can be run headless.
+pub struct World {
+    player: Player,
+    enemies: Enemies,
+    missiles: Vec<Missile>,
+    /// Flares and chaff released by every aircraft
+    decoys: Vec<Decoy>,
+    explosions: Vec<Explosion>,
+    terrain: Terrain,
+    targeting_data: Option<TargetingData>,
+    /// Kind of missile launched next, if the player's aircraft carries any
+    selected_missile: Option<MissileKind>,
+    /// Name of the loadout the player's aircraft is armed with when it spawns
+    loadout: String,
+    /// Whether the player's aircraft hasn't flown since it spawned,
+    /// so it can still be armed with another loadout
+    parked: bool,
+    /// Whether the player has crashed, or their wreck went down, and waits to be respawned
+    crashed: bool,
+    /// How the mouse flies the player's aircraft. Kept across respawns.
+    control_mode: ControlMode,
+    score: Score,
+    rng: GameRng,
+    blueprints: Blueprints,
+    missile_types: MissileCatalogue,
+    last_launch_time: f64,
+    /// ID the next missile launched gets
+    next_missile_id: MissileID,
+    next_decoy_id: DecoyID,
+    last_target_switch_time: f64,
+    events: Vec<WorldEvent>,
+}
+
+gen_ref_getters! {
+    World,
+    player -> &Player,
+    enemies -> &Enemies,
+    missiles -> &Vec<Missile>,
+    terrain -> &Terrain,
+    targeting_data -> &Option<TargetingData>,
+    score -> &Score,
+    control_mode -> &ControlMode,
+    selected_missile -> &Option<MissileKind>,
+    loadout -> &String,
+}
+
+impl World {
+    /// Create a new world with aircraft built from `blueprints` and armed from `missile_types`,
+    /// the player flying `player_kind` over terrain of the given biome, or a random one.
+    /// Everything random in it is derived from `rng`.
+    pub fn new(
+        mut rng: GameRng,
+        blueprints: Blueprints,
+        missile_types: MissileCatalogue,
+        player_kind: &AircraftKind,
+        biome: Option<Biome>,
+    ) -> Self {
+        let biome = biome.unwrap_or_else(|| Biome::ALL[rng.terrain.gen_range(0, Biome::ALL.len())]);
+        let base = -(biome.max_height() + SPAWN_CLEARANCE);
+        let terrain = Terrain::new(biome, rng.terrain.gen(), base);
+        let blueprint = blueprints
+            .get(player_kind)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let player = Player::new(blueprint);
+        let loadout = blueprint.loadouts()[0].name.clone();
+        let enemies = Enemies::new(
+            &blueprints,
+            &missile_types,
+            &terrain,
+            player.pose().position,
+            &mut rng.enemies,
+        );
+
+        let mut world = World {
+            player,
+            enemies,
+            missiles: vec![],
+            decoys: vec![],
+            explosions: vec![],
+            terrain,
+            targeting_data: None,
+            selected_missile: None,
+            loadout,
+            parked: true,
+            crashed: false,
+            control_mode: ControlMode::default(),
+            score: Score::default(),
+            rng,
+            blueprints,
+            missile_types,
+            last_launch_time: -MISSILE_COOLDOWN,
+            next_missile_id: 0,
+            next_decoy_id: 0,
+            last_target_switch_time: -SWITCH_COOLDOWN,
+            events: vec![],
+        };
+        world.arm_player();
+        world
+    }
+
+    pub fn player_mut(&mut self) -> &mut Player {
+        &mut self.player
+    }
+
+    pub fn enemies_mut(&mut self) -> &mut Enemies {
+        &mut self.enemies
+    }
+
+    pub fn missiles_mut(&mut self) -> &mut Vec<Missile> {
+        &mut self.missiles
+    }
+
+    pub fn decoys_mut(&mut self) -> &mut Vec<Decoy> {
+        &mut self.decoys
+    }
+
+    pub fn explosions_mut(&mut self) -> &mut Vec<Explosion> {
+        &mut self.explosions
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    pub fn crashed(&self) -> bool {
+        self.crashed
+    }
+
+    /// Whether the player's aircraft is still in the fight, neither crashed nor destroyed
+    fn player_alive(&self) -> bool {
+        !self.crashed && !self.player.aircraft().destroyed()
+    }
+
+    /// Missiles enemies launched at the player which still home in on them
+    pub fn incoming_missiles(&self) -> impl Iterator<Item = &Missile> {
+        self.missiles
+            .iter()
+            .filter(|m| m.target() == Some(TargetID::Player))
+    }
+
+    /// Advance the simulation by one step.
+    /// Returns everything that happened along the way.
+    pub fn update(&mut self, input: &FrameInput, frame: &FrameTime) -> Vec<WorldEvent> {
+        if !self.crashed {
+            if self.player.aircraft().destroyed() {
+                self.fall_player(frame);
+            } else {
+                self.fly_player(input, frame);
+            }
+        }
+        if let Some(shift) = self.terrain.stream(self.player.pose().xz()) {
+            self.rebase(shift);
+        }
+        self.terrain.update_lod(self.player.pose().position);
+        self.respawn_enemies();
+        self.enemies.map.values_mut().for_each(|e| {
+            e.fly(&self.terrain, frame, &mut self.rng.enemies);
+        });
+        self.clear_wrecks();
+        self.engage_player(frame);
+        self.enemies.map.values_mut().for_each(|e| {
+            e.aircraft_mut().guns_mut().update(frame);
+        });
+        self.check_player_hits(frame);
+        self.decoys.iter_mut().for_each(|d| {
+            d.update(frame.delta, &mut self.rng.particles);
+        });
+        self.decoys.retain(|d| !d.finished());
+        self.defend_enemies(frame.time);
+        self.update_missiles(frame);
+        self.missiles.iter_mut().for_each(|m| {
+            let position = m.model().position();
+            let front = m.model().front();
+            m.particle_generator_mut().update_particles(
+                position,
+                1,
+                front,
+                frame.delta,
+                &mut self.rng.particles,
+            );
+        });
+        self.missiles.retain(|m| !m.finished());
+        self.player.aircraft_mut().guns_mut().update(frame);
+        self.check_enemy_hits(frame);
+        self.update_damage(frame);
+        self.update_targeting(frame);
+        self.explosions.iter_mut().for_each(|e| {
+            e.update(frame.delta, &mut self.rng.particles);
+        });
+        self.explosions.retain(|e| !e.finished());
+        self.score.record(&self.events);
+        std::mem::take(&mut self.events)
+    }
+
+    /// Steer the player's aircraft and check if it hit the ground
+    fn fly_player(&mut self, input: &FrameInput, frame: &FrameTime) {
+        self.parked = false;
+        self.apply_input(input, frame);
+
+        // terrain collisions
+        let pose = self.player.pose();
+        if pose.position.y < self.terrain.height_at(pose.xz()) + CRASH_MARGIN {
+            self.crash();
+            return;
+        }
+
+        let was_stalled = self.player.aircraft().flight().stalled();
+        self.player.apply_controls(frame.delta);
+        match (was_stalled, self.player.aircraft().flight().stalled()) {
+            (false, true) => self.events.push(WorldEvent::StallStarted),
+            (true, false) => self.events.push(WorldEvent::StallRecovered),
+            _ => {}
+        }
+        self.player.aircraft_mut().apply_decay(frame.delta);
+        self.score.flight_time += frame.delta as f64;
+    }
+
+    /// End the sortie by flying into the ground
+    fn crash(&mut self) {
+        let pose = *self.player.pose();
+        let position = self.terrain.absolute(pose.position);
+        let slope = self.terrain.slope_at(pose.xz());
+        warn!(
+            "Crashed into terrain at {position:?} on a {:.0}° slope",
+            slope.to_degrees()
+        );
+        self.destroy_player(WorldEvent::TerrainCollision);
+    }
+
+    /// Enemy fire destroyed the player's aircraft.
+    /// Its wreck falls until it hits the ground, which ends the sortie.
+    fn shoot_down_player(&mut self) {
+        warn!("Shot down");
+        self.cease_fire();
+        self.events.push(WorldEvent::ShotDown);
+    }
+
+    /// Let the wreck of the player's aircraft fall until it's done
+    fn fall_player(&mut self, frame: &FrameTime) {
+        self.player.aircraft_mut().fall(frame.delta);
+        if self.wrecked(self.player.aircraft()) {
+            self.destroy_player(WorldEvent::WreckCrashed);
+        }
+    }
+
+    /// Whether a destroyed aircraft hit the ground, or fell for so long that it breaks up
+    fn wrecked(&self, aircraft: &Aircraft) -> bool {
+        let pose = aircraft.pose();
+        aircraft.destroyed()
+            && (aircraft.fallen_for() > WRECK_TIME
+                || pose.position.y < self.terrain.height_at(pose.xz()))
+    }
+
+    /// Blow up the player's aircraft, stop simulating it until `respawn_player` is called
+    /// and report why with `event`
+    fn destroy_player(&mut self, event: WorldEvent) {
+        let pose = *self.player.pose();
+        self.crashed = true;
+        self.explosions
+            .push(Explosion::new(pose.position, &mut self.rng.particles));
+        self.cease_fire();
+        self.events.push(event);
+    }
+
+    /// Stop the player's guns and drop the lock, once the aircraft can't fight any more
+    fn cease_fire(&mut self) {
+        if self.player.aircraft().guns().firing {
+            self.player.aircraft_mut().guns_mut().stop_firing();
+            self.events.push(WorldEvent::GunsStopped);
+        }
+        self.targeting_data = None;
+    }
+
+    /// Move everything in the world along with the origin
+    fn rebase(&mut self, shift: Vector3<f32>) {
+        self.player.rebase(shift);
+        self.enemies.map.values_mut().for_each(|e| e.rebase(shift));
+        self.missiles.iter_mut().for_each(|m| m.rebase(shift));
+        self.decoys.iter_mut().for_each(|d| d.rebase(shift));
+        self.explosions.iter_mut().for_each(|e| e.rebase(shift));
+    }
+
+    /// Put the player back at the start in a brand new aircraft of the same kind.
+    /// Does nothing unless the player has crashed, or their wreck went down.
+    pub fn respawn_player(&mut self) {
+        if !self.crashed {
+            return;
+        }
+        let blueprint = self
+            .blueprints
+            .get(self.player.aircraft().kind())
+            .unwrap_or_else(|e| panic!("{e}"));
+        self.player = Player::new(blueprint);
+        self.arm_player();
+        self.parked = true;
+        self.crashed = false;
+        self.events.push(WorldEvent::PlayerRespawned);
+    }
+
+    /// Arm the player's aircraft with the chosen loadout and select its first kind of missile
+    fn arm_player(&mut self) {
+        let blueprint = self
+            .blueprints
+            .get(self.player.aircraft().kind())
+            .unwrap_or_else(|e| panic!("{e}"));
+        let loadout = blueprint
+            .loadout(&self.loadout)
+            .expect("The chosen loadout belongs to the player's aircraft");
+        self.player
+            .aircraft_mut()
+            .arm(blueprint, loadout, &self.missile_types);
+        // Still parked, so the aircraft can be trimmed for its new weight
+        self.player.trim();
+        self.selected_missile = self.player.aircraft().missiles().keys().next().cloned();
+    }
+
+    /// Choose the next loadout of the player's aircraft.
+    /// A parked aircraft is rearmed right away, one in the air at its next respawn.
+    pub fn cycle_loadout(&mut self) {
+        let blueprint = self
+            .blueprints
+            .get(self.player.aircraft().kind())
+            .unwrap_or_else(|e| panic!("{e}"));
+        let loadouts = blueprint.loadouts();
+        let current = loadouts
+            .iter()
+            .position(|l| l.name == self.loadout)
+            .unwrap_or(0);
+        self.loadout = loadouts[(current + 1) % loadouts.len()].name.clone();
+        if self.parked {
+            self.arm_player();
+            if self.targeting_data.take().is_some() {
+                self.events.push(WorldEvent::TargetLost);
+            }
+            info!("Armed with {} loadout", self.loadout);
+        } else {
+            info!(
+                "{} loadout will be fitted at the next respawn",
+                self.loadout
+            );
+        }
+    }
+
+    /// Switch between flying with the keyboard and with mouse aim.
+    /// Mouse aim starts out pointing where the nose does.
+    pub fn toggle_control_mode(&mut self) {
+        self.control_mode = match self.control_mode {
+            ControlMode::Keyboard => ControlMode::MouseAim,
+            ControlMode::MouseAim => ControlMode::Keyboard,
+        };
+        self.player.reset_aim();
+        info!("Control mode: {}", self.control_mode);
+    }
+
+    /// Select the next kind of missile the player's aircraft carries.
+    /// Locks are kept per seeker, so switching drops the current one.
+    pub fn cycle_weapon(&mut self) {
+        let missiles = self.player.aircraft().missiles();
+        let next = match &self.selected_missile {
+            Some(current) => missiles
+                .range(current.clone()..)
+                .nth(1)
+                .or_else(|| missiles.iter().next()),
+            None => missiles.iter().next(),
+        };
+        let Some((kind, left)) = next else {
+            return;
+        };
+        let name = self.missile_type(kind).name();
+        info!("Selected {name}, {left} left");
+        self.selected_missile = Some(kind.clone());
+        if self.targeting_data.take().is_some() {
+            self.events.push(WorldEvent::TargetLost);
+        }
+    }
+
+    fn missile_type(&self, kind: &MissileKind) -> &MissileType {
+        self.missile_types
+            .get(kind)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Seeker of the selected missile. Unguided rockets and an empty aircraft have none.
+    fn seeker(&self) -> Option<SeekerSpec> {
+        self.selected_missile
+            .as_ref()
+            .and_then(|kind| self.missile_type(kind).spec().seeker)
+    }
+
+    /// Feed the frame's input to the player's aircraft and weapons.
+    /// Additionaly, set all decays on the aircraft as true so that
+    /// the surfaces not steered this frame return to neutral.
+    fn apply_input(&mut self, input: &FrameInput, frame: &FrameTime) {
+        self.player
+            .aircraft_mut()
+            .controls_mut()
+            .set_all_decays(true);
+        // The instructor goes first, so that keys and the joystick can override it
+        if self.control_mode == ControlMode::MouseAim {
+            self.player.fly_to_aim();
+        }
+        input
+            .movements
+            .iter()
+            .for_each(|m| self.player.process_key(*m, frame.delta));
+        self.player.aircraft_mut().deflect(&input.stick);
+
+        let (x, y) = input.look;
+        if x != 0. || y != 0. {
+            match self.control_mode {
+                ControlMode::Keyboard => self.player.look(x, y),
+                ControlMode::MouseAim => self.player.turn_aim(x, y),
+            }
+        }
+
+        if input.fire_guns {
+            let was_firing = self.player.aircraft().guns().firing;
+            self.fire_guns(frame);
+            match (was_firing, self.player.aircraft().guns().firing) {
+                (false, true) => self.events.push(WorldEvent::GunsStarted),
+                (true, false) => self.events.push(WorldEvent::GunsStopped),
+                _ => {}
+            }
+        } else if self.player.aircraft().guns().firing {
+            self.player.aircraft_mut().guns_mut().stop_firing();
+            self.events.push(WorldEvent::GunsStopped);
+        }
+        if input.launch_missile {
+            self.launch_missile(frame.time);
+        }
+        if input.switch_target {
+            self.switch_target(frame.time);
+        }
+        if input.release_flares {
+            self.release_decoy(DecoyKind::Flare, None, frame.time);
+        }
+        if input.release_chaff {
+            self.release_decoy(DecoyKind::Chaff, None, frame.time);
+        }
+    }
+
+    /// Throw a decoy out of the player's aircraft, or an enemy's if `released_by` is given.
+    /// Every missile homing in on that aircraft gets a chance to be fooled by it.
+    pub fn release_decoy(&mut self, kind: DecoyKind, released_by: Option<EnemyID>, time: f64) {
+        let (aircraft, velocity) = match released_by {
+            Some(id) => match self.enemies.map.get_mut(&id) {
+                Some(enemy) if !enemy.aircraft().destroyed() => {
+                    let velocity = enemy.velocity();
+                    (enemy.aircraft_mut(), velocity)
+                }
+                _ => return,
+            },
+            None => {
+                let velocity = self.player.aircraft().flight().velocity();
+                (self.player.aircraft_mut(), velocity)
+            }
+        };
+        if !aircraft.countermeasures_mut().release(kind, time) {
+            return;
+        }
+        let left = aircraft.countermeasures().dispenser(kind).left();
+        let pose = *aircraft.pose();
+        if released_by.is_none() {
+            info!("Released {kind}, {left} left");
+        }
+        let decoy = Decoy::new(
+            self.next_decoy_id,
+            kind,
+            pose.relative(DISPENSER_MOUNT.into()),
+            velocity,
+            -pose.up(),
+        );
+        self.next_decoy_id += 1;
+        let target = released_by.map_or(TargetID::Player, TargetID::Enemy);
+        for missile in self.missiles.iter_mut() {
+            if missile.target() == Some(target)
+                && missile.spoof(&decoy, &pose, &mut self.rng.countermeasures)
+            {
+                self.events.push(WorldEvent::MissileDecoyed(missile.id()));
+            }
+        }
+        self.decoys.push(decoy);
+    }
+
+    /// Enemies with a missile homing in on them release the decoys which can fool its seeker
+    fn defend_enemies(&mut self, time: f64) {
+        let threats: Vec<_> = self
+            .missiles
+            .iter()
+            .filter(|m| m.decoy().is_none())
+            .filter_map(|m| {
+                let Some(TargetID::Enemy(id)) = m.target() else {
+                    return None;
+                };
+                let seeker = m.seeker()?;
+                let enemy = self.enemies.get_by_id(id)?;
+                (enemy.position().distance(m.position()) < DEFENCE_RANGE)
+                    .then(|| (id, DecoyKind::against(&seeker.kind)))
+            })
+            .collect();
+        for (id, kind) in threats {
+            self.release_decoy(kind, Some(id), time);
+        }
+    }
+
+    /// Make the Enemies struct check for missing enemies and send the next wave
+    pub fn respawn_enemies(&mut self) {
+        let wave = self.enemies.respawn_enemies(
+            &self.blueprints,
+            &self.missile_types,
+            &self.terrain,
+            self.player.pose().position,
+            &mut self.rng.enemies,
+        );
+        if let Some(wave) = wave {
+            self.events.push(WorldEvent::WaveStarted(wave));
+        }
+    }
+
+    /// If there's an enemy being targeted, countdown the lock time
+    fn update_targeting(&mut self, frame: &FrameTime) {
+        match &mut self.targeting_data {
+            // no lock yet
+            Some(data) if data.left_until_lock > 0. => {
+                data.left_until_lock -= frame.delta as f64;
+                if data.left_until_lock < 0. {
+                    self.events.push(WorldEvent::TargetLocked);
+                }
+            }
+            _ => {}
+        }
+        if let Some(data) = &self.targeting_data {
+            let seen = self.seeker().is_some_and(|seeker| {
+                self.player
+                    .targetable_enemies(&self.enemies, &seeker)
+                    .unwrap_or(vec![])
+                    .contains(&data.target_id)
+            });
+            if !seen {
+                warn!("Target lost");
+                self.events.push(WorldEvent::TargetLost);
+                self.targeting_data = None;
+            }
+        }
+    }
+
+    pub fn switch_target(&mut self, time: f64) {
+        if self.last_target_switch_time + SWITCH_COOLDOWN > time {
+            return;
+        }
+        let Some(seeker) = self.seeker() else {
+            return;
+        };
+        if let Some(new_id) = self.player.targeted_enemy_id_nth(&self.enemies, &seeker, 0) {
+            self.targeting_data = Some(TargetingData::new(new_id, seeker.lock_time as f64));
+            self.events.push(WorldEvent::LockingStarted);
+        }
+        self.last_target_switch_time = time;
+    }
+
+    /// Perform all actions necessary to launch a missile of the selected kind.
+    /// Guided missiles need a lock, rockets fly wherever the nose points.
+    /// The world keeps track of the time of last missile launch
+    /// and doesn't let the player do it again before a specified time has passed.
+    /// Modify MISSILE_COOLDOWN to adjust.
+    pub fn launch_missile(&mut self, time: f64) {
+        if self.last_launch_time + MISSILE_COOLDOWN > time {
+            return;
+        }
+        let Some(kind) = &self.selected_missile else {
+            return;
+        };
+        let missile_type = self
+            .missile_types
+            .get(kind)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let seeker = missile_type.spec().seeker;
+        let target = match (&self.targeting_data, seeker) {
+            (_, None) => None,
+            (Some(data), Some(_)) if data.locked() => Some(TargetID::Enemy(data.target_id)),
+            (Some(_), Some(_)) => {
+                warn!("No lock");
+                self.last_launch_time = time;
+                return;
+            }
+            (None, Some(_)) => return,
+        };
+        self.last_launch_time = time;
+        let Some(mount) = self.player.aircraft_mut().take_missile(kind) else {
+            return;
+        };
+        info!(
+            "{} {} left",
+            self.player.aircraft().missiles()[kind],
+            missile_type.name()
+        );
+        let pose = self.player.pose();
+        let missile = Missile::new(
+            self.next_missile_id,
+            missile_type,
+            &Pose::new(pose.relative(mount), pose.orientation),
+            self.player.aircraft().flight().velocity(),
+            target,
+            None,
+        );
+        self.next_missile_id += 1;
+        self.missiles.push(missile);
+        self.events.push(WorldEvent::MissileLaunched);
+
+        // Radar missiles need the lock kept until their own seeker takes over
+        let radar = seeker.is_some_and(|s| matches!(s.kind, SeekerKind::Radar { .. }));
+        if !radar {
+            self.targeting_data = None;
+        }
+    }
+
+    /// Give the missiles the pose of what they are currently
+    /// targeting so they can mutate their state accordingly.
+    /// Missiles without a target go off next to whichever aircraft of the other side
+    /// they pass close to. Every missile going off damages the aircraft around it.
+    pub fn update_missiles(&mut self, frame: &FrameTime) {
+        let mut blasts = Vec::with_capacity(self.missiles.len());
+        let lock = self
+            .targeting_data
+            .as_ref()
+            .filter(|data| data.locked())
+            .map(|data| TargetID::Enemy(data.target_id));
+        let player = self.player_alive().then(|| *self.player.pose());
+        self.missiles.iter_mut().for_each(|missile| {
+            let target = match missile.target() {
+                Some(TargetID::Enemy(id)) => self
+                    .enemies
+                    .get_by_id(id)
+                    .filter(|e| !e.aircraft().destroyed())
+                    .map(|e| *e.aircraft().pose()),
+                Some(TargetID::Player) => player,
+                None => None,
+            };
+            let decoy = missile
+                .decoy()
+                .and_then(|id| self.decoys.iter().find(|d| d.id() == id));
+            let supported = match missile.launched_by() {
+                None => missile.target().is_some() && missile.target() == lock,
+                // The launching enemy keeps the lock as long as its own seeker sees the player
+                Some(id) => match (self.enemies.get_by_id(id), missile.seeker(), &player) {
+                    (Some(enemy), Some(seeker), Some(player)) => {
+                        seeker.sees(enemy.aircraft().pose(), player)
+                    }
+                    _ => false,
+                },
+            };
+            let message = missile.update(target.as_ref(), decoy, supported, frame);
+            if let Some(MissileMessage::Hit(target)) = message {
+                info!("Missile {} went off next to {target:?}", missile.id());
+            }
+            if missile.target().is_none() && !missile.finished() {
+                let fuzed_by = match missile.launched_by() {
+                    None => self
+                        .enemies
+                        .map
+                        .values()
+                        .filter(|e| !e.aircraft().destroyed())
+                        .map(|e| e.position())
+                        .find(|position| missile.fuzed_by(*position)),
+                    Some(_) => player
+                        .map(|p| p.position)
+                        .filter(|position| missile.fuzed_by(*position)),
+                };
+                if let Some(position) = fuzed_by {
+                    missile.fuze(position);
+                }
+            }
+            let position = missile.position();
+            if position.y < self.terrain.height_at(Point2::new(position.x, position.z)) {
+                missile.detonate();
+            }
+            if missile.detonated() {
+                let spec = missile.spec();
+                blasts.push((missile.position(), spec.warhead, spec.blast_radius));
+            }
+        });
+        for (position, warhead, radius) in blasts {
+            self.blast(position, warhead, radius);
+        }
+    }
+
+    /// Damage every aircraft within the blast of a warhead going off at `position`,
+    /// the more the closer it is
+    fn blast(&mut self, position: Point3<f32>, warhead: f32, radius: f32) {
+        let damage = |aircraft: &Aircraft| {
+            let distance = aircraft.pose().position.distance(position);
+            let hit = !aircraft.destroyed() && distance < radius;
+            hit.then(|| warhead * (1. - distance / radius))
+        };
+        let hits: Vec<_> = self
+            .enemies
+            .map
+            .iter()
+            .filter_map(|(id, e)| damage(e.aircraft()).map(|amount| (*id, amount)))
+            .collect();
+        let player = damage(self.player.aircraft()).filter(|_| !self.crashed);
+        for (id, amount) in hits {
+            self.damage_enemy(id, amount);
+        }
+        if let Some(amount) = player {
+            self.damage_player(amount);
+        }
+    }
+
+    /// Damage the enemies the player's bullets hit during the step
+    fn check_enemy_hits(&mut self, frame: &FrameTime) {
+        let hits = self
+            .player
+            .aircraft_mut()
+            .guns_mut()
+            .check_collisions(&self.enemies, frame.delta);
+        for (id, hits) in hits {
+            self.damage_enemy(id, hits as f32 * BULLET_DAMAGE);
+        }
+    }
+
+    /// Damage a random component of an enemy's aircraft
+    fn damage_enemy(&mut self, id: EnemyID, amount: f32) {
+        let component = Component::random(&mut self.rng.damage);
+        let Some(enemy) = self.enemies.map.get_mut(&id) else {
+            return;
+        };
+        if enemy.hit(amount, component) {
+            self.enemy_destroyed(id);
+        }
+    }
+
+    /// Damage a random component of the player's aircraft
+    fn damage_player(&mut self, amount: f32) {
+        let component = Component::random(&mut self.rng.damage);
+        if self.player.aircraft_mut().hit(amount, component) {
+            self.shoot_down_player();
+        } else {
+            self.events.push(WorldEvent::PlayerHit);
+        }
+    }
+
+    /// Report an enemy destroyed and drop the lock on it.
+    /// Its wreck stays in the world until it's done falling.
+    fn enemy_destroyed(&mut self, id: EnemyID) {
+        self.events.push(WorldEvent::EnemyDestroyed(id));
+        if self
+            .targeting_data
+            .as_ref()
+            .is_some_and(|d| d.target_id == id)
+        {
+            self.targeting_data = None;
+        }
+    }
+
+    /// Let fuel fires burn and damaged aircraft trail smoke
+    fn update_damage(&mut self, frame: &FrameTime) {
+        if self.player_alive() && self.player.aircraft_mut().burn(frame.delta) {
+            self.shoot_down_player();
+        }
+        let burnt: Vec<_> = self
+            .enemies
+            .map
+            .iter_mut()
+            .filter_map(|(id, e)| e.burn(frame.delta).then_some(*id))
+            .collect();
+        for id in burnt {
+            self.enemy_destroyed(id);
+        }
+        if !self.crashed {
+            self.player
+                .aircraft_mut()
+                .smoke(frame.delta, &mut self.rng.particles);
+        }
+        self.enemies.map.values_mut().for_each(|e| {
+            e.aircraft_mut().smoke(frame.delta, &mut self.rng.particles);
+        });
+    }
+
+    /// Blow up the wrecks of the enemies which are done falling and remove them
+    fn clear_wrecks(&mut self) {
+        let wrecks: Vec<_> = self
+            .enemies
+            .map
+            .iter()
+            .filter(|(_, e)| self.wrecked(e.aircraft()))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in wrecks {
+            if let Some(enemy) = self.enemies.map.remove(&id) {
+                self.explosions
+                    .push(Explosion::new(enemy.position(), &mut self.rng.particles));
+            }
+        }
+    }
+
+    /// Enemies shoot at the player: with their guns whenever the player is in front of them,
+    /// and with a missile once its seeker has kept the player in view for long enough
+    fn engage_player(&mut self, frame: &FrameTime) {
+        let player = self.player_alive().then(|| *self.player.pose());
+        let mut launches = vec![];
+        for (id, enemy) in self.enemies.map.iter_mut() {
+            let (Some(player), false) = (&player, enemy.aircraft().destroyed()) else {
+                enemy.aircraft_mut().guns_mut().stop_firing();
+                enemy.lock_on(None, frame);
+                continue;
+            };
+            enemy.fire_guns_at(player, frame, &mut self.rng.guns);
+            let pose = *enemy.aircraft().pose();
+            let weapon = enemy
+                .aircraft()
+                .missiles()
+                .into_iter()
+                .filter(|(_, left)| *left > 0)
+                .find_map(|(kind, _)| {
+                    let seeker = self.missile_types.get(&kind).ok()?.spec().seeker?;
+                    seeker.sees(&pose, player).then_some((kind, seeker))
+                });
+            if enemy.lock_on(weapon.as_ref().map(|(_, seeker)| seeker), frame) {
+                launches.extend(weapon.map(|(kind, _)| (*id, kind)));
+            }
+        }
+        for (id, kind) in launches {
+            self.launch_enemy_missile(id, &kind);
+        }
+    }
+
+    /// Launch a missile of the given kind from an enemy's aircraft at the player
+    fn launch_enemy_missile(&mut self, id: EnemyID, kind: &MissileKind) {
+        let Some(enemy) = self.enemies.map.get_mut(&id) else {
+            return;
+        };
+        let Some(mount) = enemy.aircraft_mut().take_missile(kind) else {
+            return;
+        };
+        let missile_type = self
+            .missile_types
+            .get(kind)
+            .unwrap_or_else(|e| panic!("{e}"));
+        info!(
+            "Enemy {id} launched a {} at the player",
+            missile_type.name()
+        );
+        let pose = enemy.aircraft().pose();
+        let missile = Missile::new(
+            self.next_missile_id,
+            missile_type,
+            &Pose::new(pose.relative(mount), pose.orientation),
+            enemy.velocity(),
+            Some(TargetID::Player),
+            Some(id),
+        );
+        self.next_missile_id += 1;
+        self.events.push(WorldEvent::MissileIncoming(missile.id()));
+        self.missiles.push(missile);
+    }
+
+    /// Damage the player's aircraft with the enemy bullets which hit it during the step
+    fn check_player_hits(&mut self, frame: &FrameTime) {
+        if !self.player_alive() {
+            return;
+        }
+        let position = self.player.pose().position;
+        let hits: u32 = self
+            .enemies
+            .map
+            .values_mut()
+            .map(|e| e.aircraft_mut().guns_mut().take_hits(position, frame.delta))
+            .sum();
+        if hits > 0 {
+            self.damage_player(hits as f32 * BULLET_DAMAGE);
+        }
+    }
+
+    pub fn fire_guns(&mut self, frame: &FrameTime) {
+        self.player
+            .aircraft_mut()
+            .fire_guns(frame, &mut self.rng.guns);
+    }
+}
@@ -1,28 +1,86 @@
 use cg::shader::Shader;
 use glfw::Context;
 extern crate glfw;
-use crate::game::game::Game;
+use crate::{
+    cli::{CliOptions, HEADLESS_TIMESTEP},
+    game::{
+        flight::blueprint::{Blueprints, AIRCRAFT_DIR},
+        game::Game,
+        input::InputSnapshot,
+        missile_catalogue::{MissileCatalogue, MISSILE_DIR},
+        replay::Replay,
+        simulation::Simulation,
+        world::{World, WorldEvent},
+    },
+};
+use log::{error, info};
 
 const SCR_WIDTH: u32 = 1000;
 const SCR_HEIGHT: u32 = 1000;
 
 mod audio;
 mod cg;
+mod cli;
 mod game;
 mod macros;
 mod tests;
 
-static mut DELTA_TIME: f32 = 0.;
-static mut GLFW_TIME: f64 = 0.;
-
 fn main() {
-    let mut first_mouse = true;
-    let mut last_x: f32 = SCR_WIDTH as f32 / 2.;
-    let mut last_y: f32 = SCR_HEIGHT as f32 / 2.;
+    log4rs::init_file("log_config.yaml", Default::default()).unwrap();
+    info!("Initialized log4rs");
 
-    let mut last_frame: f32 = 0.;
+    let options = CliOptions::from_args();
+    let replay = options.replay.as_ref().map(|path| {
+        Replay::load(path).unwrap_or_else(|e| panic!("Failed to load replay {path:?}: {e}"))
+    });
+    // A replay brings the seed, aircraft, biome and clock of the session it was recorded in
+    let (clock, rng, aircraft, biome) = match &replay {
+        Some(replay) => (
+            replay.clock(),
+            replay.rng(),
+            replay.aircraft.clone(),
+            Some(replay.biome),
+        ),
+        None => (
+            options.clock(),
+            options.rng(),
+            options.aircraft.clone(),
+            options.biome,
+        ),
+    };
 
-    let mut game = Game::new();
+    let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap_or_else(|e| {
+        error!("Failed to load aircraft definitions: {e}");
+        std::process::exit(1);
+    });
+    if let Err(e) = blueprints.get(&aircraft) {
+        error!("{e}");
+        std::process::exit(1);
+    }
+    let missile_types = MissileCatalogue::load(MISSILE_DIR).unwrap_or_else(|e| {
+        error!("Failed to load missile definitions: {e}");
+        std::process::exit(1);
+    });
+    if let Err(e) = missile_types.check_loadouts(&blueprints) {
+        error!("{e}");
+        std::process::exit(1);
+    }
+
+    if options.headless {
+        cg::model::set_headless(true);
+        let world = World::new(rng, blueprints, missile_types, &aircraft, biome);
+        run_headless(&options, Simulation::new(world, clock), replay);
+        return;
+    }
+
+    let mut game = Game::new(clock, rng, blueprints, missile_types, &aircraft, biome);
+    game.load_bindings(options.bindings);
+    if let Some(path) = options.record {
+        game.start_recording(path);
+    }
+    if let Some(replay) = replay {
+        game.play_replay(replay);
+    }
 
     let shader = Shader::new("src/shaders/light_vs.glsl", "src/shaders/light_fs.glsl");
 
@@ -32,15 +90,11 @@ fn main() {
     );
 
     while !game.window.should_close() {
-        unsafe { GLFW_TIME = game.glfw.get_time() }
-        let current_frame = game.glfw.get_time() as f32;
-        update_delta_time(current_frame, last_frame);
-        last_frame = current_frame;
-        game.process_events(&mut first_mouse, &mut last_x, &mut last_y);
+        game.process_events();
         game.process_key();
 
+        game.update();
         unsafe {
-            game.update();
             game.draw(&shader, &no_light_shader);
         }
 
@@ -53,8 +107,53 @@ fn main() {
     }
 }
 
-fn update_delta_time(current_frame: f32, last_frame: f32) {
-    unsafe {
-        DELTA_TIME = current_frame - last_frame;
+/// Run the simulation without a window, OpenGL context or audio.
+/// Plays back the replay if there is one, otherwise simulates
+/// `--frames` frames without any input.
+fn run_headless(options: &CliOptions, mut simulation: Simulation, replay: Option<Replay>) {
+    if options.record.is_some() {
+        simulation.start_recording();
+    }
+    let frames: Vec<InputSnapshot> = match replay {
+        Some(replay) => replay.frames,
+        None => {
+            let idle = InputSnapshot {
+                delta: options.timestep.unwrap_or(HEADLESS_TIMESTEP),
+                ..InputSnapshot::default()
+            };
+            vec![idle; options.frames as usize]
+        }
+    };
+    info!("Starting headless simulation of {} frames", frames.len());
+
+    let mut crashed = false;
+    for snapshot in &frames {
+        for event in simulation.advance(snapshot) {
+            if event == WorldEvent::TerrainCollision {
+                error!(
+                    "Collision at {:.2}s! Seed: {}",
+                    simulation.clock().time(),
+                    simulation.world().seed()
+                );
+                crashed = true;
+            } else if event == WorldEvent::ShotDown {
+                info!("Shot down at {:.2}s", simulation.clock().time());
+            } else if event == WorldEvent::WreckCrashed {
+                info!("Wreck went down at {:.2}s", simulation.clock().time());
+            }
+        }
+    }
+
+    if let (Some(recording), Some(path)) = (simulation.recording(), &options.record) {
+        if let Err(e) = recording.save(path) {
+            error!("Failed to save replay to {path:?}: {e}");
+        }
+    }
+    info!(
+        "Headless simulation finished: {}",
+        simulation.world().score()
+    );
+    if crashed {
+        std::process::exit(1);
     }
 }
@@ -2,8 +2,52 @@
 mod tests {
 
     extern crate glfw;
-    use crate::cg::model::Model;
-    use cgmath::{vec3, EuclideanSpace};
+    use crate::cg::{
+        camera::Movement,
+        model::{self, Model},
+    };
+    use crate::game::{
+        bindings::{Bindings, BindingsError, Button},
+        camera_rig::{CameraMode, CameraRig},
+        clock::{FrameTime, GameClock},
+        countermeasures::{Decoy, DecoyKind},
+        enemies::Enemies,
+        enemy::{self, Enemy},
+        flight::{
+            aircraft::{Aircraft, AircraftKind},
+            blueprint::{Blueprint, BlueprintError, Blueprints, Role, AIRCRAFT_DIR},
+            damage::Component,
+            dynamics::{FlightState, Pose},
+            mouse_aim,
+            steerable::Steerable,
+        },
+        guns::BULLET_DAMAGE,
+        heightfield::Heightfield,
+        input::{Action, FrameInput, InputSnapshot, StickInput},
+        joystick::{JoystickAxis, JoystickBindings},
+        lod::{self, LodKey},
+        missile::{EnemyID, Missile, MissileMessage, TargetID},
+        missile_catalogue::{
+            CatalogueError, MissileCatalogue, MissileKind, MissileType, MISSILE_DIR,
+        },
+        missile_guidance::GuidanceLaw,
+        missile_spec::SeekerKind,
+        player::Player,
+        rebase::Rebase,
+        replay::Replay,
+        rng::GameRng,
+        score::Score,
+        simulation::Simulation,
+        state::{GameState, StateEvent, Transition},
+        terrain::Terrain,
+        terrain_gen::Biome,
+        world::{World, WorldEvent, DEFAULT_AIRCRAFT},
+    };
+    use cgmath::{vec2, vec3, EuclideanSpace, InnerSpace, MetricSpace, Point2, Point3, Vector3};
+    use glfw::Key;
+    use itertools::Itertools;
+    use rand::Rng;
+    use std::ops::Range;
 
     #[test]
     fn model_position() {
@@ -12,4 +56,1281 @@ mod tests {
         model.set_translation(translation);
         assert_eq!(model.position().to_vec(), translation);
     }
+
+    #[test]
+    fn fixed_clock_accumulates_steps() {
+        let mut clock = GameClock::fixed(0.01);
+        assert!(clock.tick(0.005).is_empty());
+        let steps = clock.tick(0.025);
+        assert_eq!(steps.len(), 3);
+        assert!(steps.iter().all(|s| s.delta == 0.01));
+        assert!((clock.time() - 0.03).abs() < 1e-9);
+    }
+
+    #[test]
+    fn paused_clock_does_not_spike_on_resume() {
+        let mut clock = GameClock::variable();
+        clock.set_scale(0.5);
+        clock.pause();
+        assert!(clock.tick(0.1).is_empty());
+        clock.resume();
+        let steps = clock.tick(0.1);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].delta, 0.05);
+    }
+
+    #[test]
+    fn same_seed_gives_same_streams() {
+        let (mut a, mut b) = (GameRng::new(42), GameRng::new(42));
+        // Drawing from one stream must not affect the others
+        let _: u64 = a.guns.gen();
+        let enemies_a: Vec<u32> = (0..8).map(|_| a.enemies.gen()).collect();
+        let enemies_b: Vec<u32> = (0..8).map(|_| b.enemies.gen()).collect();
+        assert_eq!(enemies_a, enemies_b);
+        assert_ne!(enemies_a[0], GameRng::new(43).enemies.gen::<u32>());
+    }
+
+    #[test]
+    fn replay_survives_a_round_trip() {
+        let mut replay = Replay::new(
+            7,
+            AircraftKind::new("f5"),
+            Biome::Coast,
+            &GameClock::fixed(1. / 60.),
+        );
+        replay.push(&InputSnapshot {
+            delta: 0.016,
+            held: vec![Action::PitchDown, Action::ThrottleUp],
+            pressed: vec![Action::SpeedUp],
+            stick: StickInput {
+                pitch: 0.5,
+                throttle: Some(0.75),
+                ..StickInput::default()
+            },
+            cursor: (1.5, -0.25),
+            scroll: 1.,
+        });
+        replay.push(&InputSnapshot::default());
+
+        let path = std::env::temp_dir().join("danger_zone_round_trip.replay.json");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, replay);
+    }
+
+    #[test]
+    fn pulling_hard_bleeds_speed_and_stalls() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let blueprint = blueprints.get(&AircraftKind::new("mig21")).unwrap();
+        let fly = |pitch_bias: f32| {
+            let mut aircraft = Aircraft::new(blueprint);
+            *aircraft.flight_mut() = FlightState::new(aircraft.pose().front() * 150.);
+            *aircraft.controls_mut().pitch_bias_mut() = pitch_bias;
+            let mut stalled = false;
+            for _ in 0..360 {
+                aircraft.fly(1. / 120.);
+                stalled |= aircraft.flight().stalled();
+            }
+            (aircraft.flight().speed(), stalled)
+        };
+        let (level_speed, level_stalled) = fly(0.);
+        let (pulling_speed, pulling_stalled) = fly(35.);
+        assert!(!level_stalled && pulling_stalled);
+        assert!(pulling_speed < level_speed - 20.);
+    }
+
+    #[test]
+    fn players_start_trimmed_for_level_flight() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        for kind in ["mig21", "f5", "l39", "su24"] {
+            let mut player = Player::new(blueprints.get(&AircraftKind::new(kind)).unwrap());
+            let start = *player.pose();
+            let speed = player.aircraft().flight().speed();
+            // A minute hands off, as long as an idle headless run
+            for _ in 0..60 * 60 {
+                player.aircraft_mut().controls_mut().set_all_decays(true);
+                player.aircraft_mut().apply_decay(1. / 60.);
+                player.apply_controls(1. / 60.);
+            }
+            let pose = player.pose();
+            let flight = player.aircraft().flight();
+            assert!((pose.position.y - start.position.y).abs() < 100., "{kind}");
+            assert!((flight.speed() - speed).abs() < 10., "{kind}");
+            assert!(!flight.stalled(), "{kind}");
+        }
+    }
+
+    #[test]
+    fn invalid_blueprint_names_the_field() {
+        let mig21 = std::fs::read_to_string(format!("{AIRCRAFT_DIR}/mig21.json")).unwrap();
+        let path = std::env::temp_dir().join("danger_zone_invalid.json");
+        let cases = [
+            ("\"mass\": 8000", "\"mass\": -1", "mass"),
+            ("[0.6, 0.15, 0.12]", "[0.6, 0, 0.12]", "control_power"),
+            ("[0.3, 0.5, 0.04]", "[0.3, -0.5, 0.04]", "damping"),
+            ("[1.2, 0.8]", "[1.2, 1e39]", "stability"),
+            ("\"Clean\"", "\"Strike\"", "loadouts"),
+        ];
+        for (from, to, field) in cases {
+            std::fs::write(&path, mig21.replace(from, to)).unwrap();
+            let result = Blueprint::load(&path);
+            std::fs::remove_file(&path).unwrap();
+            match result {
+                Err(BlueprintError::Invalid { field: named, .. }) => assert_eq!(named, field),
+                other => panic!("{field}: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn score_counts_sortie_events() {
+        let mut score = Score::default();
+        score.record(&[
+            WorldEvent::MissileLaunched,
+            WorldEvent::EnemyDestroyed(3),
+            WorldEvent::GunsStarted,
+        ]);
+        score.record(&[WorldEvent::EnemyDestroyed(4), WorldEvent::TerrainCollision]);
+        assert_eq!(
+            (score.enemies_destroyed, score.missiles_fired, score.crashes),
+            (2, 1, 1)
+        );
+    }
+
+    #[test]
+    fn heightfield_interpolates_between_vertices() {
+        // A 10x10 quad rising by 1 along x, split into two triangles
+        let positions = [
+            vec3(0., 0., 0.),
+            vec3(10., 10., 0.),
+            vec3(10., 10., 10.),
+            vec3(0., 0., 10.),
+        ];
+        let heightfield = Heightfield::from_mesh(&positions, &[0, 1, 2, 0, 2, 3]);
+        assert!((heightfield.height(2.5, 7.) - 2.5).abs() < 1e-4);
+        assert!((heightfield.height(7.3, 1.2) - 7.3).abs() < 1e-4);
+        let normal = heightfield.normal(5., 5.);
+        let expected = vec3(-1., 1., 0.).normalize();
+        assert!((normal - expected).magnitude() < 1e-4);
+    }
+
+    #[test]
+    fn generated_terrain_depends_only_on_biome_and_seed() {
+        model::set_headless(true);
+        let heights = |biome, seed| {
+            let terrain = Terrain::new(biome, seed, 0.);
+            [(0., 0.), (-1234.5, 800.), (2500., -2999.)]
+                .map(|(x, z)| terrain.height_at(Point2::new(x, z)))
+        };
+        assert_eq!(heights(Biome::Mountains, 3), heights(Biome::Mountains, 3));
+        assert_ne!(heights(Biome::Mountains, 3), heights(Biome::Mountains, 4));
+        assert_ne!(heights(Biome::Mountains, 3), heights(Biome::Arctic, 3));
+    }
+
+    #[test]
+    fn terrain_stays_put_when_the_origin_follows_the_player() {
+        model::set_headless(true);
+        let mut terrain = Terrain::new(Biome::Coast, 9, 0.);
+        // No seam between neighbouring chunks
+        let seam = terrain.height_at(Point2::new(2000., 1234.));
+        assert!((terrain.height_at(Point2::new(1999.99, 1234.)) - seam).abs() < 0.01);
+
+        let far = Point2::new(5000., -3500.);
+        let height = terrain.height_at(far);
+        let shift = terrain.stream(far).expect("Origin follows the player");
+        assert_eq!(shift, vec3(-4000., 0., 4000.));
+        assert_eq!(terrain.height_at(far + vec2(shift.x, shift.z)), height);
+    }
+
+    #[test]
+    fn lod_triangles_cover_the_chunk_and_match_coarser_neighbours() {
+        let resolution = 65;
+        for key in [
+            LodKey {
+                level: 0,
+                edges: [0, 0, 0, 0],
+            },
+            LodKey {
+                level: 1,
+                edges: [3, 1, 2, 5],
+            },
+            LodKey {
+                level: lod::MAX_LEVEL,
+                edges: [lod::MAX_LEVEL; 4],
+            },
+        ] {
+            let indices = lod::indices(key, resolution);
+            let point = |i: u32| {
+                (
+                    (i as usize % resolution) as f32,
+                    (i as usize / resolution) as f32,
+                )
+            };
+            // Counter-clockwise triangles seen from above, together as large as the whole grid
+            let area: f32 = indices
+                .chunks_exact(3)
+                .map(|t| {
+                    let [a, b, c] = [point(t[0]), point(t[1]), point(t[2])];
+                    let area = ((b.1 - a.1) * (c.0 - a.0) - (b.0 - a.0) * (c.1 - a.1)) / 2.;
+                    assert!(area > 0.);
+                    area
+                })
+                .sum();
+            assert_eq!(area, 64. * 64.);
+            // The north edge only uses vertices its neighbour draws too
+            let north_step = 1 << key.edges[0];
+            assert!(indices
+                .iter()
+                .filter(|&&i| (i as usize) < resolution)
+                .all(|&i| i % north_step == 0));
+        }
+        assert_eq!(lod::level_for_distance(0.), 0);
+        assert!(lod::level_for_distance(3000.) > lod::level_for_distance(1500.));
+    }
+
+    #[test]
+    fn states_lead_from_the_menu_through_a_sortie_and_back() {
+        let press = |state: GameState, key| state.handle(StateEvent::Pressed(key));
+        let mut state = GameState::Menu;
+        for (event, next) in [
+            (StateEvent::Pressed(Key::Enter), GameState::Briefing),
+            (StateEvent::Pressed(Key::Enter), GameState::Flight),
+            (StateEvent::Pressed(Key::P), GameState::Paused),
+            (StateEvent::Pressed(Key::Escape), GameState::Flight),
+            (StateEvent::SortieEnded, GameState::Debrief),
+            (StateEvent::Pressed(Key::Enter), GameState::Menu),
+        ] {
+            assert_eq!(
+                state.handle(event),
+                Transition::To(next),
+                "{state} on {event:?}"
+            );
+            state = next;
+        }
+        // Flight and briefing keys reach the bindings, nothing leaks out of the other screens
+        assert_eq!(press(GameState::Flight, Key::Space), Transition::Ignored);
+        assert_eq!(press(GameState::Briefing, Key::Space), Transition::Ignored);
+        assert_eq!(press(GameState::Paused, Key::Space), Transition::Handled);
+        assert_eq!(press(GameState::Menu, Key::Escape), Transition::Quit);
+        assert!(GameState::Debrief.simulates() && !GameState::Paused.simulates());
+    }
+
+    #[test]
+    fn replays_take_off_again_after_a_crash() {
+        let event = StateEvent::ReplayRespawned;
+        let debrief = GameState::Flight.handle(StateEvent::SortieEnded);
+        assert_eq!(debrief, Transition::To(GameState::Debrief));
+        assert_eq!(
+            GameState::Debrief.handle(event),
+            Transition::To(GameState::Flight)
+        );
+        assert_eq!(GameState::Flight.handle(event), Transition::Ignored);
+    }
+
+    #[test]
+    fn paused_simulation_freezes_the_world() {
+        let mut simulation = Simulation::new(world(3), GameClock::fixed(1. / 60.));
+        let frame = |delta| InputSnapshot {
+            delta,
+            held: vec![Action::PitchDown],
+            ..InputSnapshot::default()
+        };
+        let position = |s: &Simulation| s.world().player().pose().position;
+
+        simulation.set_paused(true);
+        let before = position(&simulation);
+        for _ in 0..30 {
+            assert!(simulation.advance(&frame(1. / 30.)).is_empty());
+        }
+        assert_eq!(simulation.clock().time(), 0.);
+        assert_eq!(position(&simulation), before);
+
+        // A long pause is not made up for after resuming
+        simulation.set_paused(false);
+        simulation.advance(&frame(1. / 60.));
+        assert!((simulation.clock().time() - 1. / 60.).abs() < 1e-6);
+        assert_ne!(position(&simulation), before);
+    }
+
+    #[test]
+    fn bindings_file_overrides_defaults() {
+        let path = std::env::temp_dir().join("danger_zone_bindings.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "buttons": { "fire_guns": ["W", "MouseMiddle"] },
+                "axes": { "look_vertical": { "axis": "y", "inverted": true, "sensitivity": 2 } }
+            }"#,
+        )
+        .unwrap();
+        let bindings = Bindings::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(bindings.action(Button::Key(Key::W)), Some(Action::FireGuns));
+        assert_eq!(bindings.action(Button::Key(Key::M)), None);
+        assert_eq!(bindings.action(Button::Key(Key::S)), Some(Action::PitchUp));
+        assert_eq!(
+            bindings.held(|b| b == Button::Mouse(glfw::MouseButtonMiddle)),
+            vec![Action::FireGuns]
+        );
+        assert_eq!(bindings.mouse_motion(1., 1., 0.), ((1., -2.), 0.));
+    }
+
+    #[test]
+    fn bindings_file_rejects_conflicts() {
+        let path = std::env::temp_dir().join("danger_zone_conflicting_bindings.json");
+        let load = |json: &str| {
+            std::fs::write(&path, json).unwrap();
+            Bindings::load(&path)
+        };
+        let conflict = load(r#"{ "buttons": { "fire_guns": ["F"], "launch_missile": ["F"] } }"#);
+        assert!(matches!(
+            conflict,
+            Err(BindingsError::Conflict(Button::Key(Key::F), ..))
+        ));
+        let reserved = load(r#"{ "buttons": { "switch_target": ["P"] } }"#);
+        assert!(matches!(reserved, Err(BindingsError::Reserved(_))));
+        let axes = load(r#"{ "axes": { "zoom": { "axis": "x" } } }"#);
+        assert!(matches!(axes, Err(BindingsError::AxisConflict(..))));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn joystick_axes_deflect_the_controls_proportionally() {
+        model::set_headless(true);
+        let bindings = JoystickBindings {
+            yaw: Some(JoystickAxis {
+                inverted: true,
+                ..JoystickAxis::new(3)
+            }),
+            throttle: Some(JoystickAxis::new(2)),
+            ..JoystickBindings::default()
+        };
+        // Inside the dead zone, half way with the response curve, full and inverted, lever
+        let stick = bindings.stick(&[0.05, 0.55, 0., 1.]);
+        assert_eq!(stick.roll, 0.);
+        assert!((stick.pitch - 0.5f32.powf(1.5)).abs() < 1e-6);
+        assert_eq!(stick.yaw, -1.);
+        assert_eq!(stick.throttle, Some(0.5));
+
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let mut aircraft = Aircraft::new(blueprints.get(&AircraftKind::new("mig21")).unwrap());
+        *aircraft.controls_mut().roll_bias_mut() = 10.;
+        aircraft.deflect(&stick);
+        let deflection = aircraft.deflection();
+        assert!((deflection.x - stick.pitch).abs() < 1e-6);
+        // Full left rudder, and the centered roll axis leaves the keyboard's roll alone
+        assert_eq!(deflection.y, 1.);
+        assert_eq!(aircraft.controls().roll_bias(), 10.);
+        assert_eq!(aircraft.controls().throttle(), 0.5);
+    }
+
+    #[test]
+    fn mouse_aim_instructor_steers_toward_the_aim() {
+        let attitude = Pose::default().attitude();
+        // Aim off to the right and above: bank right, pull up, right rudder
+        let aim = (attitude.front + attitude.right + attitude.up).normalize();
+        let stick = mouse_aim::instruct(&attitude, aim);
+        assert!(stick.roll > 0. && stick.pitch > 0. && stick.yaw > 0.);
+        // Straight ahead there is nothing to correct
+        assert_eq!(
+            mouse_aim::instruct(&attitude, attitude.front),
+            StickInput::default()
+        );
+    }
+
+    #[test]
+    fn mouse_aim_turns_the_aircraft_onto_the_aim() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let mut player = Player::new(blueprints.get(&AircraftKind::new("mig21")).unwrap());
+        let front = player.pose().front();
+        *player.aircraft_mut().flight_mut() = FlightState::new(front * 200.);
+        // A quarter turn to the right
+        player.turn_aim(900., 0.);
+        let aim = *player.aim();
+        assert!(aim.dot(player.pose().right()) > 0.99);
+        for _ in 0..120 * 15 {
+            player.aircraft_mut().controls_mut().set_all_decays(true);
+            player.fly_to_aim();
+            player.apply_controls(1. / 120.);
+        }
+        let pose = player.pose();
+        assert!(pose.front().angle(aim).0 < 0.1);
+        // Wings levelled again once on the aim
+        assert!(pose.right().y.abs() < 0.2);
+    }
+
+    #[test]
+    fn mouse_look_steers_on_top_of_held_keys() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let mut player = Player::new(blueprints.get(&AircraftKind::new("mig21")).unwrap());
+        let pose = *player.pose();
+        player.process_key(Movement::PitchUp, 1. / 60.);
+        player.process_key(Movement::YawLeft, 1. / 60.);
+        let held = player.aircraft().controls().clone();
+        // Up and to the right, with the held pitch and against the held rudder
+        player.look(5., 2.);
+        let controls = player.aircraft().controls();
+        assert!(controls.pitch_bias() > held.pitch_bias());
+        assert!(controls.yaw_bias() < held.yaw_bias() && controls.yaw_bias() > 0.);
+        // The flight model turns the aircraft, not the mouse
+        assert_eq!(*player.pose(), pose);
+        // However far the mouse goes, the elevator stops at full deflection
+        player.look(0., 1e6);
+        assert_eq!(player.aircraft().deflection().x, 1.);
+    }
+
+    #[test]
+    fn camera_rig_blends_between_views() {
+        let world = world(5);
+        let mut rig = CameraRig::new(&world);
+        let cockpit = *world.player().pose();
+        let behind =
+            |rig: &CameraRig| (cockpit.position - rig.view().position).dot(cockpit.front());
+
+        rig.cycle();
+        assert_eq!(rig.mode(), CameraMode::Chase);
+        rig.update(&world, 0.3);
+        let halfway = behind(&rig);
+        assert!(!rig.in_cockpit() && halfway > 0.);
+        rig.update(&world, 1.);
+        assert!(behind(&rig) > halfway);
+        // Looking at the aircraft from behind, the right way up
+        assert!(rig.view().front.dot(cockpit.front()) > 0.9);
+        assert!(rig.view().up.dot(cockpit.up()) > 0.9);
+    }
+
+    #[test]
+    fn missile_camera_follows_the_missile_until_it_is_gone() {
+        let mut world = world(5);
+        let mut rig = CameraRig::new(&world);
+        let cockpit = *world.player().pose();
+        rig.cycle();
+        rig.cycle();
+        rig.cycle();
+        assert_eq!(rig.mode(), CameraMode::Missile);
+        let missile_types = catalogue();
+        let r60 = missile_types.get(&MissileKind::new("r60")).unwrap();
+        world
+            .missiles_mut()
+            .push(Missile::new(7, r60, &cockpit, vec3(0., 0., 0.), None, None));
+        rig.update(&world, 1.);
+        let missile = world.missiles()[0].position();
+        assert!((rig.view().position - missile).magnitude() < 10.);
+        // Then goes back to the orbit view it came from
+        world.missiles_mut().clear();
+        rig.update(&world, 1.);
+        assert_eq!(rig.mode(), CameraMode::Missile);
+        rig.update(&world, 2.);
+        assert_eq!(rig.mode(), CameraMode::Orbit);
+    }
+
+    #[test]
+    fn aircraft_model_is_drawn_where_the_pose_says() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let blueprint = blueprints
+            .get(&AircraftKind::new(DEFAULT_AIRCRAFT))
+            .unwrap();
+        let mut aircraft = Aircraft::new(blueprint);
+        let pose = banked_pose();
+        aircraft.place(pose);
+        *aircraft.flight_mut() = FlightState::new(pose.front() * 200.);
+        for _ in 0..60 {
+            aircraft.fly(1. / 120.);
+        }
+        let pose = *aircraft.pose();
+        assert_ne!(pose.position, Pose::default().position);
+        assert_eq!(aircraft.model().position(), pose.position);
+        assert_eq!(aircraft.model().orientation, pose.orientation);
+    }
+
+    #[test]
+    fn guns_fire_from_their_mounts_along_the_nose() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let blueprint = blueprints
+            .get(&AircraftKind::new(DEFAULT_AIRCRAFT))
+            .unwrap();
+        let mut aircraft = Aircraft::new(blueprint);
+        let pose = banked_pose();
+        aircraft.place(pose);
+        *aircraft.flight_mut() = FlightState::new(pose.front() * 200.);
+
+        // The first round leaves the first gun and hits what's 150 m ahead of it
+        let mut rng = GameRng::new(1);
+        let mut enemies = Enemies::new(
+            &blueprints,
+            &catalogue(),
+            &terrain(),
+            Point3::origin(),
+            &mut rng.enemies,
+        );
+        let muzzle = pose.relative(blueprint.gun_mounts()[0]);
+        let ids: Vec<_> = enemies.map.keys().copied().collect();
+        for (n, enemy) in enemies.map.values_mut().enumerate() {
+            let mut target = pose;
+            target.position = muzzle + pose.front() * 150. + pose.up() * 50. * n as f32;
+            enemy.aircraft_mut().place(target);
+        }
+        aircraft.fire_guns(&FrameTime::new(1. / 120., 1.), &mut rng.guns);
+        let delta = 1. / 120.;
+        assert!(aircraft
+            .guns_mut()
+            .check_collisions(&enemies, delta)
+            .is_empty());
+        aircraft.guns_mut().update(&FrameTime::new(0.15, 1.15));
+        let hits = aircraft.guns_mut().check_collisions(&enemies, 0.15);
+        assert_eq!(hits.into_iter().collect::<Vec<_>>(), vec![(ids[0], 1)]);
+    }
+
+    #[test]
+    fn missiles_leave_from_the_launcher_pose() {
+        model::set_headless(true);
+        let pose = banked_pose();
+        let missile_types = catalogue();
+        let r60 = missile_types.get(&MissileKind::new("r60")).unwrap();
+        let missile = Missile::new(0, r60, &pose, pose.front() * 200., None, None);
+        assert!((missile.position() - pose.position).magnitude() < 1e-3);
+        assert!(missile.model.front().angle(pose.front()).0 < 1e-3);
+    }
+
+    #[test]
+    fn missiles_fly_on_their_own_and_can_miss() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let mut rng = GameRng::new(3);
+        let mut enemies = Enemies::new(
+            &blueprints,
+            &catalogue(),
+            &terrain(),
+            Point3::origin(),
+            &mut rng.enemies,
+        );
+        let enemy = enemies.map.values_mut().next().unwrap();
+        let missile_types = catalogue();
+        let r60 = missile_types.get(&MissileKind::new("r60")).unwrap();
+        let guided = |guidance, max_g| {
+            let mut spec = *r60.spec();
+            spec.max_g = max_g;
+            spec.seeker.as_mut().unwrap().guidance = guidance;
+            let kind = MissileKind::new("test");
+            MissileType::new(kind, r60.name().clone(), r60.model_path().clone(), spec)
+        };
+        // Launch from the origin at 250 m/s towards -z at a target flying along `path`.
+        // Tells whether the missile hit and whether it lost track before that.
+        let mut shoot = |missile_type: MissileType, path: &dyn Fn(f32) -> Pose| {
+            let delta = 1. / 120.;
+            enemy.aircraft_mut().place(path(0.));
+            let launcher = Pose::default();
+            let velocity = launcher.front() * 250.;
+            let target = Some(TargetID::Enemy(enemy.id()));
+            let mut missile = Missile::new(0, &missile_type, &launcher, velocity, target, None);
+            let mut lost = false;
+            for step in 1..(missile_type.spec().lifetime / delta) as usize {
+                enemy.aircraft_mut().place(path(step as f32 * delta));
+                let frame = FrameTime::new(delta, (step as f32 * delta) as f64);
+                match missile.update(Some(enemy.aircraft().pose()), None, false, &frame) {
+                    Some(MissileMessage::Hit(_)) => return (true, lost),
+                    Some(MissileMessage::LostTarget) => lost = true,
+                    None => {}
+                }
+            }
+            (false, lost)
+        };
+        let straight = |start: Point3<f32>, velocity: Vector3<f32>| {
+            move |t: f32| Pose::new(start + velocity * t, Pose::default().orientation)
+        };
+
+        // A crossing target is hit by the navigation laws, without losing it from the seeker
+        let crossing = straight(Point3::new(-600., 0., -3000.), vec3(200., 0., 0.));
+        for guidance in [
+            GuidanceLaw::ProportionalNavigation,
+            GuidanceLaw::AugmentedPN,
+        ] {
+            assert_eq!(
+                shoot(guided(guidance, 30.), &crossing),
+                (true, false),
+                "{guidance}"
+            );
+        }
+        // A target running away from too far off outruns the missile once its motor burns out
+        let running = straight(Point3::new(0., 0., -4800.), vec3(0., 0., -300.));
+        assert!(!shoot(r60.clone(), &running).0);
+        // A target off to the side is never seen
+        let beside = straight(Point3::new(1000., 0., 0.), vec3(0., 0., -200.));
+        assert_eq!(shoot(r60.clone(), &beside), (false, true));
+        // A target breaking hard across the nose close in makes a missile with weak fins overshoot
+        let breaking = |t: f32| {
+            let radius = 300.;
+            let angle = t * 250. / radius;
+            let centre = Point3::new(radius, 0., -1000.);
+            let position = centre + vec3(-angle.cos(), 0., angle.sin()) * radius;
+            Pose::new(position, Pose::default().orientation)
+        };
+        assert!(!shoot(guided(GuidanceLaw::AugmentedPN, 10.), &breaking).0);
+        assert!(shoot(r60.clone(), &breaking).0);
+        // Pure pursuit chases the target's tail and can't turn tightly enough
+        assert!(!shoot(guided(GuidanceLaw::PurePursuit, 30.), &breaking).0);
+    }
+
+    #[test]
+    fn missile_types_have_their_own_seekers() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let missile_types = catalogue();
+        missile_types.check_loadouts(&blueprints).unwrap();
+        let get = |name| missile_types.get(&MissileKind::new(name)).unwrap();
+        let (ir, radar) = (
+            get("r60").spec().seeker.unwrap(),
+            get("r27").spec().seeker.unwrap(),
+        );
+        assert!(get("s5").spec().seeker.is_none());
+        assert!(ir.lock_time < radar.lock_time && ir.range < radar.range);
+
+        // The heat seeker only sees the exhaust of a target flying away,
+        // the radar sees it either way
+        let shooter = Pose::default();
+        let mut target = Pose::new(Point3::new(0., 0., -2000.), Pose::default().orientation);
+        assert!(ir.sees(&shooter, &target) && radar.sees(&shooter, &target));
+        target.yaw(180.);
+        assert!(!ir.sees(&shooter, &target) && radar.sees(&shooter, &target));
+    }
+
+    #[test]
+    fn invalid_missile_type_names_the_field() {
+        let r60 = std::fs::read_to_string(format!("{MISSILE_DIR}/r60.json")).unwrap();
+        let path = std::env::temp_dir().join("danger_zone_invalid_missile.json");
+        std::fs::write(&path, r60.replace("\"lifetime\": 25", "\"lifetime\": 0")).unwrap();
+        let result = MissileType::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            result,
+            Err(CatalogueError::Invalid {
+                field: "lifetime",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn radar_missile_launched_out_of_active_range_is_lost_without_the_lock() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let missile_types = catalogue();
+        let r27 = missile_types.get(&MissileKind::new("r27")).unwrap();
+        let mut rng = GameRng::new(3);
+        let mut enemies = Enemies::new(
+            &blueprints,
+            &missile_types,
+            &terrain(),
+            Point3::origin(),
+            &mut rng.enemies,
+        );
+        let enemy = enemies.map.values_mut().next().unwrap();
+        let shooter = Pose::default();
+        let mut target = Pose::new(Point3::new(0., 0., -6000.), Pose::default().orientation);
+        target.yaw(180.);
+        enemy.aircraft_mut().place(target);
+        let frame = FrameTime::new(1. / 120., 0.);
+        let velocity = shooter.front() * 250.;
+        let id = Some(TargetID::Enemy(enemy.id()));
+        let mut missile = Missile::new(0, r27, &shooter, velocity, id, None);
+        assert!(missile.update(Some(&target), None, true, &frame).is_none());
+        assert!(missile.target().is_some());
+        let lost = missile.update(Some(&target), None, false, &frame);
+        assert!(matches!(lost, Some(MissileMessage::LostTarget)));
+    }
+
+    #[test]
+    fn missile_kinds_run_out_on_their_own_and_rockets_need_no_lock() {
+        let mut world = world(3);
+        let left =
+            |world: &World, name| world.player().aircraft().missiles()[&MissileKind::new(name)];
+        let radars = left(&world, "r27");
+        // The radar missile is selected first, and can't be launched without a lock
+        world.launch_missile(1.);
+        assert!(world.missiles().is_empty());
+        world.cycle_weapon();
+        world.cycle_weapon();
+        let rockets = left(&world, "s5");
+        world.launch_missile(2.);
+        world.launch_missile(3.);
+        assert_eq!(world.missiles().len(), 2);
+        assert!(world.missiles().iter().all(|m| m.target().is_none()));
+        assert_eq!(left(&world, "s5"), rockets - 2);
+        assert_eq!(left(&world, "r27"), radars);
+    }
+
+    #[test]
+    fn stores_weigh_the_aircraft_down() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let blueprint = blueprints
+            .get(&AircraftKind::new(DEFAULT_AIRCRAFT))
+            .unwrap();
+        let fly = |loadout: &str| {
+            let mut aircraft = Aircraft::new(blueprint);
+            aircraft.arm(blueprint, blueprint.loadout(loadout).unwrap(), &catalogue());
+            *aircraft.flight_mut() = FlightState::new(aircraft.pose().front() * 100.);
+            for _ in 0..240 {
+                aircraft.fly(1. / 120.);
+            }
+            (aircraft.mass(), aircraft.flight().speed())
+        };
+        let (clean_mass, clean_speed) = fly("Clean");
+        let (strike_mass, strike_speed) = fly("Strike");
+        assert!(strike_mass > clean_mass + 300.);
+        assert!(strike_speed < clean_speed);
+    }
+
+    #[test]
+    fn parked_aircraft_is_rearmed_as_soon_as_a_loadout_is_picked() {
+        let mut world = world(5);
+        let s5 = MissileKind::new("s5");
+        assert_eq!(world.loadout(), "Mixed");
+        assert_eq!(world.player().aircraft().missiles()[&s5], 32);
+        world.cycle_loadout();
+        world.cycle_loadout();
+        assert_eq!(world.loadout(), "Strike");
+        assert_eq!(world.player().aircraft().missiles()[&s5], 64);
+    }
+
+    #[test]
+    fn rockets_leave_from_alternating_hardpoints() {
+        let mut world = world(5);
+        let s5 = MissileKind::new("s5");
+        while world.selected_missile().as_ref() != Some(&s5) {
+            world.cycle_weapon();
+        }
+        let rockets = world.player().aircraft().missiles()[&s5];
+        world.launch_missile(1.);
+        world.launch_missile(2.);
+        let pose = *world.player().pose();
+        let sides: Vec<f32> = world
+            .missiles()
+            .iter()
+            .map(|m| (m.position() - pose.position).dot(pose.right()))
+            .collect();
+        // Not from the pilot
+        assert!(sides[0] * sides[1] < 0. && sides.iter().all(|side| side.abs() > 1.));
+        assert_eq!(world.player().aircraft().missiles()[&s5], rockets - 2);
+    }
+
+    #[test]
+    fn airborne_loadout_change_waits_for_the_respawn() {
+        let mut world = world(5);
+        let s5 = MissileKind::new("s5");
+        let frame = FrameTime::new(1. / 60., 3.);
+        world.update(&FrameInput::default(), &frame);
+        world.cycle_loadout();
+        assert_eq!(world.loadout(), "Intercept");
+        assert_eq!(world.player().aircraft().missiles()[&s5], 32);
+        let aircraft = world.player_mut().aircraft_mut();
+        aircraft.fire_guns(&FrameTime::new(1. / 60., 4.), &mut GameRng::new(5).guns);
+        assert!(aircraft.guns().rounds() < aircraft.guns().capacity());
+
+        // The respawn rearms everything
+        let mut crash = *world.player().pose();
+        crash.position.y = -10000.;
+        world.player_mut().aircraft_mut().place(crash);
+        world.update(&FrameInput::default(), &frame);
+        assert!(world.crashed());
+        world.cycle_loadout();
+        world.respawn_player();
+        assert_eq!(world.loadout(), "Strike");
+        assert_eq!(world.player().aircraft().missiles()[&s5], 64);
+        let guns = world.player().aircraft().guns();
+        assert_eq!(guns.rounds(), guns.capacity());
+    }
+
+    #[test]
+    fn briefing_lets_a_bound_key_change_the_loadout() {
+        model::set_headless(true);
+        let event = StateEvent::Pressed(Key::L);
+        assert_eq!(GameState::Briefing.handle(event), Transition::Ignored);
+        let action = Bindings::default().action(Button::Key(Key::L));
+        assert_eq!(action, Some(Action::CycleLoadout));
+
+        let mut simulation = Simulation::new(world(3), GameClock::fixed(1. / 60.));
+        let loadout = simulation.world().loadout().clone();
+        simulation.advance(&InputSnapshot {
+            pressed: action.into_iter().collect(),
+            ..InputSnapshot::default()
+        });
+        assert_ne!(*simulation.world().loadout(), loadout);
+    }
+
+    #[test]
+    fn decoys_fool_seekers_depending_on_kind_aspect_and_timing() {
+        let (ir, radar) = (
+            SeekerKind::Infrared { rear_aspect: 1. },
+            SeekerKind::Radar { active_range: 1. },
+        );
+        let (head_on, beam, tail) = (0., std::f32::consts::FRAC_PI_2, std::f32::consts::PI);
+        assert_eq!(DecoyKind::Flare.seduction(&radar, beam, 2.), 0.);
+        assert_eq!(DecoyKind::Chaff.seduction(&ir, beam, 2.), 0.);
+        // Heat seekers see the flare outshone by the exhaust from behind,
+        // radars tell chaff apart from a target which doesn't cross their beam
+        let (flare, chaff) = (DecoyKind::Flare, DecoyKind::Chaff);
+        assert!(flare.seduction(&ir, head_on, 2.) > flare.seduction(&ir, tail, 2.));
+        assert!(chaff.seduction(&radar, beam, 2.) > chaff.seduction(&radar, head_on, 2.));
+        // Released too early or too late decoys hardly work
+        let timing = |time_to_go| flare.seduction(&ir, beam, time_to_go);
+        assert!(timing(2.) > timing(12.) * 2. && timing(2.) > timing(0.2) * 2.);
+    }
+
+    #[test]
+    fn fooled_missile_homes_in_on_the_decoy_and_lets_the_target_get_away() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let mut rng = GameRng::new(3);
+        let mut enemies = Enemies::new(
+            &blueprints,
+            &catalogue(),
+            &terrain(),
+            Point3::origin(),
+            &mut rng.enemies,
+        );
+        let enemy = enemies.map.values_mut().next().unwrap();
+        let missile_types = catalogue();
+        let r60 = missile_types.get(&MissileKind::new("r60")).unwrap();
+        let delta = 1. / 120.;
+        let path = |t: f32| {
+            let start = Point3::new(-300., 0., -1500.);
+            Pose::new(start + vec3(250., 0., 0.) * t, Pose::default().orientation)
+        };
+        let mut shoot = |flares: bool| {
+            enemy.aircraft_mut().place(path(0.));
+            let launcher = Pose::default();
+            let velocity = launcher.front() * 600.;
+            let id = Some(TargetID::Enemy(enemy.id()));
+            let mut missile = Missile::new(0, r60, &launcher, velocity, id, None);
+            let target = path(0.);
+            let release = |id| {
+                let position = target.relative(vec3(0., -1., -4.));
+                Decoy::new(
+                    id,
+                    DecoyKind::Flare,
+                    position,
+                    vec3(250., 0., 0.),
+                    -target.up(),
+                )
+            };
+            let mut decoy = None;
+            if flares {
+                let still = vec3(0., 0., 0.);
+                let chaff = Decoy::new(0, DecoyKind::Chaff, target.position, still, -target.up());
+                assert!((0..50).all(|_| !missile.spoof(&chaff, &target, &mut rng.countermeasures)));
+                decoy = (1..30)
+                    .map(release)
+                    .find(|d| missile.spoof(d, &target, &mut rng.countermeasures));
+                assert!(decoy.is_some() && missile.decoy() == decoy.as_ref().map(|d| d.id()));
+            }
+            for step in 1..(r60.spec().lifetime / delta) as usize {
+                enemy.aircraft_mut().place(path(step as f32 * delta));
+                if let Some(decoy) = &mut decoy {
+                    decoy.update(delta, &mut rng.particles);
+                }
+                let frame = FrameTime::new(delta, (step as f32 * delta) as f64);
+                let pose = enemy.aircraft().pose();
+                if let Some(MissileMessage::Hit(_)) =
+                    missile.update(Some(pose), decoy.as_ref(), false, &frame)
+                {
+                    return true;
+                }
+            }
+            false
+        };
+        assert!(shoot(false));
+        assert!(!shoot(true));
+    }
+
+    #[test]
+    fn enemies_and_the_player_dispense_decoys_against_incoming_missiles() {
+        let mut world = world(5);
+        let dispenser = |world: &World, enemy: Option<EnemyID>| {
+            let aircraft = match enemy {
+                Some(id) => world.enemies().get_by_id(id).unwrap().aircraft(),
+                None => world.player().aircraft(),
+            };
+            aircraft
+                .countermeasures()
+                .dispenser(DecoyKind::Flare)
+                .left()
+        };
+        let (&id, enemy) = world.enemies().map.iter().next().unwrap();
+        let flares = (dispenser(&world, Some(id)), dispenser(&world, None));
+        let mut launcher = *enemy.aircraft().pose();
+        launcher.position -= launcher.front() * 1000.;
+        let velocity = launcher.front() * 600.;
+        let missile_types = catalogue();
+        let r60 = missile_types.get(&MissileKind::new("r60")).unwrap();
+        let missile = Missile::new(0, r60, &launcher, velocity, Some(TargetID::Enemy(id)), None);
+        world.missiles_mut().push(missile);
+        let input = FrameInput {
+            release_flares: true,
+            ..FrameInput::default()
+        };
+        world.update(&input, &FrameTime::new(1. / 120., 1.));
+        assert_eq!(dispenser(&world, Some(id)), flares.0 - 1);
+        assert_eq!(dispenser(&world, None), flares.1 - 1);
+        assert_eq!(world.decoys_mut().len(), 2);
+    }
+
+    #[test]
+    fn aircraft_kinds_are_drawn_at_their_own_size() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let scales: Vec<_> = blueprints
+            .iter()
+            .map(|b| Aircraft::new(b).model().transformation.scale)
+            .collect();
+        let unique = scales.iter().map(|s| s.to_bits()).all_unique();
+        assert!(unique, "{scales:?}");
+    }
+
+    #[test]
+    fn enemy_roles_decide_how_they_fight() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let mut rng = GameRng::new(3);
+        // Guns firing at a target 600 m ahead of an enemy of the given kind
+        let mut fires_at_600m = |kind: &str| {
+            let blueprint = blueprints.get(&AircraftKind::new(kind)).unwrap();
+            let mut enemy = Enemy::new(0, blueprint, &catalogue(), &mut rng.enemies);
+            let pose = Pose::default();
+            enemy.aircraft_mut().place(pose);
+            let target = Pose::new(pose.position + pose.front() * 600., pose.orientation);
+            enemy.fire_guns_at(&target, &FrameTime::new(1. / 120., 1.), &mut rng.guns);
+            (*blueprint.role(), enemy.aircraft().guns().firing)
+        };
+        assert_eq!(fires_at_600m("mig21"), (Role::Fighter, true));
+        assert_eq!(fires_at_600m("su24"), (Role::Bomber, false));
+        assert!(Role::Bomber.launch_interval() > Role::Fighter.launch_interval());
+    }
+
+    #[test]
+    fn enemy_guns_only_fire_at_a_target_in_front_of_the_nose() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let mut rng = GameRng::new(3);
+        let mut enemies = Enemies::new(
+            &blueprints,
+            &catalogue(),
+            &terrain(),
+            Point3::origin(),
+            &mut rng.enemies,
+        );
+        let enemy = enemies.map.values_mut().next().unwrap();
+        let pose = Pose::default();
+        enemy.aircraft_mut().place(pose);
+        let blueprint = blueprints.get(enemy.aircraft().kind()).unwrap();
+        let muzzle = pose.relative(blueprint.gun_mounts()[0]);
+        let behind = Pose::new(muzzle - pose.front() * 300., pose.orientation);
+        enemy.fire_guns_at(&behind, &FrameTime::new(1. / 120., 1.), &mut rng.guns);
+        assert!(!enemy.aircraft().guns().firing);
+        let ahead = Pose::new(muzzle + pose.front() * 300., pose.orientation);
+        enemy.fire_guns_at(&ahead, &FrameTime::new(1. / 120., 2.), &mut rng.guns);
+        assert!(enemy.aircraft().guns().firing);
+        // Each bullet hits once
+        let guns = enemy.aircraft_mut().guns_mut();
+        guns.update(&FrameTime::new(0.3, 2.3));
+        assert_eq!(guns.take_hits(ahead.position, 0.3), 1);
+        assert_eq!(guns.take_hits(ahead.position, 0.3), 0);
+    }
+
+    #[test]
+    fn enemies_launch_missiles_once_locked_and_then_only_after_a_while() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let missile_types = catalogue();
+        let mut rng = GameRng::new(3);
+        let mut enemies = Enemies::new(
+            &blueprints,
+            &missile_types,
+            &terrain(),
+            Point3::origin(),
+            &mut rng.enemies,
+        );
+        let enemy = enemies.map.values_mut().next().unwrap();
+        assert!(enemy.aircraft().missiles().values().sum::<u32>() > 0);
+        let blueprint = blueprints.get(enemy.aircraft().kind()).unwrap();
+        let r60 = missile_types.get(&MissileKind::new("r60")).unwrap();
+        let seeker = r60.spec().seeker.unwrap();
+        let delta = 1. / 120.;
+        let launches: Vec<_> = (0..(30. / delta) as usize)
+            .filter(|step| {
+                let frame = FrameTime::new(delta, *step as f64 * delta as f64);
+                enemy.lock_on(Some(&seeker), &frame)
+            })
+            .collect();
+        assert_eq!(launches.len(), 2);
+        assert!(launches[0] as f32 * delta >= seeker.lock_time);
+        let interval = blueprint.role().launch_interval() as f32;
+        assert!((launches[1] - launches[0]) as f32 * delta >= interval - delta);
+        assert!(!enemy.lock_on(None, &FrameTime::new(delta, 100.)));
+    }
+
+    #[test]
+    fn enemy_fire_wears_the_player_down_until_shot_down() {
+        // Bullets wear the player down
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let blueprint = blueprints
+            .get(&AircraftKind::new(DEFAULT_AIRCRAFT))
+            .unwrap();
+        let health = blueprint.health();
+        let mut player = Player::new(blueprint);
+        assert!(!player.aircraft_mut().hit(BULLET_DAMAGE, Component::Engine));
+        assert!(player.aircraft().damage().health() < health);
+
+        // A missile's blast shoots them down, and the wreck falls before the sortie ends
+        let mut world = world(5);
+        let id = *world.enemies().map.keys().next().unwrap();
+        let target = *world.player().pose();
+        let launcher = Pose::new(
+            target.position + target.front() * -1000.,
+            target.orientation,
+        );
+        let velocity = launcher.front() * 600.;
+        let missile_types = catalogue();
+        let r60 = missile_types.get(&MissileKind::new("r60")).unwrap();
+        let missile = Missile::new(
+            0,
+            r60,
+            &launcher,
+            velocity,
+            Some(TargetID::Player),
+            Some(id),
+        );
+        world.missiles_mut().push(missile);
+        assert_eq!(world.incoming_missiles().count(), 1);
+        let delta = 1. / 120.;
+        let mut events = vec![];
+        for step in 1..(20. / delta) as usize {
+            let frame = FrameTime::new(delta, step as f64 * delta as f64);
+            events.extend(world.update(&FrameInput::default(), &frame));
+            if world.crashed() {
+                break;
+            }
+        }
+        assert!(events.contains(&WorldEvent::ShotDown));
+        assert_eq!(events.last(), Some(&WorldEvent::WreckCrashed));
+        assert_eq!(world.score().shot_down, 1);
+        world.respawn_player();
+        assert_eq!(world.player().aircraft().damage().health(), health);
+    }
+
+    #[test]
+    fn damage_only_degrades_the_component_hit() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let blueprint = blueprints
+            .get(&AircraftKind::new(DEFAULT_AIRCRAFT))
+            .unwrap();
+        let mut aircraft = Aircraft::new(blueprint);
+        let thrust = aircraft.spec().thrust().at(200.);
+        let pitch_rate = aircraft.spec().pitch_rate();
+        let amount = blueprint.health() / 4.;
+        assert!(!aircraft.hit(amount, Component::Engine));
+        assert!(aircraft.spec().thrust().at(200.) < thrust);
+        assert_eq!(aircraft.spec().pitch_rate(), pitch_rate);
+        assert!(!aircraft.hit(amount, Component::ControlSurfaces));
+        assert!(aircraft.spec().pitch_rate() < pitch_rate);
+        assert!(aircraft.spec().pitch_rate() > 0.);
+        // Without a holed fuel tank nothing burns
+        assert!(!aircraft.burn(1.));
+        assert_eq!(aircraft.damage().integrity(), 0.5);
+    }
+
+    #[test]
+    fn holed_fuel_tank_burns_until_the_aircraft_is_lost() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let blueprint = blueprints
+            .get(&AircraftKind::new(DEFAULT_AIRCRAFT))
+            .unwrap();
+        let mut aircraft = Aircraft::new(blueprint);
+        let amount = blueprint.health() / 4.;
+        assert!(!aircraft.hit(amount, Component::FuelTank));
+        let health = aircraft.damage().health();
+        assert!(!aircraft.burn(1.));
+        assert!(aircraft.damage().health() < health);
+        assert!((0..120).any(|_| aircraft.burn(1.)));
+        assert!(aircraft.destroyed());
+        // A wreck can't be destroyed twice
+        assert!(!aircraft.hit(amount, Component::Engine));
+    }
+
+    #[test]
+    fn blast_brings_down_nearby_enemies_whose_wrecks_fall() {
+        let mut world = world(7);
+        // Enemies fly along their paths, so move the paths apart, high above the ground
+        for (n, enemy) in world.enemies_mut().map.values_mut().enumerate() {
+            enemy.rebase(vec3(0., 1500. + 100. * n as f32, 0.));
+        }
+        let ids: Vec<_> = world.enemies().map.keys().copied().collect();
+        let (near, far) = (ids[0], ids[1]);
+        let pose = *world.enemies().get_by_id(near).unwrap().aircraft().pose();
+        let missile_types = catalogue();
+        let r60 = missile_types.get(&MissileKind::new("r60")).unwrap();
+        let velocity = world.enemies().get_by_id(near).unwrap().velocity();
+        world
+            .missiles_mut()
+            .push(Missile::new(0, r60, &pose, velocity, None, None));
+        let delta = 1. / 120.;
+        let events = world.update(&FrameInput::default(), &FrameTime::new(delta, 0.));
+        assert!(events.contains(&WorldEvent::EnemyDestroyed(near)));
+        assert!(world.missiles().is_empty());
+        // One further away is spared
+        let far = world.enemies().get_by_id(far).unwrap().aircraft();
+        assert_eq!(far.damage().integrity(), 1.);
+
+        // The wreck falls for a while before it's removed
+        let altitude = pose.position.y;
+        for step in 1..120 {
+            let frame = FrameTime::new(delta, step as f64 * delta as f64);
+            world.update(&FrameInput::default(), &frame);
+        }
+        let wreck = world.enemies().get_by_id(near).unwrap();
+        assert!(wreck.aircraft().destroyed());
+        assert!(wreck.position().y < altitude);
+        let cleared = (120..(12. / delta) as usize).any(|step| {
+            let frame = FrameTime::new(delta, step as f64 * delta as f64);
+            world.update(&FrameInput::default(), &frame);
+            world.enemies().get_by_id(near).is_none()
+        });
+        assert!(cleared);
+    }
+
+    #[test]
+    fn damaged_enemies_fly_slower() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let blueprint = blueprints
+            .get(&AircraftKind::new(DEFAULT_AIRCRAFT))
+            .unwrap();
+        let mut terrain = terrain();
+        terrain.stream(Point2::origin());
+        let frame = FrameTime::new(1. / 60., 0.);
+        // Distance covered in a frame after a hit on `component`, if any
+        let flown = |component: Option<Component>| {
+            let mut rng = GameRng::new(5);
+            let mut enemy = Enemy::new(0, blueprint, &catalogue(), &mut rng.enemies);
+            if let Some(component) = component {
+                assert!(!enemy.hit(blueprint.health() / 4., component));
+            }
+            let start = enemy.position();
+            enemy.fly(&terrain, &frame, &mut rng.enemies);
+            (
+                enemy.position().distance(start),
+                enemy.velocity().magnitude(),
+            )
+        };
+        let (distance, speed) = flown(None);
+        let (damaged_distance, damaged_speed) = flown(Some(Component::Engine));
+        assert!(damaged_distance < distance);
+        assert!(damaged_speed < speed);
+        // Damaged controls make turns wider but leave the speed alone
+        assert_eq!(flown(Some(Component::ControlSurfaces)).1, speed);
+    }
+
+    #[test]
+    fn enemies_head_for_points_over_the_loaded_terrain() {
+        model::set_headless(true);
+        let mut terrain = Terrain::new(Biome::Desert, 1, 0.);
+        terrain.stream(Point2::origin());
+        let bounds = terrain.bounds();
+        let middle = |range: Range<f32>| {
+            let quarter = (range.end - range.start) / 4.;
+            range.start + quarter..=range.end - quarter
+        };
+        let mut rng = GameRng::new(6);
+        for _ in 0..50 {
+            // Where an enemy which strayed off the terrain heads back to
+            let point = enemy::point_over(&terrain, 0.5, &mut rng.enemies);
+            assert!(middle(bounds.x.clone()).contains(&point.x), "{point}");
+            assert!(middle(bounds.z.clone()).contains(&point.z), "{point}");
+            let ground = terrain.height_at(Point2::new(point.x, point.z));
+            assert!(point.y >= ground + 40., "{point} over {ground}");
+        }
+    }
+
+    #[test]
+    fn waves_show_up_out_of_range_and_hold_off_the_player() {
+        for seed in 1..4 {
+            let mut world = world(seed);
+            let player = world.player().pose().position;
+            assert!(world
+                .enemies()
+                .map
+                .values()
+                .all(|e| e.position().distance(player) > 2000.));
+            let delta = 1. / 120.;
+            for step in 0..(5. / delta) as usize {
+                let frame = FrameTime::new(delta, step as f64 * delta as f64);
+                let events = world.update(&FrameInput::default(), &frame);
+                assert!(!events.contains(&WorldEvent::PlayerHit), "seed {seed}");
+            }
+            assert_eq!(world.player().aircraft().damage().integrity(), 1.);
+        }
+    }
+
+    #[test]
+    fn waves_show_up_clear_of_high_ground() {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        // Ground everywhere above where the wave would show up over the player
+        let terrain = Terrain::new(Biome::Mountains, 2, 600.);
+        let mut rng = GameRng::new(4);
+        let enemies = Enemies::new(
+            &blueprints,
+            &catalogue(),
+            &terrain,
+            Point3::origin(),
+            &mut rng.enemies,
+        );
+        for enemy in enemies.map.values() {
+            let position = enemy.position();
+            let ground = terrain.height_at(Point2::new(position.x, position.z));
+            assert!(position.y > ground + 250., "{} over {ground}", position.y);
+        }
+    }
+
+    fn catalogue() -> MissileCatalogue {
+        MissileCatalogue::load(MISSILE_DIR).unwrap()
+    }
+
+    /// Headless world with the default aircraft over the desert
+    fn world(seed: u64) -> World {
+        model::set_headless(true);
+        let blueprints = Blueprints::load(AIRCRAFT_DIR).unwrap();
+        let kind = AircraftKind::new(DEFAULT_AIRCRAFT);
+        World::new(
+            GameRng::new(seed),
+            blueprints,
+            catalogue(),
+            &kind,
+            Some(Biome::Desert),
+        )
+    }
+
+    /// Pose rolled on its side, pitched up and away from the origin
+    fn banked_pose() -> Pose {
+        let mut pose = Pose::default();
+        pose.roll(90.);
+        pose.pitch(30.);
+        pose.position += vec3(100., 50., -20.);
+        pose
+    }
+
+    /// Terrain well below the origin, so it never gets in the way of a test
+    fn terrain() -> Terrain {
+        Terrain::new(Biome::Desert, 1, -1000.)
+    }
 }